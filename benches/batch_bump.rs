@@ -0,0 +1,26 @@
+// Time to solve all small instances with and without batched activity bumps:
+//
+//     cargo bench --bench batch_bump
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::time::Instant;
+
+
+fn main() {
+    println!("{:12} {:>10} {:>8}", "batch_bump", "conflicts", "time");
+    for &batch_bump in [false, true].iter() {
+        let start = Instant::now();
+        let mut conflicts = 0;
+        for entry in std::fs::read_dir("./tests/cnf").expect("IO Error") {
+            let mut settings = minisat::CoreSettings::default();
+            settings.heur.batch_bump = batch_bump;
+            let mut solver = minisat::CoreSolver::new(settings);
+            dimacs::parse_file(entry.expect("IO Error").path(), &mut solver, false).expect("IO Error");
+            conflicts += match solver.solve_limited(&Budget::new(), &[]) {
+                SolveRes::SAT(_, stats) | SolveRes::UnSAT(stats) => stats.conflicts,
+                SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+            };
+        }
+        println!("{:12} {:>10} {:>7.2}s", batch_bump, conflicts, start.elapsed().as_secs_f64());
+    }
+}
@@ -0,0 +1,50 @@
+// Time to extract a model of a few million variables, best of several runs: through the
+// intermediate 'VarMap', into a reused buffer, and as a packed snapshot:
+//
+//     cargo bench --bench model
+use minisat_rust::sat::formula::{assignment::{Assignment, PackedAssignment}, util, Lit};
+use std::time::Instant;
+
+
+const RUNS: usize = 10;
+const VARS: usize = 4 << 20;
+
+fn best_of<F: FnMut()>(mut f: F) -> f64 {
+    let mut best = f64::INFINITY;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    best
+}
+
+fn main() {
+    let mut assigns = Assignment::new();
+    for i in 0..VARS {
+        let v = assigns.new_var();
+        assigns.assign_lit(v.sign_lit(i % 3 == 0), None);
+    }
+
+    let via_map = best_of(|| {
+        let model: Vec<Lit> = util::extract_model(&assigns).iter().map(|(v, s)| v.sign_lit(!*s)).collect();
+        assert_eq!(model.len(), VARS);
+    });
+
+    let mut model = Vec::new();
+    let into = best_of(|| {
+        assigns.extract_model_into(&mut model);
+        assert_eq!(model.len(), VARS);
+    });
+
+    let mut packed = PackedAssignment::new();
+    let pack = best_of(|| {
+        assigns.pack_into(&mut packed);
+        assert_eq!(packed.len(), VARS);
+    });
+
+    println!("{:28} {:>9}", "extraction", "time");
+    println!("{:28} {:>7.1}ms", "extract_model (VarMap)", via_map * 1000.0);
+    println!("{:28} {:>7.1}ms", "extract_model_into", into * 1000.0);
+    println!("{:28} {:>7.1}ms", "pack_into", pack * 1000.0);
+}
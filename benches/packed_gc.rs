@@ -0,0 +1,43 @@
+// Propagation throughput with and without packed garbage collection, on the same number of
+// conflicts. Clauses only move, so the work done is the same and only the time differs:
+//
+//     cargo bench --bench packed_gc
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver, Stats};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::time::Instant;
+
+
+const CONFLICTS: u64 = 20000;
+const INSTANCES: [&str; 4] = [
+    "./tests/cnf/uf250-014.cnf.gz",
+    "./tests/cnf-hard/uuf250-01.cnf.gz",
+    "./tests/cnf-hard/uuf250-02.cnf.gz",
+    "./tests/cnf-hard/uuf250-03.cnf.gz",
+];
+
+fn run(path: &str, packed_gc: bool) -> (Stats, f64) {
+    let mut settings = minisat::CoreSettings::default();
+    settings.core.packed_gc = packed_gc;
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+
+    let start = Instant::now();
+    let stats =
+        match solver.solve_limited(&Budget::new().conflicts(CONFLICTS), &[]) {
+            SolveRes::UnSAT(stats) | SolveRes::SAT(_, stats) => stats,
+            SolveRes::Interrupted(_, solver) | SolveRes::Invalid(_, solver) => solver.stats(),
+        };
+    (stats, start.elapsed().as_secs_f64())
+}
+
+fn main() {
+    println!("{:36} {:>8} {:>12} {:>12} {:>8}", "instance", "gc runs", "propagations", "props/sec", "time");
+    for path in INSTANCES.iter() {
+        for &packed_gc in [false, true].iter() {
+            let (stats, time) = run(path, packed_gc);
+            println!("{:36} {:>8} {:>12} {:>12.0} {:>7.2}s{}",
+                     path, stats.gc_runs, stats.propagations, stats.propagations as f64 / time, time,
+                     if packed_gc { "  (packed)" } else { "" });
+        }
+    }
+}
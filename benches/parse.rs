@@ -0,0 +1,34 @@
+// Time to read large instances into a solver, best of several runs:
+//
+//     cargo bench --bench parse
+use minisat_rust::sat::{dimacs, minisat, Solver};
+use std::time::Instant;
+
+
+const RUNS: usize = 5;
+const INSTANCES: [&str; 3] = [
+    "./tests/cnf-hard/stric-bmc-ibm-10.cnf.gz",
+    "./tests/cnf-hard/stric-bmc-ibm-12.cnf.gz",
+    "./tests/cnf-hard/ewddr2-10-by-5-1.cnf.gz",
+];
+
+fn run(path: &str) -> (usize, f64) {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let start = Instant::now();
+    dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+    (solver.n_clauses(), start.elapsed().as_secs_f64())
+}
+
+fn main() {
+    println!("{:44} {:>9} {:>8}", "instance", "clauses", "time");
+    for path in INSTANCES.iter() {
+        let mut best = f64::INFINITY;
+        let mut clauses = 0;
+        for _ in 0..RUNS {
+            let (n, time) = run(path);
+            clauses = n;
+            best = best.min(time);
+        }
+        println!("{:44} {:>9} {:>7.3}s", path, clauses, best);
+    }
+}
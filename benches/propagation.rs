@@ -0,0 +1,22 @@
+// Propagation throughput over all small instances, solved to the end:
+//
+//     cargo bench --bench propagation
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::time::Instant;
+
+
+fn main() {
+    let start = Instant::now();
+    let mut propagations = 0;
+    for entry in std::fs::read_dir("./tests/cnf").expect("IO Error") {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse_file(entry.expect("IO Error").path(), &mut solver, false).expect("IO Error");
+        propagations += match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(_, stats) | SolveRes::UnSAT(stats) => stats.propagations,
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        };
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!("{} propagations in {:.3} s ({:.0} props/sec)", propagations, elapsed, propagations as f64 / elapsed);
+}
@@ -0,0 +1,43 @@
+// Work done on the same number of conflicts with and without trail saving. Restarts that keep
+// decisions unassign fewer literals, which then don't have to be propagated again:
+//
+//     cargo bench --bench trail_saving
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver, Stats};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::time::Instant;
+
+
+const CONFLICTS: u64 = 20000;
+const INSTANCES: [&str; 4] = [
+    "./tests/cnf/uf250-014.cnf.gz",
+    "./tests/cnf-hard/uuf250-01.cnf.gz",
+    "./tests/cnf-hard/uuf250-02.cnf.gz",
+    "./tests/cnf-hard/uuf250-03.cnf.gz",
+];
+
+fn run(path: &str, trail_saving: bool) -> (Stats, f64) {
+    let mut settings = minisat::CoreSettings::default();
+    settings.core.trail_saving = trail_saving;
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+
+    let start = Instant::now();
+    let stats =
+        match solver.solve_limited(&Budget::new().conflicts(CONFLICTS), &[]) {
+            SolveRes::UnSAT(stats) | SolveRes::SAT(_, stats) => stats,
+            SolveRes::Interrupted(_, solver) | SolveRes::Invalid(_, solver) => solver.stats(),
+        };
+    (stats, start.elapsed().as_secs_f64())
+}
+
+fn main() {
+    println!("{:36} {:>9} {:>12} {:>14} {:>8}", "instance", "restarts", "propagations", "unassigned", "time");
+    for path in INSTANCES.iter() {
+        for &trail_saving in [false, true].iter() {
+            let (stats, time) = run(path, trail_saving);
+            println!("{:36} {:>9} {:>12} {:>14} {:>7.2}s{}",
+                     path, stats.restarts, stats.propagations, stats.restart_unassigns, time,
+                     if trail_saving { "  (trail saving)" } else { "" });
+        }
+    }
+}
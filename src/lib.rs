@@ -1,74 +1,132 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 #[macro_use]
 extern crate log;
-use time;
-
+extern crate alloc;
+#[cfg(feature = "std")]
 use std::{fs, io, path};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "std")]
 use crate::sat::*;
+#[cfg(feature = "std")]
 use crate::sat::minisat::budget::Budget;
 
 pub mod sat;
+#[cfg(feature = "std")]
 pub(crate) mod util;
 
 
+#[cfg(feature = "std")]
 pub enum SolverOptions {
     Core(minisat::CoreSettings),
     Simp(minisat::SimpSettings),
 }
 
+#[cfg(feature = "std")]
 pub struct MainOptions {
-    pub strict: bool,
+    pub strict: bool, // Check the number of clauses declared in the DIMACS header.
+    pub header_vars: dimacs::HeaderMismatch, // What to do about variables above the declared number.
     pub pre: bool,
     pub solve: bool,
+    pub analyze: bool, // Only report the shape of the formula: neither preprocess nor solve.
     pub in_path: path::PathBuf,
     pub out_path: Option<path::PathBuf>,
-    pub dimacs_path: Option<path::PathBuf>,
+    pub dimacs_path: Option<path::PathBuf>, // Write the formula left after preprocessing to this file.
+    pub compact: bool, // Renumber variables of the 'dimacs_path' formula to 1..k, with the renaming next to it.
+    pub trace_path: Option<path::PathBuf>,  // Record the search to this file.
+    pub replay_path: Option<path::PathBuf>, // Check the search against a trace recorded earlier.
+}
+
+// Wall-clock time spent in each phase of 'solve'. Phases that were skipped take zero time.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timings {
+    pub parse: Duration,
+    pub preprocess: Duration,
+    pub search: Duration,
+}
+
+#[cfg(feature = "std")]
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.preprocess + self.search
+    }
 }
 
 
-pub fn solve(main_opts: MainOptions, solver_opts: SolverOptions) -> io::Result<()> {
+#[cfg(feature = "std")]
+pub fn solve(main_opts: MainOptions, solver_opts: SolverOptions) -> io::Result<Timings> {
     match solver_opts {
         SolverOptions::Core(opts) => {
-            let solver = minisat::CoreSolver::new(opts);
-            solve_with(solver, main_opts)
+            let mut solver = minisat::CoreSolver::new(opts);
+            let mut traces = Vec::new();
+            if let Some(ref path) = main_opts.trace_path {
+                traces.push(solver.record_trace(io::BufWriter::new(fs::File::create(path)?)));
+            }
+            if let Some(ref path) = main_opts.replay_path {
+                traces.push(solver.replay_trace(io::BufReader::new(fs::File::open(path)?)));
+            }
+            let timings = solve_with(solver, main_opts)?;
+            finish_traces(&traces)?;
+            Ok(timings)
         }
 
         SolverOptions::Simp(opts) => {
             let mut solver = minisat::SimpSolver::new(opts);
+            let mut traces = Vec::new();
+            if let Some(ref path) = main_opts.trace_path {
+                traces.push(solver.record_trace(io::BufWriter::new(fs::File::create(path)?)));
+            }
+            if let Some(ref path) = main_opts.replay_path {
+                traces.push(solver.replay_trace(io::BufReader::new(fs::File::open(path)?)));
+            }
             if !main_opts.pre {
                 solver.preprocess(&Budget::new());
             }
-            solve_with(solver, main_opts)
+            let timings = solve_with(solver, main_opts)?;
+            finish_traces(&traces)?;
+            Ok(timings)
         }
     }
 }
 
+// The solver is gone after 'solve_with', so traces are finished through their handles. Reports IO
+// errors of a recorded trace and events of a replayed one the search didn't get to.
+#[cfg(feature = "std")]
+fn finish_traces(traces: &[minisat::Trace]) -> io::Result<()> {
+    traces.iter().try_for_each(|trace| trace.finish())
+}
+
+
+#[cfg(feature = "std")]
+pub fn solve_with<S: Solver + ClauseSource>(mut solver: S, options: MainOptions) -> io::Result<Timings> {
+    let mut timings = Timings::default();
+    if options.analyze {
+        analyze(&options.in_path)?;
+        return Ok(timings);
+    }
 
-pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<()> {
     info!("============================[ Problem Statistics ]=============================");
     info!("|                                                                             |");
 
-    let initial_time = time::precise_time_s();
-    let backward_subst = dimacs::parse_file(&options.in_path, &mut solver, options.strict)?;
-    let parse_end_time = time::precise_time_s();
+    let initial_time = Instant::now();
+    let backward_subst = dimacs::parse_file_with(&options.in_path, &mut solver, options.strict, options.header_vars)?;
+    timings.parse = initial_time.elapsed();
 
     info!("|  Number of variables:  {:12}                                         |", solver.n_vars());
     info!("|  Number of clauses:    {:12}                                         |", solver.n_clauses());
 
-    {
-        let parse_time = parse_end_time - initial_time;
-        info!("|  Parse time:           {:12.2} s                                       |", parse_time);
-    }
+    info!("|  Parse time:           {:12.2} s                                       |", timings.parse.as_secs_f64());
 
     let mut budget = Budget::new();
     budget.off();
 
+    let start = Instant::now();
     let elim_res = solver.preprocess(&budget);
+    timings.preprocess = start.elapsed();
 
-    {
-        let simplify_time = time::precise_time_s() - parse_end_time;
-        info!("|  Simplification time:  {:12.2} s                                       |", simplify_time);
-    }
+    info!("|  Simplification time:  {:12.2} s                                       |", timings.preprocess.as_secs_f64());
 
     info!("|                                                                             |");
 
@@ -79,37 +137,36 @@ pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<
     } else {
         let result =
             if options.solve {
-                solver.solve_limited(&budget, &[])
+                let start = Instant::now();
+                let result = solver.solve_limited(&budget, &[]);
+                timings.search = start.elapsed();
+                result
             } else {
                 info!("===============================================================================");
                 SolveRes::Interrupted(0.0, solver)
             };
 
-        //            if let TotalResult::Interrupted = result {
-        //                if let Some(path) = options.dimacs_path {
-        //                    let mut out = try!(fs::File::create(path));
-        //                    try!(dimacs::write(&mut out, &solver));
-        //                }
-        //            }
+        if let (SolveRes::Interrupted(_, solver), Some(path)) = (&result, &options.dimacs_path) {
+            dimacs::write_file(path, solver, &backward_subst, options.compact)?;
+        }
 
         result
     };
 
-    let cpu_time = time::precise_time_s() - initial_time;
     let mem_used = util::mem_used_peak();
     match result {
         SolveRes::UnSAT(ref stats) => {
-            print_stats(stats, cpu_time, mem_used);
+            print_stats(stats, initial_time, mem_used);
             println!("UNSATISFIABLE");
         }
 
-        SolveRes::Interrupted(_, ref s) => {
-            print_stats(&s.stats(), cpu_time, mem_used);
+        SolveRes::Interrupted(_, ref s) | SolveRes::Invalid(_, ref s) => {
+            print_stats(&s.stats(), initial_time, mem_used);
             println!("INDETERMINATE");
         }
 
         SolveRes::SAT(ref model, ref stats) => {
-            print_stats(stats, cpu_time, mem_used);
+            print_stats(stats, initial_time, mem_used);
             println!("SATISFIABLE");
             assert!(
                 dimacs::validate_model_file(&options.in_path, &backward_subst, &model)?,
@@ -119,13 +176,158 @@ pub fn solve_with<S: Solver>(mut solver: S, options: MainOptions) -> io::Result<
     }
 
     if let Some(path) = options.out_path {
-        dimacs::write_result(fs::File::create(path)?, result, &backward_subst)?;
+        dimacs::write_result_file(path, result, &backward_subst)?;
+    }
+
+    Ok(timings)
+}
+
+#[cfg(feature = "std")]
+pub fn analyze<P: AsRef<path::Path>>(path: P) -> io::Result<dimacs::FormulaStats> {
+    let stats = dimacs::analyze_file(path)?;
+
+    info!("============================[ Formula Statistics ]=============================");
+    info!("|                                                                             |");
+    info!("|  Declared variables:   {:12}                                         |", stats.header_vars);
+    info!("|  Declared clauses:     {:12}                                         |", stats.header_clauses);
+    info!("|  Occurring variables:  {:12}                                         |", stats.vars);
+    info!("|  Clauses:              {:12}                                         |", stats.clauses);
+    info!("|                                                                             |");
+    info!("|  Clause length histogram:                                                   |");
+    print_histogram(&stats.clause_lengths);
+    info!("|  Literal occurrence histogram:                                              |");
+    print_histogram(&stats.occurrences);
+    info!("===============================================================================");
+
+    Ok(stats)
+}
+
+// Line-based interactive protocol over an incremental solver, one command per line:
+//   declare <n>        make variables 1..n available
+//   clause <lits> 0    add a clause of declared variables, in DIMACS notation
+//   assume <lit>       assume a literal for the next 'check' only
+//   check              solve, answering 'sat', 'unsat' or 'unknown'
+//   model              print the model found by the latest 'check', terminated by 0
+//   reset              start over with no variables and no clauses
+// Every other command is answered with 'ok' or with 'error: ...' if it is rejected. Empty lines
+// and comment lines starting with 'c ' are skipped.
+#[cfg(feature = "std")]
+pub fn repl<R: io::BufRead, W: io::Write>(settings: minisat::CoreSettings, input: R, mut output: W) -> io::Result<()> {
+    let mut solver = minisat::CoreSolver::new(settings.clone());
+    let mut backward_subst = formula::VarMap::new();
+    let mut assumptions = Vec::new();
+    let mut model = None;
+
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            None => continue,
+            Some("c") => continue,
+            Some(command) => command,
+        };
+        let args: Result<Vec<i32>, _> = words.map(|word| word.parse::<i32>()).collect();
+        let args = match args {
+            Ok(args) => args,
+            Err(err) => {
+                writeln!(output, "error: {}", err)?;
+                continue;
+            }
+        };
+
+        let lit = |solver: &minisat::CoreSolver, lit_id: i32| {
+            let index = lit_id.unsigned_abs() as usize;
+            if lit_id == 0 || index > solver.n_vars() {
+                Err(format!("undeclared variable {}", lit_id))
+            } else {
+                Ok(formula::Var::from_index(index - 1).sign_lit(lit_id < 0))
+            }
+        };
+
+        let reply = match (command, &args[..]) {
+            ("declare", &[n]) if n >= 0 => {
+                while solver.n_vars() < n as usize {
+                    let v = solver.new_var(None, true);
+                    backward_subst.insert(&v, solver.n_vars() as i32);
+                }
+                Ok(None)
+            }
+
+            ("clause", &[ref lits @ .., 0]) => {
+                lits.iter().map(|&lit_id| lit(&solver, lit_id)).collect::<Result<Vec<_>, _>>()
+                    .map(|lits| {
+                        solver.add_clause(&lits);
+                        None
+                    })
+            }
+
+            ("assume", &[lit_id]) => {
+                lit(&solver, lit_id).map(|lit| {
+                    assumptions.push(lit);
+                    None
+                })
+            }
+
+            ("check", &[]) => {
+                let res = solver.solve_under(&Budget::new(), &assumptions);
+                assumptions.clear();
+                model = None;
+                match res {
+                    minisat::StepRes::SAT(lits) => {
+                        model = Some(lits);
+                        Ok(Some("sat".to_string()))
+                    }
+                    minisat::StepRes::UnSAT | minisat::StepRes::AssumpsConfl(_) => Ok(Some("unsat".to_string())),
+                    minisat::StepRes::Interrupted(_) => Ok(Some("unknown".to_string())),
+                }
+            }
+
+            ("model", &[]) => {
+                match model {
+                    Some(ref lits) => {
+                        let mut ids: Vec<i32> = lits.iter().map(|&lit| dimacs::DimacsLit(lit, &backward_subst).id()).collect();
+                        ids.sort_by_key(|id| id.abs());
+                        let ids: Vec<String> = ids.iter().chain(Some(&0)).map(|id| id.to_string()).collect();
+                        Ok(Some(ids.join(" ")))
+                    }
+                    None => Err("no model, the latest 'check' was not 'sat'".to_string()),
+                }
+            }
+
+            ("reset", &[]) => {
+                solver = minisat::CoreSolver::new(settings.clone());
+                backward_subst = formula::VarMap::new();
+                assumptions.clear();
+                model = None;
+                Ok(None)
+            }
+
+            _ => Err(format!("unexpected command '{}'", line.trim())),
+        };
+
+        match reply {
+            Ok(None) => writeln!(output, "ok")?,
+            Ok(Some(reply)) => writeln!(output, "{}", reply)?,
+            Err(err) => writeln!(output, "error: {}", err)?,
+        }
+        output.flush()?;
     }
 
     Ok(())
 }
 
-fn print_stats(stats: &Stats, cpu_time: f64, mem_used: Option<usize>) {
+#[cfg(feature = "std")]
+fn print_histogram(histogram: &[usize]) {
+    for (k, &n) in histogram.iter().enumerate() {
+        if n > 0 {
+            info!("|  {:20}: {:12}                                         |", k, n);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn print_stats(stats: &Stats, initial_time: Instant, mem_used: Option<usize>) {
+    let cpu_time = cpu_time(initial_time);
     info!("restarts              : {:<12}", stats.restarts);
 
     {
@@ -149,9 +351,37 @@ fn print_stats(stats: &Stats, cpu_time: f64, mem_used: Option<usize>) {
         info!("conflict literals     : {:<12}   ({:4.2} % deleted)", stats.tot_literals, del_percent);
     }
 
+    info!("longest clause        : {:<12}", stats.max_clause_len);
+
     if let Some(mem_used) = mem_used {
         info!("Memory used           : {:.2} MB", mem_used as f32 / 1024f32);
     }
     info!("CPU time              : {} s", cpu_time);
     info!("");
 }
+
+// Seconds reported as CPU time, i.e. wall-clock time since 'initial_time':
+#[cfg(feature = "std")]
+fn cpu_time(initial_time: Instant) -> f64 {
+    initial_time.elapsed().as_secs_f64()
+}
+
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_cpu_time() {
+        let initial_time = Instant::now();
+        thread::sleep(Duration::from_millis(1));
+        let mut prev = cpu_time(initial_time);
+        assert!(prev > 0.0);
+        for _ in 0..100 {
+            let t = cpu_time(initial_time);
+            assert!(t >= prev);
+            prev = t;
+        }
+    }
+}
@@ -5,56 +5,32 @@ use env_logger;
 use log;
 use minisat_rust;
 
+use std::env;
 use std::path;
+use std::process;
+use std::str;
+use std::io;
 use std::io::Write;
-use minisat_rust::sat::minisat::{self, CCMinMode, PhaseSaving};
+use minisat_rust::sat::dimacs;
+use minisat_rust::sat::minisat::settings::SettingsBuilder;
+
+
+const LS012: [&str; 3] = ["0", "1", "2"];
+const LS0123: [&str; 4] = ["0", "1", "2", "3"];
+
+// Core options taking a value, and flags (some with a 'no-' counterpart):
+const CORE_VALUES: [&str; 13] = [
+    "var-decay", "cla-decay", "rnd-freq", "rnd-seed", "ccmin-mode", "phase-saving", "rfirst", "rinc",
+    "gc-frac", "lbd-cutoff", "protect-learnts", "min-learnts", "shuffle-lits",
+];
+const CORE_FLAGS: [&str; 9] = [
+    "rnd-init", "deterministic", "luby", "rcheck", "trail-saving", "unlock-before-reduce", "simplify-on-add",
+    "adaptive-gc", "detect-tractable",
+];
 
 
 fn main() {
-    let ls012 = ["0", "1", "2"];
-    let matches =
-        clap::App::new("minisat-rust")
-        .version(&crate_version!()[..])
-        .about("Minisat reimplementation in Rust")
-
-        .arg(clap::Arg::with_name("verb").long("verb").takes_value(true).possible_values(&ls012).help("Verbosity level (0=silent, 1=some, 2=more)"))
-        .arg(clap::Arg::with_name("core").long("core").help("Use core solver"))
-        .arg(clap::Arg::with_name("strict").long("strict").help("Validate DIMACS header during parsing"))
-        .arg(clap::Arg::with_name("pre").long("pre").help("Completely turn on/off any preprocessing"))
-        .arg(clap::Arg::with_name("no-pre").long("no-pre").conflicts_with("pre"))
-        .arg(clap::Arg::with_name("solve").long("solve").help("Completely turn on/off solving after preprocessing"))
-        .arg(clap::Arg::with_name("no-solve").long("no-solve").conflicts_with("solve"))
-        .arg(clap::Arg::with_name("dimacs").long("dimacs").takes_value(true).requires("no-solve").help("If given, stop after preprocessing and write the result to this file"))
-        .arg(clap::Arg::with_name("input").required(true))
-        .arg(clap::Arg::with_name("output").required(false))
-
-        .arg(clap::Arg::with_name("var-decay").long("var-decay").takes_value(true).help("The variable activity decay factor"))
-        .arg(clap::Arg::with_name("cla-decay").long("cla-decay").takes_value(true).help("The clause activity decay factor"))
-        .arg(clap::Arg::with_name("rnd-freq").long("rnd-freq").takes_value(true).help("The frequency with which the decision heuristic tries to choose a random variable"))
-        .arg(clap::Arg::with_name("rnd-seed").long("rnd-seed").takes_value(true).help("Used by the random variable selection"))
-        .arg(clap::Arg::with_name("ccmin-mode").long("ccmin-mode").takes_value(true).possible_values(&ls012).help("Controls conflict clause minimization (0=none, 1=basic, 2=deep)"))
-        .arg(clap::Arg::with_name("phase-saving").long("phase-saving").takes_value(true).possible_values(&ls012).help("Controls the level of phase saving (0=none, 1=limited, 2=full)"))
-        .arg(clap::Arg::with_name("rnd-init").long("rnd-init").help("Randomize the initial activity"))
-        .arg(clap::Arg::with_name("no-rnd-init").long("no-rnd-init").conflicts_with("rnd-init"))
-        .arg(clap::Arg::with_name("luby").long("luby").help("Use the Luby restart sequence"))
-        .arg(clap::Arg::with_name("no-luby").long("no-luby").conflicts_with("luby"))
-        .arg(clap::Arg::with_name("rfirst").long("rfirst").takes_value(true).help("The base restart interval"))
-        .arg(clap::Arg::with_name("rinc").long("rinc").takes_value(true).help("Restart interval increase factor"))
-        .arg(clap::Arg::with_name("gc-frac").long("gc-frac").takes_value(true).help("The fraction of wasted memory allowed before a garbage collection is triggered"))
-        .arg(clap::Arg::with_name("min-learnts").long("min-learnts").takes_value(true).help("Minimum learnt clause limit"))
-        .arg(clap::Arg::with_name("rcheck").long("rcheck").help("Check if a clause is already implied. (costly)"))
-        .arg(clap::Arg::with_name("no-rcheck").long("no-rcheck").conflicts_with("rcheck"))
-
-        .arg(clap::Arg::with_name("asymm").long("asymm").conflicts_with("core").help("Shrink clauses by asymmetric branching"))
-        .arg(clap::Arg::with_name("no-asymm").long("no-asymm").conflicts_with("asymm").conflicts_with("core"))
-        .arg(clap::Arg::with_name("elim").long("elim").conflicts_with("core").help("Perform variable elimination"))
-        .arg(clap::Arg::with_name("no-elim").long("no-elim").conflicts_with("elim").conflicts_with("core"))
-        .arg(clap::Arg::with_name("grow").long("grow").takes_value(true).conflicts_with("core").help("Allow a variable elimination step to grow by a number of clauses"))
-        .arg(clap::Arg::with_name("cl-lim").long("cl-lim").takes_value(true).conflicts_with("core").help("Variables are not eliminated if it produces a resolvent with a length above this limit. -1 means no limit"))
-        .arg(clap::Arg::with_name("sub-lim").long("sub-lim").takes_value(true).conflicts_with("core").help("Do not check if subsumption against a clause larger than this. -1 means no limit."))
-        .arg(clap::Arg::with_name("simp-gc-frac").long("simp-gc-frac").takes_value(true).conflicts_with("core").help("The fraction of wasted memory allowed before a garbage collection is triggered during simplification."))
-
-        .get_matches();
+    let matches = cli().get_matches();
 
     {
         let mut builder = env_logger::Builder::new();
@@ -70,213 +46,237 @@ fn main() {
                 })
                 .unwrap_or(log::LevelFilter::Info),
         );
+        // Per-subsystem filters ("minisat::search", "minisat::simp", "minisat::gc") on top of verbosity:
+        if let Ok(filters) = env::var("RUST_LOG") {
+            builder.parse_filters(&filters);
+        }
         builder.init();
     }
 
+    if matches.is_present("repl") {
+        let settings = match core_settings(&matches).and_then(|b| b.core().map_err(|err| err.to_string())) {
+            Ok(settings) => settings,
+            Err(err) => {
+                eprintln!("ERROR! {}", err);
+                process::exit(1);
+            }
+        };
+
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        minisat_rust::repl(settings, stdin.lock(), stdout.lock()).expect("IO Error");
+        return;
+    }
+
+    let analyze = matches.is_present("analyze");
     let main = minisat_rust::MainOptions {
         strict: matches.is_present("strict"),
-        pre: !matches.is_present("no-pre"),
-        solve: !matches.is_present("no-solve"),
+        header_vars: match matches.value_of("header-vars") {
+            Some("error") => dimacs::HeaderMismatch::Error,
+            Some("warn") => dimacs::HeaderMismatch::Warn,
+            Some(_) => dimacs::HeaderMismatch::Ignore,
+            None => dimacs::HeaderMismatch::validating(matches.is_present("strict")),
+        },
+        pre: !matches.is_present("no-pre") && !analyze,
+        solve: !matches.is_present("no-solve") && !analyze,
+        analyze,
         in_path: path::PathBuf::from(matches.value_of("input").unwrap()),
         out_path: matches.value_of("output").map(|x| path::PathBuf::from(x)),
         dimacs_path: matches.value_of("dimacs").map(|x| path::PathBuf::from(x)),
+        compact: matches.is_present("compact"),
+        trace_path: matches.value_of("trace").map(|x| path::PathBuf::from(x)),
+        replay_path: matches.value_of("replay").map(|x| path::PathBuf::from(x)),
     };
 
     let solver = {
-        let core_options = {
-            let mut s = minisat::CoreSettings::default();
-
-            for &x in matches
-                .value_of("var-decay")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0.0 < x && x < 1.0 {
-                    s.heur.var_decay = x;
-                }
+        let options = core_settings(&matches).and_then(|builder| {
+            if matches.is_present("core") {
+                builder.core().map(minisat_rust::SolverOptions::Core)
+            } else {
+                simp_settings(&matches, builder)?.simp().map(minisat_rust::SolverOptions::Simp)
             }
-
-            for &x in matches
-                .value_of("cla-decay")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0.0 < x && x < 1.0 {
-                    s.db.clause_decay = x;
-                }
+            .map_err(|err| err.to_string())
+        });
+
+        match options {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!("ERROR! {}", err);
+                process::exit(1);
             }
+        }
+    };
 
-            for &x in matches
-                .value_of("rnd-freq")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0.0 <= x && x <= 1.0 {
-                    s.heur.random_var_freq = x;
-                }
-            }
+    minisat_rust::solve(main, solver).expect("IO Error");
+}
 
-            for &x in matches
-                .value_of("rnd-seed")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0.0 < x {
-                    s.heur.random_seed = x;
-                }
-            }
 
-            for &x in matches.value_of("ccmin-mode").iter() {
-                match x {
-                    "0" => {
-                        s.ccmin_mode = CCMinMode::None;
-                    }
-                    "1" => {
-                        s.ccmin_mode = CCMinMode::Basic;
-                    }
-                    "2" => {
-                        s.ccmin_mode = CCMinMode::Deep;
-                    }
-                    _ => {}
-                }
-            }
+fn cli() -> clap::App<'static, 'static> {
+    clap::App::new("minisat-rust")
+    .version(&crate_version!()[..])
+    .about("Minisat reimplementation in Rust")
+
+    .arg(clap::Arg::with_name("verb").long("verb").takes_value(true).possible_values(&LS012).help("Verbosity level (0=silent, 1=some, 2=more)"))
+    .arg(clap::Arg::with_name("core").long("core").help("Use core solver"))
+    .arg(clap::Arg::with_name("strict").long("strict").help("Validate DIMACS header during parsing"))
+    .arg(clap::Arg::with_name("header-vars").long("header-vars").takes_value(true).possible_values(&["error", "warn", "ignore"]).help("What to do about variables above the DIMACS header count (default: error with --strict, ignore otherwise)"))
+    .arg(clap::Arg::with_name("pre").long("pre").help("Completely turn on/off any preprocessing"))
+    .arg(clap::Arg::with_name("no-pre").long("no-pre").conflicts_with("pre"))
+    .arg(clap::Arg::with_name("solve").long("solve").help("Completely turn on/off solving after preprocessing"))
+    .arg(clap::Arg::with_name("no-solve").long("no-solve").conflicts_with("solve"))
+    .arg(clap::Arg::with_name("analyze").long("analyze").alias("dry-run").help("Only parse the input and report formula statistics"))
+    .arg(clap::Arg::with_name("dimacs").long("dimacs").takes_value(true).requires("no-solve").help("If given, stop after preprocessing and write the result to this file"))
+    .arg(clap::Arg::with_name("compact").long("compact").requires("dimacs").help("Renumber variables of the written formula to 1..k and write the original ids to the same path with '.map' appended"))
+    .arg(clap::Arg::with_name("trace").long("trace").takes_value(true).help("Record decisions, conflicts, restarts and garbage collections to this file"))
+    .arg(clap::Arg::with_name("replay").long("replay").takes_value(true).conflicts_with("trace").help("Check that the search reproduces a trace recorded with --trace"))
+    .arg(clap::Arg::with_name("repl").long("repl").conflicts_with("input").help("Read commands from standard input and answer them, keeping the solver between queries"))
+    .arg(clap::Arg::with_name("input").required_unless("repl"))
+    .arg(clap::Arg::with_name("output").required(false))
+
+    .arg(clap::Arg::with_name("var-decay").long("var-decay").takes_value(true).help("The variable activity decay factor"))
+    .arg(clap::Arg::with_name("cla-decay").long("cla-decay").takes_value(true).help("The clause activity decay factor"))
+    .arg(clap::Arg::with_name("rnd-freq").long("rnd-freq").takes_value(true).help("The frequency with which the decision heuristic tries to choose a random variable"))
+    .arg(clap::Arg::with_name("rnd-seed").long("rnd-seed").takes_value(true).help("Used by the random variable selection"))
+    .arg(clap::Arg::with_name("ccmin-mode").long("ccmin-mode").takes_value(true).possible_values(&LS0123).help("Controls conflict clause minimization (0=none, 1=basic, 2=deep, 3=adaptive)"))
+    .arg(clap::Arg::with_name("phase-saving").long("phase-saving").takes_value(true).possible_values(&LS012).help("Controls the level of phase saving (0=none, 1=limited, 2=full)"))
+    .arg(clap::Arg::with_name("rnd-init").long("rnd-init").help("Randomize the initial activity"))
+    .arg(clap::Arg::with_name("no-rnd-init").long("no-rnd-init").conflicts_with("rnd-init"))
+    .arg(clap::Arg::with_name("deterministic").long("deterministic").help("Use fixed-point activities and integer random numbers for the same search on every platform"))
+    .arg(clap::Arg::with_name("luby").long("luby").help("Use the Luby restart sequence"))
+    .arg(clap::Arg::with_name("no-luby").long("no-luby").conflicts_with("luby"))
+    .arg(clap::Arg::with_name("rfirst").long("rfirst").takes_value(true).help("The base restart interval"))
+    .arg(clap::Arg::with_name("rinc").long("rinc").takes_value(true).help("Restart interval increase factor"))
+    .arg(clap::Arg::with_name("gc-frac").long("gc-frac").takes_value(true).help("The fraction of wasted memory allowed before a garbage collection is triggered"))
+    .arg(clap::Arg::with_name("adaptive-gc").long("adaptive-gc").help("Adjust the garbage collection trigger to the memory reclaimed"))
+    .arg(clap::Arg::with_name("protect-learnts").long("protect-learnts").takes_value(true).help("Never remove this many earliest learnt clauses"))
+    .arg(clap::Arg::with_name("lbd-cutoff").long("lbd-cutoff").takes_value(true).help("Never remove learnt clauses with LBD not above this (default 2; 0 = only binary ones)"))
+    .arg(clap::Arg::with_name("min-learnts").long("min-learnts").takes_value(true).help("Minimum learnt clause limit"))
+    .arg(clap::Arg::with_name("rcheck").long("rcheck").help("Check if a clause is already implied. (costly)"))
+    .arg(clap::Arg::with_name("no-rcheck").long("no-rcheck").conflicts_with("rcheck"))
+    .arg(clap::Arg::with_name("simplify-on-add").long("simplify-on-add").help("Clean clauses read so far whenever a new unit is found"))
+    .arg(clap::Arg::with_name("shuffle-lits").long("shuffle-lits").takes_value(true).help("Permute literals of every clause randomly with this seed"))
+    .arg(clap::Arg::with_name("trail-saving").long("trail-saving").help("Keep decisions that would be taken again at restarts"))
+    .arg(clap::Arg::with_name("no-trail-saving").long("no-trail-saving").conflicts_with("trail-saving"))
+    .arg(clap::Arg::with_name("unlock-before-reduce").long("unlock-before-reduce").help("Restart before reducing learnt clauses if many of them are locked as reasons"))
+    .arg(clap::Arg::with_name("detect-tractable").long("detect-tractable").help("Decide Horn and 2-SAT formulas in polynomial time"))
+
+    .arg(clap::Arg::with_name("asymm").long("asymm").conflicts_with("core").help("Shrink clauses by asymmetric branching"))
+    .arg(clap::Arg::with_name("no-asymm").long("no-asymm").conflicts_with("asymm").conflicts_with("core"))
+    .arg(clap::Arg::with_name("hbr").long("hbr").conflicts_with("core").help("Probe literals before elimination, adding hyper-binary resolvents"))
+    .arg(clap::Arg::with_name("elim").long("elim").conflicts_with("core").help("Perform variable elimination"))
+    .arg(clap::Arg::with_name("no-elim").long("no-elim").conflicts_with("elim").conflicts_with("core"))
+    .arg(clap::Arg::with_name("grow").long("grow").takes_value(true).conflicts_with("core").help("Allow a variable elimination step to grow by a number of clauses"))
+    .arg(clap::Arg::with_name("cl-lim").long("cl-lim").takes_value(true).conflicts_with("core").help("Variables are not eliminated if it produces a resolvent with a length above this limit. -1 means no limit"))
+    .arg(clap::Arg::with_name("sub-lim").long("sub-lim").takes_value(true).conflicts_with("core").help("Do not check if subsumption against a clause larger than this. -1 means no limit."))
+    .arg(clap::Arg::with_name("subsuming-lim").long("subsuming-lim").takes_value(true).conflicts_with("core").help("Do not subsume other clauses with a clause larger than this. -1 means no limit."))
+    .arg(clap::Arg::with_name("preprocess-rounds").long("preprocess-rounds").takes_value(true).conflicts_with("core").help("Repeat subsumption and elimination until nothing changes, at most this many times"))
+    .arg(clap::Arg::with_name("simp-gc-frac").long("simp-gc-frac").takes_value(true).conflicts_with("core").help("The fraction of wasted memory allowed before a garbage collection is triggered during simplification."))
+}
 
-            for &x in matches.value_of("phase_saving").iter() {
-                match x {
-                    "0" => {
-                        s.heur.phase_saving = PhaseSaving::None;
-                    }
-                    "1" => {
-                        s.heur.phase_saving = PhaseSaving::Limited;
-                    }
-                    "2" => {
-                        s.heur.phase_saving = PhaseSaving::Full;
-                    }
-                    _ => {}
-                }
-            }
+// Value of an option, or an error naming the option if it does not parse:
+fn value<T: str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Result<Option<T>, String> {
+    match matches.value_of(name) {
+        Some(s) => s.parse().map(Some).map_err(|_| format!("Invalid value '{}' of '{}'", s, name)),
+        None => Ok(None),
+    }
+}
 
-            if matches.is_present("rnd-init") {
-                s.heur.rnd_init_act = true;
-            }
-            if matches.is_present("no-rnd-init") {
-                s.heur.rnd_init_act = false;
-            }
+// Core options are applied by name through 'SettingsBuilder::set', the same way as in config strings:
+fn core_settings(matches: &clap::ArgMatches) -> Result<SettingsBuilder, String> {
+    let mut b = SettingsBuilder::new();
 
-            if matches.is_present("luby") {
-                s.search.restart.luby_restart = true;
-            }
-            if matches.is_present("no-luby") {
-                s.search.restart.luby_restart = false;
-            }
+    for &name in CORE_VALUES.iter() {
+        if let Some(x) = matches.value_of(name) {
+            b = b.set(name, x)?;
+        }
+    }
 
-            for &x in matches
-                .value_of("rfirst")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0.0 < x {
-                    s.search.restart.restart_first = x;
-                }
-            }
+    for &name in CORE_FLAGS.iter() {
+        if matches.is_present(name) {
+            b = b.set(name, "true")?;
+        }
+        if matches.is_present(format!("no-{}", name)) {
+            b = b.set(name, "false")?;
+        }
+    }
 
-            for &x in matches.value_of("rinc").and_then(|s| s.parse().ok()).iter() {
-                if 1.0 < x {
-                    s.search.restart.restart_inc = x;
-                }
-            }
+    Ok(b)
+}
 
-            for &x in matches
-                .value_of("gc-frac")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0.0 < x && x <= 1.0 {
-                    s.core.garbage_frac = x;
-                }
-            }
+fn simp_settings(matches: &clap::ArgMatches, mut b: SettingsBuilder) -> Result<SettingsBuilder, String> {
+    if matches.is_present("asymm") {
+        b = b.use_asymm(true);
+    }
+    if matches.is_present("no-asymm") {
+        b = b.use_asymm(false);
+    }
 
-            for &x in matches
-                .value_of("min-learnts")
-                .and_then(|s| s.parse().ok())
-                .iter()
-            {
-                if 0 <= x {
-                    s.search.learn.min_learnts_lim = x;
-                }
-            }
+    if matches.is_present("hbr") {
+        b = b.use_hbr(true);
+    }
 
-            if matches.is_present("rcheck") {
-                s.core.use_rcheck = true;
-            }
-            if matches.is_present("no-rcheck") {
-                s.core.use_rcheck = false;
-            }
+    if matches.is_present("elim") {
+        b = b.use_elim(true);
+    }
+    if matches.is_present("no-elim") {
+        b = b.use_elim(false);
+    }
 
-            s
-        };
+    if let Some(x) = value(matches, "grow")? {
+        b = b.grow(x);
+    }
 
-        if matches.is_present("core") {
-            minisat_rust::SolverOptions::Core(core_options)
-        } else {
-            let simp_options = {
-                let mut s = minisat::SimpSettings::default();
-                s.core = core_options;
-
-                if matches.is_present("asymm") {
-                    s.simp.use_asymm = true;
-                }
-                if matches.is_present("no-asymm") {
-                    s.simp.use_asymm = false;
-                }
-
-                if matches.is_present("elim") {
-                    s.simp.use_elim = true;
-                }
-                if matches.is_present("no-elim") {
-                    s.simp.use_elim = false;
-                }
-
-                for &x in matches.value_of("grow").and_then(|s| s.parse().ok()).iter() {
-                    s.simp.grow = x;
-                }
-
-                for &x in matches
-                    .value_of("cl-lim")
-                    .and_then(|s| s.parse().ok())
-                    .iter()
-                {
-                    if -1 <= x {
-                        s.simp.clause_lim = x;
-                    }
-                }
-
-                for &x in matches
-                    .value_of("sub-lim")
-                    .and_then(|s| s.parse().ok())
-                    .iter()
-                {
-                    if -1 <= x {
-                        s.simp.subsumption_lim = x;
-                    }
-                }
-
-                for &x in matches
-                    .value_of("simp-gc-frac")
-                    .and_then(|s| s.parse().ok())
-                    .iter()
-                {
-                    if 0.0 < x && x <= 1.0 {
-                        s.simp.simp_garbage_frac = x;
-                    }
-                }
-
-                s
-            };
-
-            minisat_rust::SolverOptions::Simp(simp_options)
-        }
-    };
+    if let Some(x) = value(matches, "cl-lim")? {
+        b = b.clause_lim(x);
+    }
 
-    minisat_rust::solve(main, solver).expect("IO Error");
+    if let Some(x) = value(matches, "sub-lim")? {
+        b = b.subsumption_lim(x);
+    }
+
+    if let Some(x) = value(matches, "subsuming-lim")? {
+        b = b.subsuming_clause_max_len(x);
+    }
+
+    if let Some(x) = value(matches, "preprocess-rounds")? {
+        b = b.max_preprocess_rounds(x);
+    }
+
+    if let Some(x) = value(matches, "simp-gc-frac")? {
+        b = b.simp_garbage_frac(x);
+    }
+
+    Ok(b)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minisat_rust::sat::minisat::{self, PhaseSaving};
+
+    fn parse(args: &[&str]) -> minisat::CoreSettings {
+        let matches = cli().get_matches_from(["minisat-rust"].iter().chain(args.iter()).chain(["input.cnf"].iter()));
+        core_settings(&matches).unwrap().core().unwrap()
+    }
+
+    fn simp_error(args: &[&str]) -> Option<String> {
+        let matches = cli().get_matches_from(["minisat-rust"].iter().chain(args.iter()).chain(["input.cnf"].iter()));
+        simp_settings(&matches, SettingsBuilder::new()).err()
+    }
+
+    #[test]
+    fn test_phase_saving() {
+        assert!(parse(&[]).heur.phase_saving == PhaseSaving::Full);
+        assert!(parse(&["--phase-saving", "0"]).heur.phase_saving == PhaseSaving::None);
+        assert!(parse(&["--phase-saving", "1"]).heur.phase_saving == PhaseSaving::Limited);
+    }
+
+    #[test]
+    fn test_invalid_simp_values() {
+        assert_eq!(simp_error(&["--grow", "1", "--cl-lim=-1"]), None);
+        assert_eq!(simp_error(&["--grow", "many"]), Some("Invalid value 'many' of 'grow'".to_string()));
+        assert_eq!(simp_error(&["--cl-lim", "1.5"]), Some("Invalid value '1.5' of 'cl-lim'".to_string()));
+        assert_eq!(simp_error(&["--simp-gc-frac", "x"]), Some("Invalid value 'x' of 'simp-gc-frac'".to_string()));
+    }
 }
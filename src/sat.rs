@@ -1,6 +1,10 @@
+use core::ops;
+use alloc::vec::Vec;
 use crate::sat::formula::{Lit, Var};
 
+#[cfg(feature = "std")]
 pub mod dimacs;
+pub mod encoding;
 pub mod formula;
 pub mod minisat;
 
@@ -15,6 +19,93 @@ pub struct Stats {
     pub propagations: u64,
     pub tot_literals: u64,
     pub del_literals: u64,
+    pub gc_runs: u64,
+    pub gc_reclaimed_bytes: u64,
+    pub max_trail_size: u64,
+    pub propagations_since_last_conflict: u64,
+    pub max_learnts: u64, // Largest number of learnt clauses kept at once.
+    pub restart_unassigns: u64, // Literals unassigned by restarts, that have to be decided or propagated again.
+    pub max_locked: u64, // Most learnt clauses locked as reasons for the assignment at a reduction of learnt clauses.
+    pub unlock_restarts: u64, // Restarts to unlock learnt clauses before reducing them (see 'unlock_before_reduce').
+    pub progress: f64, // Estimated fraction of the search space covered when the search last stopped, in [0, 1].
+    pub learnts: u64, // Clauses learnt from conflicts, units included (removed ones are counted too).
+    pub learnt_literals: u64,
+    pub learnt_lbd: u64, // Sum of LBD over learnt clauses.
+    pub max_learnt_len: u64,
+    pub max_clause_len: u64, // Longest problem clause stored, i.e. without literals false at ground level.
+}
+
+impl Stats {
+    pub fn detailed_stats(&self) -> DetailedStats {
+        DetailedStats {
+            max_trail_size: self.max_trail_size,
+            propagations_since_last_conflict: self.propagations_since_last_conflict,
+            propagations_per_conflict: average(self.propagations, self.conflicts),
+            conflicts_per_restart: average(self.conflicts, self.restarts),
+            max_learnt_len: self.max_learnt_len,
+            avg_learnt_len: average(self.learnt_literals, self.learnts),
+            avg_lbd: average(self.learnt_lbd, self.learnts),
+        }
+    }
+
+    // Combine statistics of independent runs (portfolio members, cubes): counters are summed, peak
+    // values and progress take the maximum.
+    pub fn merge(&mut self, other: &Stats) {
+        self.solves += other.solves;
+        self.restarts += other.restarts;
+        self.decisions += other.decisions;
+        self.rnd_decisions += other.rnd_decisions;
+        self.conflicts += other.conflicts;
+        self.propagations += other.propagations;
+        self.tot_literals += other.tot_literals;
+        self.del_literals += other.del_literals;
+        self.gc_runs += other.gc_runs;
+        self.gc_reclaimed_bytes += other.gc_reclaimed_bytes;
+        self.restart_unassigns += other.restart_unassigns;
+        self.unlock_restarts += other.unlock_restarts;
+        self.learnts += other.learnts;
+        self.learnt_literals += other.learnt_literals;
+        self.learnt_lbd += other.learnt_lbd;
+        self.max_learnt_len = self.max_learnt_len.max(other.max_learnt_len);
+        self.max_clause_len = self.max_clause_len.max(other.max_clause_len);
+        self.max_trail_size = self.max_trail_size.max(other.max_trail_size);
+        self.propagations_since_last_conflict = self.propagations_since_last_conflict.max(other.propagations_since_last_conflict);
+        self.max_learnts = self.max_learnts.max(other.max_learnts);
+        self.max_locked = self.max_locked.max(other.max_locked);
+        self.progress = self.progress.max(other.progress);
+    }
+}
+
+impl ops::Add for Stats {
+    type Output = Stats;
+
+    fn add(mut self, other: Stats) -> Stats {
+        self.merge(&other);
+        self
+    }
+}
+
+// Profiling counters derived from 'Stats'. Averages are zero if nothing was counted yet.
+#[derive(Default, Debug)]
+pub struct DetailedStats {
+    pub max_trail_size: u64,
+    pub propagations_since_last_conflict: u64,
+    pub propagations_per_conflict: f64,
+    pub conflicts_per_restart: f64,
+    pub max_learnt_len: u64,
+    pub avg_learnt_len: f64,
+    pub avg_lbd: f64,
+}
+
+fn average(total: u64, count: u64) -> f64 {
+    if count == 0 { 0.0 } else { total as f64 / count as f64 }
+}
+
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AssumptionError {
+    TooMany(usize),  // More assumptions than allowed by 'set_assumption_limit'.
+    Eliminated(Var), // Variable removed by elimination that can't be reintroduced (see 'reversible_elim').
 }
 
 
@@ -22,6 +113,7 @@ pub enum SolveRes<Solver> {
     UnSAT(Stats),
     SAT(Vec<Lit>, Stats),
     Interrupted(f64, Solver),
+    Invalid(AssumptionError, Solver), // Assumptions rejected before solving, the solver is left as it was.
 }
 
 
@@ -29,8 +121,42 @@ pub trait Solver: Sized {
     fn n_vars(&self) -> usize;
     fn n_clauses(&self) -> usize;
     fn new_var(&mut self, upol: Option<bool>, dvar: bool) -> Var;
+    fn reserve_vars(&mut self, _: usize) {} // Room for 'n' more variables, so that adding them does not reallocate.
     fn add_clause(&mut self, clause: &[Lit]) -> bool;
     fn preprocess(&mut self, _: &minisat::budget::Budget) -> bool;
+    fn simplify(&mut self) -> bool; // Top-level simplification only: returns false if the formula is found UNSAT.
     fn solve_limited(self, _: &minisat::budget::Budget, _: &[Lit]) -> SolveRes<Self>;
+    fn verify_model(&self, model: &[Lit]) -> bool; // Check 'model' against clauses currently held by the solver.
     fn stats(&self) -> Stats;
 }
+
+// Solvers that can give back the formula they hold, e.g. to write it out (see 'dimacs::write').
+pub trait ClauseSource {
+    fn clauses(&self) -> Vec<Vec<Lit>>; // Problem clauses currently held by the solver, ground units included.
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_stats() {
+        let a = Stats { solves: 1, restarts: 2, decisions: 30, conflicts: 10, propagations: 100,
+                        gc_runs: 1, max_trail_size: 40, max_learnts: 7, progress: 0.25, learnts: 10, max_learnt_len: 12, ..Default::default() };
+        let b = Stats { solves: 1, restarts: 3, decisions: 20, rnd_decisions: 2, conflicts: 5, propagations: 80,
+                        max_trail_size: 25, max_learnts: 9, restart_unassigns: 6, progress: 0.5, learnts: 5, max_learnt_len: 8, ..Default::default() };
+
+        let mut total = Stats::default();
+        total.merge(&a);
+        total.merge(&b);
+        assert_eq!((total.solves, total.restarts, total.decisions, total.rnd_decisions), (2, 5, 50, 2));
+        assert_eq!((total.conflicts, total.propagations, total.gc_runs, total.restart_unassigns), (15, 180, 1, 6));
+        assert_eq!((total.max_trail_size, total.max_learnts), (40, 9));
+        assert_eq!((total.learnts, total.max_learnt_len), (15, 12));
+        assert_eq!(total.progress, 0.5);
+
+        let sum = a + b;
+        assert_eq!((sum.decisions, sum.conflicts, sum.max_learnts), (50, 15, 9));
+    }
+}
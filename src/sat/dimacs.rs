@@ -1,15 +1,161 @@
 // TODO: wait for io stabilization and completely rewrite it
-use std::{fs, io, path, str};
-use std::io::{Seek, SeekFrom};
+use std::{error, fmt, fs, io, path, str};
+use std::cell::Cell;
+use std::io::{Seek, SeekFrom, Write};
 use std::collections::{HashMap, HashSet};
+use flate2::Compression;
 use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use crate::sat::formula::{Lit, Var, VarMap};
-use crate::sat::{SolveRes, Solver};
+use crate::sat::{ClauseSource, SolveRes, Solver};
 
 
-pub fn write<W: io::Write, S: Solver>(_: W, _: &S) -> io::Result<()> {
-    // TODO: implement
-    unimplemented!()
+// Write clauses held by 'solver' (see 'ClauseSource::clauses') with variables numbered as in the
+// input. Variables without an id there (see 'extend_subst') are numbered above all the others.
+pub fn write<W: io::Write, S: ClauseSource>(writer: W, solver: &S, backward_subst: &VarMap<i32>) -> io::Result<()> {
+    let clauses = solver.clauses();
+    write_clauses(writer, &clauses, &extend_subst(&clauses, backward_subst))
+}
+
+// Like 'write', to 'path'. With 'compact', variables left in the formula are renumbered to 1..k
+// (see 'compact_subst'), and the renaming is written to 'path' with '.map' appended: one line
+// 'new original' per variable that has an original id. Variables without one get no map line.
+pub fn write_file<P: AsRef<path::Path>, S: ClauseSource>(
+    path: P,
+    solver: &S,
+    backward_subst: &VarMap<i32>,
+    compact: bool,
+) -> io::Result<()> {
+    let clauses = solver.clauses();
+    let out = io::BufWriter::new(fs::File::create(path.as_ref())?);
+    if !compact {
+        return write_clauses(out, &clauses, &extend_subst(&clauses, backward_subst));
+    }
+
+    let (subst, original) = compact_subst(&clauses, backward_subst);
+    write_clauses(out, &clauses, &subst)?;
+
+    let mut map_path = path.as_ref().as_os_str().to_owned();
+    map_path.push(".map");
+    let mut map = io::BufWriter::new(fs::File::create(map_path)?);
+    for (i, id) in original.iter().enumerate() {
+        writeln!(map, "{} {}", i + 1, id)?;
+    }
+    map.flush()
+}
+
+// 'backward_subst' with ids above the largest one given to variables occurring in 'clauses'
+// without an id: selectors, encoding auxiliaries and any other variable created after parsing.
+pub fn extend_subst(clauses: &[Vec<Lit>], backward_subst: &VarMap<i32>) -> VarMap<i32> {
+    let mut subst = backward_subst.clone();
+    let mut next = backward_subst.iter().map(|(_, &id)| id).max().unwrap_or(0);
+    for lit in clauses.iter().flat_map(|c| c.iter()) {
+        if subst.get(&lit.var()).is_none() {
+            next += 1;
+            subst.insert(&lit.var(), next);
+        }
+    }
+    subst
+}
+
+// Renumbering of variables occurring in 'clauses' to 1..k, in the order of their ids in
+// 'backward_subst'. Returned along with the original id of every new one. Variables without an
+// id (e.g. created by the solver itself) are numbered after all of them, and have no original.
+pub fn compact_subst(clauses: &[Vec<Lit>], backward_subst: &VarMap<i32>) -> (VarMap<i32>, Vec<i32>) {
+    let mut original: Vec<i32> = clauses.iter().flat_map(|c| c.iter()).filter_map(|lit| backward_subst.get(&lit.var()).cloned()).collect();
+    original.sort();
+    original.dedup();
+
+    let mut subst = VarMap::new();
+    for (v, id) in backward_subst.iter() {
+        if let Ok(i) = original.binary_search(id) {
+            subst.insert(&v, i as i32 + 1);
+        }
+    }
+
+    let mut next = original.len() as i32;
+    for lit in clauses.iter().flat_map(|c| c.iter()) {
+        if subst.get(&lit.var()).is_none() {
+            next += 1;
+            subst.insert(&lit.var(), next);
+        }
+    }
+    (subst, original)
+}
+
+
+// What to do when clauses use variables above the number declared in the header. Variables are
+// allocated as they occur, so the formula is read the same way unless it is an error. The largest
+// variable is checked rather than the number of distinct ones (as strict parsing used to do): a
+// header 'p cnf 3 ...' does not cover variable 5 even if only two variables occur.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum HeaderMismatch {
+    Error,
+    Warn,
+    Ignore,
+}
+
+impl HeaderMismatch {
+    // Policy of 'parse' and 'parse_file': variables are checked along with clauses if 'validate'.
+    pub fn validating(validate: bool) -> HeaderMismatch {
+        if validate { HeaderMismatch::Error } else { HeaderMismatch::Ignore }
+    }
+}
+
+
+// Why a formula could not be read:
+#[derive(Debug)]
+pub enum DimacsError {
+    HeaderMismatch { what: &'static str, declared: usize, found: usize }, // 'what' is "vars" or "clauses".
+    UnexpectedChar { expected: &'static str, found: char },
+    UnexpectedEof { expected: &'static str },
+    UnsupportedFormat(&'static str),
+    Io(io::Error),
+}
+
+impl fmt::Display for DimacsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DimacsError::HeaderMismatch { what, declared, found } => {
+                write!(f, "PARSE ERROR! DIMACS header mismatch: {} {} declared, {} discovered", declared, what, found)
+            }
+            DimacsError::UnexpectedChar { expected, found } => {
+                write!(f, "PARSE ERROR! Unexpected character '{}', expected {}", found, expected)
+            }
+            DimacsError::UnexpectedEof { expected } => {
+                write!(f, "PARSE ERROR! Unexpected end of file, expected {}", expected)
+            }
+            DimacsError::UnsupportedFormat(format) => {
+                write!(f, "PARSE ERROR! {} format is not supported, expected 'p cnf'", format)
+            }
+            DimacsError::Io(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for DimacsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            DimacsError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DimacsError {
+    fn from(err: io::Error) -> DimacsError {
+        DimacsError::Io(err)
+    }
+}
+
+// Malformed input becomes 'InvalidData', for functions reporting plain IO errors:
+impl From<DimacsError> for io::Error {
+    fn from(err: DimacsError) -> io::Error {
+        match err {
+            DimacsError::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
 }
 
 
@@ -17,18 +163,21 @@ pub fn parse_file<P: AsRef<path::Path>, S: Solver>(
     path: P,
     solver: &mut S,
     validate: bool,
-) -> io::Result<VarMap<i32>> {
-    let mut reader = io::BufReader::new(fs::File::open(path)?);
-    {
-        let gz = GzDecoder::new(&mut reader);
-
-        if gz.header().is_some() {
-            return parse(gz, solver, validate);
-        }
-    }
+) -> Result<VarMap<i32>, DimacsError> {
+    parse_file_with(path, solver, validate, HeaderMismatch::validating(validate))
+}
 
-    reader.seek(SeekFrom::Start(0))?;
-    parse(reader, solver, validate)
+// Like 'parse_file', but 'validate' only checks the number of clauses, while 'vars' tells what
+// to do about undeclared variables.
+pub fn parse_file_with<P: AsRef<path::Path>, S: Solver>(
+    path: P,
+    solver: &mut S,
+    validate: bool,
+    vars: HeaderMismatch,
+) -> Result<VarMap<i32>, DimacsError> {
+    let mut subst = Subst::new(solver);
+    subst.parse_file(path, validate, vars)?;
+    Ok(subst.into_backward_subst())
 }
 
 
@@ -36,13 +185,40 @@ pub fn parse<R: io::Read, S: Solver>(
     reader: R,
     solver: &mut S,
     validate: bool,
-) -> io::Result<VarMap<i32>> {
+) -> Result<VarMap<i32>, DimacsError> {
+    parse_with(reader, solver, validate, HeaderMismatch::validating(validate))
+}
+
+pub fn parse_with<R: io::Read, S: Solver>(
+    reader: R,
+    solver: &mut S,
+    validate: bool,
+    vars: HeaderMismatch,
+) -> Result<VarMap<i32>, DimacsError> {
     let mut subst = Subst::new(solver);
-    DimacsParser::parse(reader, validate, |cl| subst.add_clause(cl))?;
-    Ok(subst.backward_subst)
+    subst.parse(reader, validate, vars)?;
+    Ok(subst.into_backward_subst())
 }
 
 
+// Write result to 'path', gzip-compressed if it has '.gz' extension.
+pub fn write_result_file<P: AsRef<path::Path>, S>(
+    path: P,
+    result: SolveRes<S>,
+    backward_subst: &VarMap<i32>,
+) -> io::Result<()> {
+    let gzipped = path.as_ref().extension() == Some("gz".as_ref());
+    let file = fs::File::create(path)?;
+    if gzipped {
+        let mut gz = GzEncoder::new(file, Compression::default());
+        write_result(&mut gz, result, backward_subst)?;
+        gz.finish()?;
+        Ok(())
+    } else {
+        write_result(file, result, backward_subst)
+    }
+}
+
 pub fn write_result<W: io::Write, S>(
     mut writer: W,
     result: SolveRes<S>,
@@ -53,15 +229,14 @@ pub fn write_result<W: io::Write, S>(
             writeln!(writer, "UNSAT")?;
         }
 
-        SolveRes::Interrupted(_, _) => {
+        SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => {
             writeln!(writer, "INDET")?;
         }
 
         SolveRes::SAT(model, _) => {
             writeln!(writer, "SAT")?;
-            for lit in model.iter() {
-                let var_id = backward_subst[&lit.var()];
-                write!(writer, "{} ", if lit.sign() { -var_id } else { var_id })?;
+            for &lit in model.iter() {
+                write!(writer, "{} ", DimacsLit(lit, backward_subst))?;
             }
             writeln!(writer, "0")?;
         }
@@ -70,6 +245,64 @@ pub fn write_result<W: io::Write, S>(
 }
 
 
+// Write 'clauses' in DIMACS format with variables numbered as in the input file.
+pub fn write_clauses<W: io::Write>(
+    mut writer: W,
+    clauses: &[Vec<Lit>],
+    backward_subst: &VarMap<i32>,
+) -> io::Result<()> {
+    let vars = backward_subst.iter().map(|(_, &id)| id).max().unwrap_or(0);
+    writeln!(writer, "p cnf {} {}", vars, clauses.len())?;
+    for clause in clauses.iter() {
+        for &lit in clause.iter() {
+            write!(writer, "{} ", DimacsLit(lit, backward_subst))?;
+        }
+        writeln!(writer, "0")?;
+    }
+    Ok(())
+}
+
+
+// Read clauses written by 'write_clauses' back into variables of the solver 'backward_subst'
+// belongs to. All variables must already exist there.
+pub fn parse_clauses<R: io::Read>(reader: R, backward_subst: &VarMap<i32>) -> io::Result<Vec<Vec<Lit>>> {
+    let forward_subst: HashMap<i32, Var> = backward_subst.iter().map(|(v, &id)| (id, v)).collect();
+    let mut clauses = Vec::new();
+    let mut unknown = None;
+    DimacsParser::parse(reader, false, |raw| {
+        let lits: Option<Vec<Lit>> = raw.iter()
+            .map(|&lit_id| forward_subst.get(&lit_id.abs()).map(|v| v.sign_lit(lit_id < 0)))
+            .collect();
+        match lits {
+            Some(lits) => clauses.push(lits),
+            None => unknown = raw.iter().cloned().find(|lit_id| !forward_subst.contains_key(&lit_id.abs())),
+        }
+    })?;
+
+    match unknown {
+        Some(lit_id) => Err(io::Error::new(io::ErrorKind::InvalidData, format!("PARSE ERROR! Unknown variable: {}", lit_id.abs()))),
+        None => Ok(clauses),
+    }
+}
+
+
+// Literal printed with the variable number from the input file, as given by 'backward_subst':
+pub struct DimacsLit<'a>(pub Lit, pub &'a VarMap<i32>);
+
+impl<'a> DimacsLit<'a> {
+    pub fn id(&self) -> i32 {
+        let var_id = self.1[&self.0.var()];
+        if self.0.sign() { -var_id } else { var_id }
+    }
+}
+
+impl<'a> fmt::Display for DimacsLit<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+
 pub fn validate_model_file<P: AsRef<path::Path>>(
     path: P,
     backward_subst: &VarMap<i32>,
@@ -93,15 +326,8 @@ pub fn validate_model<R: io::Read>(
     model: &Vec<Lit>,
 ) -> io::Result<bool> {
     let mut lits = HashSet::new();
-    for lit in model.iter() {
-        let lit_id = {
-            let var_id = backward_subst[&lit.var()];
-            if lit.sign() {
-                -var_id
-            } else {
-                var_id
-            }
-        };
+    for &lit in model.iter() {
+        let lit_id = DimacsLit(lit, backward_subst).id();
 
         lits.insert(lit_id);
         if lits.contains(&(-lit_id)) {
@@ -113,7 +339,7 @@ pub fn validate_model<R: io::Read>(
     DimacsParser::parse(reader, false, |cl| {
         let mut found = false;
         for lit in cl {
-            if lits.contains(&lit) {
+            if lits.contains(lit) {
                 found = true;
                 break;
             }
@@ -128,10 +354,82 @@ pub fn validate_model<R: io::Read>(
 }
 
 
-struct Subst<'s, S: 's> {
+// Shape of a CNF formula as written in the file, before any simplification.
+#[derive(Default, Debug)]
+pub struct FormulaStats {
+    pub header_vars: usize,
+    pub header_clauses: usize,
+    pub vars: usize, // Distinct variables occurring in clauses.
+    pub clauses: usize,
+    pub clause_lengths: Vec<usize>, // 'clause_lengths[k]' is the number of clauses with 'k' literals.
+    pub occurrences: Vec<usize>,    // 'occurrences[k]' is the number of literals of occurring variables found in 'k' clauses.
+}
+
+pub fn analyze_file<P: AsRef<path::Path>>(path: P) -> io::Result<FormulaStats> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    {
+        let gz = GzDecoder::new(&mut reader);
+        if gz.header().is_some() {
+            return analyze(gz);
+        }
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    analyze(reader)
+}
+
+pub fn analyze<R: io::Read>(reader: R) -> io::Result<FormulaStats> {
+    let mut stats = FormulaStats::default();
+    let mut lit_occurs: HashMap<i32, usize> = HashMap::new();
+    let (header_vars, header_clauses) = DimacsParser::parse(reader, false, |cl| {
+        stats.clauses += 1;
+        bump_histogram(&mut stats.clause_lengths, cl.len());
+        for &lit in cl {
+            *lit_occurs.entry(lit).or_insert(0) += 1;
+            lit_occurs.entry(-lit).or_insert(0);
+        }
+    })?;
+
+    stats.header_vars = header_vars;
+    stats.header_clauses = header_clauses;
+    stats.vars = lit_occurs.len() / 2;
+    for &n in lit_occurs.values() {
+        bump_histogram(&mut stats.occurrences, n);
+    }
+    Ok(stats)
+}
+
+fn bump_histogram(histogram: &mut Vec<usize>, k: usize) {
+    if histogram.len() <= k {
+        histogram.resize(k + 1, 0);
+    }
+    histogram[k] += 1;
+}
+
+
+pub enum ProofStep {
+    Add(Vec<i32>),
+    Delete(Vec<i32>),
+}
+
+// Parse DRAT proof: clause additions and deletions ("d" lines) in DIMACS literal notation.
+pub fn parse_proof<R: io::Read, F: FnMut(ProofStep)>(reader: R, step: F) -> io::Result<()> {
+    Ok(DimacsParser::parse_proof(reader, step)?)
+}
+
+
+// Headers are not trusted with more variables than this to reserve up front:
+const MAX_RESERVED_VARS: usize = 1 << 24;
+
+// Numbering of DIMACS variables in 'solver', kept between parse calls so that several files can
+// share variables. Numbers not registered yet get fresh variables, allocated in increasing order of
+// numbers up to the one found.
+pub struct Subst<'s, S: 's> {
     solver: &'s mut S,
     forward_subst: HashMap<i32, Var>,
     backward_subst: VarMap<i32>,
+    next_id: i32, // All numbers up to this one are registered.
+    lits: Vec<Lit>, // Scratch buffer reused for every clause.
 }
 
 impl<'s, S: Solver> Subst<'s, S> {
@@ -140,28 +438,88 @@ impl<'s, S: Solver> Subst<'s, S> {
             solver,
             forward_subst: HashMap::new(),
             backward_subst: VarMap::new(),
+            next_id: 0,
+            lits: Vec::new(),
+        }
+    }
+
+    // DIMACS variable 'id' stands for 'v' for every '(id, v)' of 'map'. Variables of 'map' missing
+    // in 'solver' are created.
+    pub fn with_var_map<I: IntoIterator<Item = (i32, Var)>>(solver: &'s mut S, map: I) -> Self {
+        let mut subst = Subst::new(solver);
+        for (var_id, v) in map {
+            assert!(var_id > 0, "DIMACS variable numbers are positive: {}", var_id);
+            while Var::from_index(subst.solver.n_vars()) <= v {
+                subst.solver.new_var(None, true);
+            }
+            subst.register(var_id, v);
         }
+        subst
     }
 
-    pub fn add_clause(&mut self, raw: Vec<i32>) {
-        let lits: Vec<Lit> = raw.iter().map(|&lit_id| self.lit_by_id(lit_id)).collect();
-        self.solver.add_clause(&lits[..]);
+    pub fn var(&self, var_id: i32) -> Option<Var> {
+        self.forward_subst.get(&var_id).cloned()
+    }
+
+    pub fn backward_subst(&self) -> &VarMap<i32> {
+        &self.backward_subst
+    }
+
+    pub fn into_backward_subst(self) -> VarMap<i32> {
+        self.backward_subst
+    }
+
+    // Room for the variables declared in the header, less those the solver has already, is reserved
+    // before the first clause.
+    pub fn parse<R: io::Read>(&mut self, reader: R, validate: bool, vars: HeaderMismatch) -> Result<(), DimacsError> {
+        let declared = Cell::new(None);
+        DimacsParser::parse_with(reader, validate, vars, |n, _| declared.set(Some(n)), |cl| {
+            if let Some(n) = declared.take() {
+                self.solver.reserve_vars(n.saturating_sub(self.solver.n_vars()).min(MAX_RESERVED_VARS));
+            }
+            self.add_clause(cl)
+        })?;
+        Ok(())
+    }
+
+    // Same as 'parse', reading gzip-compressed files as well.
+    pub fn parse_file<P: AsRef<path::Path>>(&mut self, path: P, validate: bool, vars: HeaderMismatch) -> Result<(), DimacsError> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+        {
+            let gz = GzDecoder::new(&mut reader);
+
+            if gz.header().is_some() {
+                return self.parse(gz, validate, vars);
+            }
+        }
+
+        reader.seek(SeekFrom::Start(0))?;
+        self.parse(reader, validate, vars)
+    }
+
+    fn add_clause(&mut self, raw: &[i32]) {
+        self.lits.clear();
+        for &lit_id in raw.iter() {
+            let lit = self.lit_by_id(lit_id);
+            self.lits.push(lit);
+        }
+        self.solver.add_clause(&self.lits);
     }
 
     fn lit_by_id(&mut self, lit_id: i32) -> Lit {
-        if !self.forward_subst.contains_key(&lit_id.abs()) {
-            // TODO: finish it
-            while (lit_id.abs() as usize) > self.solver.n_vars() {
-                let idx = (self.solver.n_vars() + 1) as i32;
-                self.new_var(idx);
+        let var_id = lit_id.abs();
+        while !self.forward_subst.contains_key(&var_id) {
+            self.next_id += 1;
+            if !self.forward_subst.contains_key(&self.next_id) {
+                let v = self.solver.new_var(None, true);
+                self.register(self.next_id, v);
             }
         }
 
-        self.forward_subst[&lit_id.abs()].sign_lit(lit_id < 0)
+        self.forward_subst[&var_id].sign_lit(lit_id < 0)
     }
 
-    fn new_var(&mut self, var_id: i32) {
-        let v = self.solver.new_var(None, true);
+    fn register(&mut self, var_id: i32, v: Var) {
         self.forward_subst.insert(var_id, v);
         self.backward_subst.insert(&v, var_id);
     }
@@ -171,39 +529,88 @@ impl<'s, S: Solver> Subst<'s, S> {
 struct DimacsParser<'p> {
     reader: str::Chars<'p>,
     cur: Option<char>,
-    vars: HashSet<i32>,
+    max_var: usize, // Largest variable number found in clauses.
     clauses: usize,
 }
 
 impl<'p> DimacsParser<'p> {
-    pub fn parse<R: io::Read + 'p, F: FnMut(Vec<i32>) -> ()>(
+    // Returns the number of variables and clauses declared in the header. Literals passed to
+    // 'clause' live in a buffer reused for the next clause.
+    pub fn parse<R: io::Read + 'p, F: FnMut(&[i32]) -> ()>(
+        reader: R,
+        validate: bool,
+        clause: F,
+    ) -> Result<(usize, usize), DimacsError> {
+        DimacsParser::parse_with(reader, validate, HeaderMismatch::validating(validate), |_, _| {}, clause)
+    }
+
+    // Same as 'parse', also passing numbers of variables and clauses to 'header' once it is read.
+    pub fn parse_with<R: io::Read + 'p, H: FnMut(usize, usize), F: FnMut(&[i32])>(
         mut reader: R,
         validate: bool,
+        vars: HeaderMismatch,
+        header: H,
         clause: F,
-    ) -> io::Result<()> {
+    ) -> Result<(usize, usize), DimacsError> {
         let mut buf = String::new();
         reader.read_to_string(&mut buf)?;
 
         let mut p = DimacsParser {
             reader: buf.chars(),
             cur: None,
-            vars: HashSet::new(),
+            max_var: 0,
             clauses: 0,
         };
         p.next()?;
-        p.parse_me(validate, clause)
+        p.parse_me(validate, vars, header, clause)
     }
 
-    fn parse_me<F: FnMut(Vec<i32>) -> ()>(
+    pub fn parse_proof<R: io::Read + 'p, F: FnMut(ProofStep)>(mut reader: R, mut step: F) -> Result<(), DimacsError> {
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+
+        let mut p = DimacsParser {
+            reader: buf.chars(),
+            cur: None,
+            max_var: 0,
+            clauses: 0,
+        };
+        p.next()?;
+
+        loop {
+            p.skip_whitespace()?;
+            match p.current() {
+                None => return Ok(()),
+
+                Some('c') => {
+                    p.skip_line()?;
+                }
+
+                Some('d') => {
+                    p.next()?;
+                    step(ProofStep::Delete(p.parse_clause()?));
+                }
+
+                _ => {
+                    step(ProofStep::Add(p.parse_clause()?));
+                }
+            }
+        }
+    }
+
+    fn parse_me<H: FnMut(usize, usize), F: FnMut(&[i32]) -> ()>(
         &mut self,
         validate: bool,
+        vars_policy: HeaderMismatch,
+        mut header: H,
         mut clause: F,
-    ) -> io::Result<()> {
+    ) -> Result<(usize, usize), DimacsError> {
         enum State {
             Waiting,
             Parsing(usize, usize),
         }
 
+        let mut lits = Vec::new();
         let mut state = State::Waiting;
         loop {
             self.skip_whitespace()?;
@@ -214,9 +621,15 @@ impl<'p> DimacsParser<'p> {
                     }
 
                     _ => {
-                        self.consume("p cnf")?;
+                        self.consume("p ")?;
+                        if self.current() == Some('s') {
+                            // Nested formulas of 'p sat' (and its 'satx', 'sate' flavours) are not parsed:
+                            return Err(DimacsError::UnsupportedFormat("SAT (non-CNF)"));
+                        }
+                        self.consume("cnf")?;
                         let vars = self.next_uint()?;
                         let clauses = self.next_uint()?;
+                        header(vars, clauses);
                         state = State::Parsing(vars, clauses);
                     }
                 },
@@ -227,38 +640,48 @@ impl<'p> DimacsParser<'p> {
                     }
 
                     None => {
-                        if validate {
-                            if clauses != self.clauses {
-                                return Err(io::Error::new(io::ErrorKind::Other,
-                                        format!("PARSE ERROR! DIMACS header mismatch: {} clauses declared, {} found", clauses, self.clauses)));
-                            }
+                        if validate && clauses != self.clauses {
+                            return Err(DimacsError::HeaderMismatch { what: "clauses", declared: clauses, found: self.clauses });
+                        }
 
-                            if vars < self.vars.len() {
-                                return Err(io::Error::new(io::ErrorKind::Other,
-                                        format!("PARSE ERROR! DIMACS header mismatch: {} vars declared, {} discovered", vars, self.vars.len())));
+                        if vars < self.max_var {
+                            match vars_policy {
+                                HeaderMismatch::Error => {
+                                    return Err(DimacsError::HeaderMismatch { what: "vars", declared: vars, found: self.max_var });
+                                }
+                                HeaderMismatch::Warn => {
+                                    warn!(target: "minisat::dimacs", "DIMACS header mismatch: {} vars declared, {} discovered", vars, self.max_var);
+                                }
+                                HeaderMismatch::Ignore => {}
                             }
                         }
-                        return Ok(());
+                        return Ok((vars, clauses));
                     }
 
                     _ => {
-                        let c = self.parse_clause()?;
-                        clause(c);
+                        self.read_clause(&mut lits)?;
+                        clause(&lits);
                     }
                 },
             }
         }
     }
 
-    fn parse_clause(&mut self) -> io::Result<Vec<i32>> {
+    fn parse_clause(&mut self) -> Result<Vec<i32>, DimacsError> {
         let mut lits = Vec::new();
+        self.read_clause(&mut lits)?;
+        Ok(lits)
+    }
+
+    fn read_clause(&mut self, lits: &mut Vec<i32>) -> Result<(), DimacsError> {
+        lits.clear();
         loop {
             let lit = self.next_int()?;
             if lit == 0 {
                 self.clauses += 1;
-                return Ok(lits);
+                return Ok(());
             } else {
-                self.vars.insert(lit.abs());
+                self.max_var = self.max_var.max(lit.unsigned_abs() as usize);
                 lits.push(lit);
             }
         }
@@ -266,7 +689,7 @@ impl<'p> DimacsParser<'p> {
 
 
     #[inline]
-    pub fn next(&mut self) -> io::Result<()> {
+    pub fn next(&mut self) -> Result<(), DimacsError> {
         self.cur = self.reader.next();
         Ok(())
     }
@@ -276,7 +699,7 @@ impl<'p> DimacsParser<'p> {
         self.cur
     }
 
-    pub fn skip_whitespace(&mut self) -> io::Result<()> {
+    pub fn skip_whitespace(&mut self) -> Result<(), DimacsError> {
         loop {
             match self.cur {
                 None => break,
@@ -287,7 +710,7 @@ impl<'p> DimacsParser<'p> {
         Ok(())
     }
 
-    pub fn skip_line(&mut self) -> io::Result<()> {
+    pub fn skip_line(&mut self) -> Result<(), DimacsError> {
         loop {
             match self.cur {
                 None => break,
@@ -301,22 +724,25 @@ impl<'p> DimacsParser<'p> {
         Ok(())
     }
 
-    pub fn consume(&mut self, target: &str) -> io::Result<()> {
+    pub fn consume(&mut self, target: &'static str) -> Result<(), DimacsError> {
         for tc in target.chars() {
             match self.cur {
                 Some(c) if c == tc => self.next()?,
-                _ => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("failed to consume; expected '{}'", target),
-                    ));
-                }
+                _ => return Err(self.unexpected(target)),
             }
         }
         Ok(())
     }
 
-    fn read_int_body(&mut self) -> io::Result<usize> {
+    // Error for the current character (or the end of input) where 'expected' should be:
+    fn unexpected(&self, expected: &'static str) -> DimacsError {
+        match self.cur {
+            Some(found) => DimacsError::UnexpectedChar { expected, found },
+            None => DimacsError::UnexpectedEof { expected },
+        }
+    }
+
+    fn read_int_body(&mut self) -> Result<usize, DimacsError> {
         let mut len: usize = 0;
         let mut value = 0;
         loop {
@@ -329,14 +755,12 @@ impl<'p> DimacsParser<'p> {
 
                 _ if len > 0 => return Ok(value),
 
-                _ => {
-                    return Err(io::Error::new(io::ErrorKind::Other, "int expected"));
-                }
+                _ => return Err(self.unexpected("an integer")),
             }
         }
     }
 
-    pub fn next_int(&mut self) -> io::Result<i32> {
+    pub fn next_int(&mut self) -> Result<i32, DimacsError> {
         self.skip_whitespace()?;
         let sign = match self.cur {
             Some('+') => {
@@ -354,7 +778,7 @@ impl<'p> DimacsParser<'p> {
         Ok(sign * (val as i32))
     }
 
-    pub fn next_uint(&mut self) -> io::Result<usize> {
+    pub fn next_uint(&mut self) -> Result<usize, DimacsError> {
         self.skip_whitespace()?;
         match self.cur {
             Some('+') => self.next()?,
@@ -363,3 +787,203 @@ impl<'p> DimacsParser<'p> {
         self.read_int_body()
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimacs_lit() {
+        let v = Var::from_index(3);
+        let mut backward_subst = VarMap::new();
+        backward_subst.insert(&v, 42);
+
+        assert_eq!(DimacsLit(v.pos_lit(), &backward_subst).to_string(), "42");
+        assert_eq!(DimacsLit(v.neg_lit(), &backward_subst).to_string(), "-42");
+    }
+
+    #[test]
+    fn test_clauses_roundtrip() {
+        let mut backward_subst = VarMap::new();
+        let vars: Vec<Var> = (0..3).map(Var::from_index).collect();
+        for (i, v) in vars.iter().enumerate() {
+            backward_subst.insert(v, 10 + i as i32);
+        }
+
+        let clauses = vec![vec![vars[0].pos_lit(), vars[2].neg_lit()], vec![vars[1].neg_lit()]];
+        let mut buf = Vec::new();
+        write_clauses(&mut buf, &clauses, &backward_subst).expect("IO Error");
+        assert_eq!(String::from_utf8(buf.clone()).unwrap(), "p cnf 12 2\n10 -12 0\n-11 0\n");
+        assert_eq!(parse_clauses(&buf[..], &backward_subst).expect("IO Error"), clauses);
+
+        assert!(parse_clauses(&b"p cnf 13 1\n10 13 0\n"[..], &backward_subst).is_err());
+    }
+
+    #[test]
+    fn test_non_cnf_format() {
+        let err = DimacsParser::parse(&b"c circuit\np sat 3\n*(+(1 2) -3)\n"[..], false, |_| {}).unwrap_err();
+        assert!(err.to_string().contains("SAT (non-CNF) format is not supported"), "{}", err);
+
+        let (vars, clauses) = DimacsParser::parse(&b"p cnf 2 1\n1 -2 0\n"[..], false, |_| {}).expect("IO Error");
+        assert_eq!((vars, clauses), (2, 1));
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let parse_str = |cnf: &str, validate| {
+            let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+            parse(cnf.as_bytes(), &mut solver, validate)
+        };
+
+        match parse_str("p cnf 2 3\n1 -2 0\n", true) {
+            Err(DimacsError::HeaderMismatch { what: "clauses", declared: 3, found: 1 }) => {}
+            res => panic!("Expected a header mismatch, got {:?}", res.map(|_| ())),
+        }
+        match parse_str("p cnf 2 1\n1 x 0\n", false) {
+            Err(DimacsError::UnexpectedChar { found: 'x', .. }) => {}
+            res => panic!("Expected an unexpected character, got {:?}", res.map(|_| ())),
+        }
+        match parse_str("p dnf 2 1\n", false) {
+            Err(DimacsError::UnexpectedChar { expected: "cnf", found: 'd' }) => {}
+            res => panic!("Expected an unexpected character, got {:?}", res.map(|_| ())),
+        }
+        match parse_str("p cnf 2 1\n1 -2", false) {
+            Err(DimacsError::UnexpectedEof { .. }) => {}
+            res => panic!("Expected an unexpected end of file, got {:?}", res.map(|_| ())),
+        }
+
+        let err = parse_str("p cnf 2 1\n1 x 0\n", false).map(|_| ()).unwrap_err();
+        assert_eq!(err.to_string(), "PARSE ERROR! Unexpected character 'x', expected an integer");
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidData);
+
+        let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+        match parse_file("./tests/cnf/missing.cnf", &mut solver, false) {
+            Err(DimacsError::Io(ref err)) if err.kind() == io::ErrorKind::NotFound => {}
+            res => panic!("Expected an IO error, got {:?}", res.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_clause_buffer_reuse() {
+        // Longer clauses followed by shorter ones must not leave literals behind:
+        let cnf = "p cnf 4 4\n1 -2 3 4 0\n-1 0\nc comment\n2 -3 0\n4 1 2 0\n";
+        let mut clauses = Vec::new();
+        DimacsParser::parse(cnf.as_bytes(), true, |cl| clauses.push(cl.to_vec())).expect("IO Error");
+        assert_eq!(clauses, vec![vec![1, -2, 3, 4], vec![-1], vec![2, -3], vec![4, 1, 2]]);
+
+        let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+        let backward_subst = parse("p cnf 4 3\n1 -2 3 4 0\n2 -3 0\n4 1 0\n".as_bytes(), &mut solver, true).expect("IO Error");
+        let mut found: Vec<Vec<i32>> = solver.clauses_with(Var::from_index(0).pos_lit()).iter()
+            .map(|c| c.iter().map(|&lit| DimacsLit(lit, &backward_subst).id()).collect())
+            .collect();
+        for c in found.iter_mut() {
+            c.sort();
+        }
+        found.sort();
+        assert_eq!(found, vec![vec![-2, 1, 3, 4], vec![1, 4]]);
+    }
+
+    #[test]
+    fn test_compact_subst() {
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| Var::from_index(i));
+        let mut backward_subst = VarMap::new();
+        backward_subst.insert(&a, 7);
+        backward_subst.insert(&b, 3);
+        backward_subst.insert(&c, 5);
+
+        // 'c' is gone from the formula, and 'd' has no id:
+        let clauses = vec![vec![a.pos_lit(), d.neg_lit()], vec![b.neg_lit(), a.neg_lit()]];
+        let (subst, original) = compact_subst(&clauses, &backward_subst);
+        assert_eq!(original, vec![3, 7]);
+        let ids: Vec<Option<i32>> = [a, b, c, d].iter().map(|v| subst.get(v).cloned()).collect();
+        assert_eq!(ids, vec![Some(2), Some(1), None, Some(3)]);
+    }
+
+    #[test]
+    fn test_write_unmapped_vars() {
+        let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+        let backward_subst = parse("p cnf 3 2\n1 -3 0\n2 3 0\n".as_bytes(), &mut solver, true).expect("IO Error");
+
+        // 'x' is created after parsing, so it has no id in the input:
+        let x = solver.new_var(None, true);
+        assert!(solver.add_clause(&[x.pos_lit(), Var::from_index(0).neg_lit()]));
+
+        let mut out = Vec::new();
+        write(&mut out, &solver, &backward_subst).expect("IO Error");
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("p cnf 4 3\n"), "{}", out);
+        assert!(out.lines().any(|line| line == "4 -1 0" || line == "-1 4 0"), "{}", out);
+    }
+
+    #[test]
+    fn test_header_mismatch() {
+        // Only 2 distinct variables, but the largest is above the declared count:
+        let cnf = "p cnf 3 2\n1 -5 0\n5 0\n";
+        let parse_with_policy = |validate, vars| {
+            let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+            parse_with(cnf.as_bytes(), &mut solver, validate, vars).map(|_| solver.n_vars())
+        };
+
+        let err = parse_with_policy(false, HeaderMismatch::Error).unwrap_err();
+        assert!(err.to_string().contains("3 vars declared, 5 discovered"), "{}", err);
+        assert_eq!(parse_with_policy(true, HeaderMismatch::Warn).expect("IO Error"), 5);
+        assert_eq!(parse_with_policy(true, HeaderMismatch::Ignore).expect("IO Error"), 5);
+
+        // Defaults of 'parse':
+        let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+        assert!(parse(cnf.as_bytes(), &mut solver, true).is_err());
+        let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+        assert!(parse(cnf.as_bytes(), &mut solver, false).is_ok());
+
+        // More distinct variables than declared are above the count as well, while sparse ones
+        // within it are fine:
+        for &(cnf, ok) in [("p cnf 2 1\n1 2 3 0\n", false), ("p cnf 5 1\n1 -5 0\n", true)].iter() {
+            let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+            assert_eq!(parse(cnf.as_bytes(), &mut solver, true).is_ok(), ok, "{}", cnf);
+        }
+    }
+
+    #[test]
+    fn test_var_map() {
+        let mut solver = crate::sat::minisat::CoreSolver::new(Default::default());
+        let vars: Vec<Var> = (0..3).map(|_| solver.new_var(None, true)).collect();
+        let backward_subst = {
+            let mut subst = Subst::with_var_map(&mut solver, vec![(1, vars[2]), (3, vars[0])]);
+            subst.parse("p cnf 3 2\n1 -3 0\n-1 2 0\n".as_bytes(), true, HeaderMismatch::Error).expect("IO Error");
+            assert_eq!(subst.var(2), Some(Var::from_index(3)));
+
+            // Numbers are kept for the next file:
+            subst.parse("p cnf 4 1\n-2 4 0\n".as_bytes(), true, HeaderMismatch::Error).expect("IO Error");
+            assert_eq!(subst.var(4), Some(Var::from_index(4)));
+            subst.into_backward_subst()
+        };
+        let ids: Vec<Option<i32>> = (0..5).map(|i| backward_subst.get(&Var::from_index(i)).cloned()).collect();
+        assert_eq!(ids, vec![Some(3), None, Some(1), Some(2), Some(4)]);
+        assert_eq!(solver.n_vars(), 5);
+
+        let clauses_with = |lit: Lit| -> Vec<Vec<i32>> {
+            solver.clauses_with(lit).iter()
+                .map(|c| {
+                    let mut c: Vec<i32> = c.iter().map(|&lit| DimacsLit(lit, &backward_subst).id()).collect();
+                    c.sort();
+                    c
+                })
+                .collect()
+        };
+        assert_eq!(clauses_with(vars[0].neg_lit()), vec![vec![-3, 1]]);
+        assert_eq!(clauses_with(vars[2].neg_lit()), vec![vec![-1, 2]]);
+        assert_eq!(clauses_with(Var::from_index(3).neg_lit()), vec![vec![-2, 4]]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let cnf = "c example\np cnf 5 4\n1 -2 0\n2 3 0\n-1 -2 3 0\n3 0\n";
+        let stats = analyze(cnf.as_bytes()).expect("IO Error");
+        assert_eq!((stats.header_vars, stats.header_clauses), (5, 4));
+        assert_eq!((stats.vars, stats.clauses), (3, 4));
+        assert_eq!(stats.clause_lengths, vec![0, 1, 2, 1]);
+        // Literal -3 never occurs, 1, -1 and 2 occur once, -2 twice and 3 three times:
+        assert_eq!(stats.occurrences, vec![1, 3, 1, 1]);
+    }
+}
@@ -0,0 +1,243 @@
+use alloc::vec::Vec;
+use crate::sat::formula::{Lit, Var};
+use crate::sat::Solver;
+
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AmoEncoding {
+    Pairwise,   // n(n-1)/2 binary clauses, no auxiliary variables.
+    Sequential, // Ladder of n-1 auxiliary variables, 3n-4 clauses.
+    Commander,  // Groups of 3 literals with a commander variable each, applied recursively.
+}
+
+const COMMANDER_GROUP: usize = 3;
+const XOR_DIRECT_LIMIT: usize = 5; // Longer parity constraints are cut into a chain.
+
+
+// Encode "at most one of 'lits' is true". Returns auxiliary variables allocated for the encoding.
+pub fn at_most_one<S: Solver>(solver: &mut S, lits: &[Lit], encoding: AmoEncoding) -> Vec<Var> {
+    let mut aux = Vec::new();
+    match encoding {
+        AmoEncoding::Pairwise => pairwise(solver, lits),
+        AmoEncoding::Sequential => sequential(solver, lits, &mut aux),
+        AmoEncoding::Commander => commander(solver, lits, &mut aux),
+    }
+    aux
+}
+
+// Encode "at most 'k' of 'lits' are true" with sequential counter. Returns auxiliary variables
+// allocated for the encoding.
+pub fn at_most_k<S: Solver>(solver: &mut S, lits: &[Lit], k: usize) -> Vec<Var> {
+    let mut aux = Vec::new();
+    let n = lits.len();
+    if k >= n {
+        return aux;
+    }
+
+    if k == 0 {
+        for &x in lits {
+            solver.add_clause(&[!x]);
+        }
+        return aux;
+    }
+
+    // 's[i][j]' is true if at least 'j + 1' of the first 'i + 1' literals are true:
+    let s: Vec<Vec<Lit>> = (0..n - 1)
+        .map(|_| (0..k).map(|_| new_aux(solver, &mut aux)).collect())
+        .collect();
+
+    solver.add_clause(&[!lits[0], s[0][0]]);
+    for &s0j in &s[0][1..] {
+        solver.add_clause(&[!s0j]);
+    }
+
+    for i in 1..n - 1 {
+        solver.add_clause(&[!lits[i], s[i][0]]);
+        solver.add_clause(&[!s[i - 1][0], s[i][0]]);
+        for j in 1..k {
+            solver.add_clause(&[!lits[i], !s[i - 1][j - 1], s[i][j]]);
+            solver.add_clause(&[!s[i - 1][j], s[i][j]]);
+        }
+        solver.add_clause(&[!lits[i], !s[i - 1][k - 1]]);
+    }
+
+    solver.add_clause(&[!lits[n - 1], !s[n - 2][k - 1]]);
+    aux
+}
+
+// Encode "xor of 'lits' equals 'rhs'". Up to 5 literals it is expanded directly into 2^(n-1)
+// clauses of length n, each forbidding one assignment with wrong parity. Longer constraints are
+// cut into a chain: every 4 literals are replaced by a fresh auxiliary variable equal to their
+// xor, so n literals cost about n/3 auxiliary variables and 16 * n/3 clauses. Returns auxiliary
+// variables allocated for the encoding.
+pub fn add_xor<S: Solver>(solver: &mut S, lits: &[Lit], rhs: bool) -> Vec<Var> {
+    let mut aux = Vec::new();
+    let mut rest = lits.to_vec();
+    while rest.len() > XOR_DIRECT_LIMIT {
+        let t = new_aux(solver, &mut aux);
+        let mut chunk: Vec<Lit> = rest.drain(..XOR_DIRECT_LIMIT - 1).collect();
+        chunk.push(t);
+        xor_direct(solver, &chunk, false);
+        rest.push(t);
+    }
+
+    xor_direct(solver, &rest, rhs);
+    aux
+}
+
+// Encode "a <-> b".
+pub fn add_iff<S: Solver>(solver: &mut S, a: Lit, b: Lit) {
+    solver.add_clause(&[!a, b]);
+    solver.add_clause(&[a, !b]);
+}
+
+// Encode "out <-> (if c then t else e)". Two redundant clauses are added to let propagation
+// derive 'out' when 't' and 'e' agree.
+pub fn add_ite<S: Solver>(solver: &mut S, c: Lit, t: Lit, e: Lit, out: Lit) {
+    solver.add_clause(&[!c, !t, out]);
+    solver.add_clause(&[!c, t, !out]);
+    solver.add_clause(&[c, !e, out]);
+    solver.add_clause(&[c, e, !out]);
+    solver.add_clause(&[!t, !e, out]);
+    solver.add_clause(&[t, e, !out]);
+}
+
+
+fn xor_direct<S: Solver>(solver: &mut S, lits: &[Lit], rhs: bool) {
+    let mut clause = Vec::with_capacity(lits.len());
+    for mask in 0u32..(1 << lits.len()) {
+        // Forbid assignment 'mask' if it has the wrong parity:
+        if (mask.count_ones() % 2 == 1) != rhs {
+            clause.clear();
+            for (i, &x) in lits.iter().enumerate() {
+                clause.push(if (mask >> i) & 1 == 1 { !x } else { x });
+            }
+            solver.add_clause(&clause);
+        }
+    }
+}
+
+fn new_aux<S: Solver>(solver: &mut S, aux: &mut Vec<Var>) -> Lit {
+    let v = solver.new_var(None, true);
+    aux.push(v);
+    v.pos_lit()
+}
+
+fn pairwise<S: Solver>(solver: &mut S, lits: &[Lit]) {
+    for (i, &x) in lits.iter().enumerate() {
+        for &y in &lits[i + 1..] {
+            solver.add_clause(&[!x, !y]);
+        }
+    }
+}
+
+fn sequential<S: Solver>(solver: &mut S, lits: &[Lit], aux: &mut Vec<Var>) {
+    if lits.len() < 2 {
+        return;
+    }
+
+    let mut prev = new_aux(solver, aux);
+    solver.add_clause(&[!lits[0], prev]);
+    for &x in &lits[1..lits.len() - 1] {
+        let next = new_aux(solver, aux);
+        solver.add_clause(&[!x, next]);
+        solver.add_clause(&[!prev, next]);
+        solver.add_clause(&[!x, !prev]);
+        prev = next;
+    }
+    solver.add_clause(&[!lits[lits.len() - 1], !prev]);
+}
+
+fn commander<S: Solver>(solver: &mut S, lits: &[Lit], aux: &mut Vec<Var>) {
+    if lits.len() <= COMMANDER_GROUP + 1 {
+        pairwise(solver, lits);
+        return;
+    }
+
+    let mut commanders = Vec::with_capacity(lits.len() / COMMANDER_GROUP + 1);
+    for group in lits.chunks(COMMANDER_GROUP) {
+        let c = new_aux(solver, aux);
+        commanders.push(c);
+
+        // At most one literal of the group is true, and exactly when the commander is:
+        pairwise(solver, group);
+        let mut clause = Vec::with_capacity(group.len() + 1);
+        clause.push(!c);
+        for &x in group {
+            clause.push(x);
+            solver.add_clause(&[!x, c]);
+        }
+        solver.add_clause(&clause);
+    }
+
+    commander(solver, &commanders, aux);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::{minisat, SolveRes};
+    use crate::sat::minisat::budget::Budget;
+
+    // Fix primary variables to the bits of 'mask' and check whether the encoding admits it.
+    fn admits<F: Fn(&mut minisat::CoreSolver, &[Lit])>(n: usize, mask: u32, encode: F) -> bool {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let lits: Vec<Lit> = (0..n).map(|_| solver.new_var(None, true).pos_lit()).collect();
+        encode(&mut solver, &lits);
+        for (i, &x) in lits.iter().enumerate() {
+            solver.add_clause(&[if (mask >> i) & 1 == 1 { x } else { !x }]);
+        }
+
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(_, _) => true,
+            SolveRes::UnSAT(_) => false,
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+
+    #[test]
+    fn test_at_most_one() {
+        for &encoding in [AmoEncoding::Pairwise, AmoEncoding::Sequential, AmoEncoding::Commander].iter() {
+            for n in 1..8 {
+                for mask in 0..(1u32 << n) {
+                    let admitted = admits(n, mask, |solver, lits| { at_most_one(solver, lits, encoding); });
+                    assert_eq!(admitted, mask.count_ones() <= 1, "{:?} n = {}, mask = {:b}", encoding, n, mask);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_at_most_k() {
+        for n in 1..6 {
+            for k in 0..n + 1 {
+                for mask in 0..(1u32 << n) {
+                    let admitted = admits(n, mask, |solver, lits| { at_most_k(solver, lits, k); });
+                    assert_eq!(admitted, (mask.count_ones() as usize) <= k, "n = {}, k = {}, mask = {:b}", n, k, mask);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_xor() {
+        for n in 0..9 {
+            for &rhs in [false, true].iter() {
+                for mask in 0..(1u32 << n) {
+                    let admitted = admits(n, mask, |solver, lits| { add_xor(solver, lits, rhs); });
+                    assert_eq!(admitted, (mask.count_ones() % 2 == 1) == rhs, "n = {}, rhs = {}, mask = {:b}", n, rhs, mask);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ite() {
+        for mask in 0..16u32 {
+            let admitted = admits(4, mask, |solver, lits| { add_ite(solver, lits[0], lits[1], lits[2], lits[3]); });
+            let bit = |i: u32| (mask >> i) & 1 == 1;
+            assert_eq!(admitted, bit(3) == if bit(0) { bit(1) } else { bit(2) }, "mask = {:b}", mask);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt, mem, ops};
+use core::{fmt, mem, ops};
 pub use self::index_map::*;
 
 mod allocator;
@@ -113,6 +113,18 @@ impl Lit {
     fn var_index(self) -> usize {
         (self.0 >> 1) as usize
     }
+
+
+    // Raw encoding for serialization: variable index in the upper bits, sign in the lowest one.
+    #[inline]
+    pub fn code(self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub(crate) fn from_code(code: u32) -> Lit {
+        Lit(code)
+    }
 }
 
 impl ops::Not for Lit {
@@ -134,6 +146,9 @@ impl fmt::Debug for Lit {
 }
 
 
+// Literals keep the sign in the lowest bit of 'u32', so variable indices have one bit less:
+pub const MAX_VAR_INDEX: usize = 0x7FFFFFFF;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
 pub struct Var(u32);
 
@@ -160,11 +175,11 @@ impl Var {
     }
 
     #[inline]
-    fn from_index(index: usize) -> Var {
-        if index <= 0x7FFFFFFF {
+    pub fn from_index(index: usize) -> Var {
+        if index <= MAX_VAR_INDEX {
             Var(index as u32)
         } else {
-            panic!("Var index {} is out of bound", index)
+            panic!("Var index {} exceeds the limit of {}", index, MAX_VAR_INDEX)
         }
     }
 }
@@ -223,6 +238,21 @@ mod tests {
         assert_ne!(pv, nv);
     }
 
+    #[test]
+    fn test_max_var() {
+        let v = Var::from_index(MAX_VAR_INDEX);
+        assert_eq!(v.pos_lit().var(), v);
+        assert_eq!(v.neg_lit().var(), v);
+        assert!(!v.pos_lit().sign());
+        assert!(v.neg_lit().sign());
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the limit")]
+    fn test_var_overflow() {
+        Var::from_index(MAX_VAR_INDEX + 1);
+    }
+
     #[test]
     fn test_not() {
         assert_eq!(!LBool::True, LBool::False);
@@ -1,4 +1,5 @@
-use std::{alloc, mem, ptr};
+use core::{mem, ptr};
+use alloc::alloc;
 
 
 pub type Ref = u32;
@@ -10,6 +11,9 @@ pub struct RegionAllocator {
     align: usize
 }
 
+// The region is owned exclusively, like the buffer of a 'Vec', so it can move to another thread:
+unsafe impl Send for RegionAllocator {}
+
 impl RegionAllocator {
     pub fn with_capacity(capacity: usize, align: usize) -> Self {
         let mut ra = RegionAllocator {
@@ -100,6 +104,35 @@ impl RegionAllocator {
     pub fn allocated_bytes(&self) -> usize {
         self.offset
     }
+
+    // Skip to the next multiple of 'line' (relative to the region, which is expected to be aligned
+    // to it) unless 'size' bytes still fit before it.
+    pub fn pad_to_fit(&mut self, size: usize, line: usize) {
+        let used = self.offset % line;
+        if used != 0 && used + size > line {
+            self.offset += line - used;
+        }
+    }
+}
+
+// Copies are laid out exactly as the original, so references stay valid in both of them.
+impl Clone for RegionAllocator {
+    fn clone(&self) -> Self {
+        let mut ra = RegionAllocator {
+            memory: ptr::null_mut(),
+            offset: 0,
+            capacity: 0,
+            align: self.align
+        };
+        unsafe {
+            ra.ensure_capacity(self.capacity);
+            if self.offset > 0 {
+                ptr::copy_nonoverlapping(self.memory, ra.memory, self.offset);
+            }
+        }
+        ra.offset = self.offset;
+        ra
+    }
 }
 
 impl Drop for RegionAllocator {
@@ -1,5 +1,7 @@
-use std::{cmp, fmt};
-use super::{clause::*, LBool, Lit, Var};
+use core::{cmp, fmt};
+use alloc::vec;
+use alloc::vec::Vec;
+use super::{clause::*, Idx, LBool, Lit, Var};
 
 
 type LevelIndex = usize;
@@ -9,6 +11,18 @@ pub struct DecisionLevel(LevelIndex);
 
 pub const GROUND_LEVEL: DecisionLevel = DecisionLevel(1);
 
+impl Idx for DecisionLevel {
+    #[inline]
+    fn idx(&self) -> usize {
+        self.0
+    }
+
+    #[inline]
+    fn unidx(idx: usize) -> DecisionLevel {
+        DecisionLevel(idx)
+    }
+}
+
 impl DecisionLevel {
     pub fn is_ground(&self) -> bool {
         self.0 == 1
@@ -17,20 +31,50 @@ impl DecisionLevel {
     pub fn offset_from_ground(&self) -> usize {
         (self.0 - 1) as usize
     }
+
+    pub fn below(&self) -> DecisionLevel {
+        assert!(!self.is_ground());
+        DecisionLevel(self.0 - 1)
+    }
+}
+
+
+// Decision level and trail length at some point of search, to come back to with 'restore':
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Checkpoint {
+    level: DecisionLevel,
+    opened: u64, // Which opening of 'level' it was taken at (see 'Assignment::opened').
+}
+
+impl Checkpoint {
+    pub fn level(&self) -> DecisionLevel {
+        self.level
+    }
+}
+
+// Reasons for 'restore' to reject a checkpoint:
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum CheckpointError {
+    AboveLevel(DecisionLevel), // Taken at a level above the current one.
+    Undone,                    // Some of its levels were undone since it was taken.
 }
 
 
+#[derive(Clone)]
 pub struct VarData {
     pub reason: Option<ClauseRef>,
     pub level: DecisionLevel,
 }
 
+#[derive(Clone)]
 pub struct Assignment {
     assign: Vec<LBool>,
     vd: Vec<VarData>,
     free_vars: Vec<Var>,
     trail: Vec<Lit>,
     lim: Vec<usize>,
+    opened: Vec<u64>, // Number of the 'new_decision_level' call that opened every level, 0 for the ground one.
+    levels_opened: u64,
     qhead: usize,
 }
 
@@ -42,11 +86,20 @@ impl Assignment {
             free_vars: Vec::new(),
             trail: Vec::new(),
             lim: vec![0],
+            opened: vec![0],
+            levels_opened: 0,
             qhead: 0,
         }
     }
 
 
+    // Room for 'n' more variables, so that 'new_var' does not reallocate:
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.assign.reserve(n);
+        self.vd.reserve(n);
+        self.trail.reserve(n);
+    }
+
     pub fn new_var(&mut self) -> Var {
         let vd = VarData { reason: None, level: GROUND_LEVEL };
         match self.free_vars.pop() {
@@ -98,6 +151,8 @@ impl Assignment {
     #[inline]
     pub fn new_decision_level(&mut self) {
         self.lim.push(self.trail.len());
+        self.levels_opened += 1;
+        self.opened.push(self.levels_opened);
     }
 
     #[inline]
@@ -112,6 +167,30 @@ impl Assignment {
         }
     }
 
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { level: self.current_level(), opened: self.opened[self.lim.len() - 1] }
+    }
+
+    // Backtrack to the level of 'cp'. The levels up to it must be the ones it was taken at: none of
+    // them was undone since, even to be decided again the same way. Otherwise nothing is changed.
+    pub fn restore(&mut self, cp: Checkpoint) -> Result<(), CheckpointError> {
+        self.check_checkpoint(cp)?;
+        self.backtrack_to(cp.level);
+        Ok(())
+    }
+
+    pub fn check_checkpoint(&self, cp: Checkpoint) -> Result<(), CheckpointError> {
+        if cp.level > self.current_level() {
+            return Err(CheckpointError::AboveLevel(cp.level));
+        }
+
+        // Undoing a level undoes all above it, so it's enough to check the level of 'cp':
+        if self.opened[cp.level.0 - 1] != cp.opened {
+            return Err(CheckpointError::Undone);
+        }
+        Ok(())
+    }
+
     // Revert to the state at given level (keeping all assignment at 'level' but not beyond).
     pub fn backtrack_to(&mut self, target_level: DecisionLevel) {
         if let Some(&target) = self.lim.get(target_level.0) {
@@ -124,6 +203,7 @@ impl Assignment {
 
             self.trail.truncate(target);
             self.lim.truncate(target_level.0);
+            self.opened.truncate(target_level.0);
         }
 
         self.qhead = cmp::min(self.qhead, self.trail.len());
@@ -175,6 +255,39 @@ impl Assignment {
         }
     }
 
+    /// Iterate over all decision levels (starting from the ground one) with literals assigned at
+    /// each of them. The first literal of every non-ground level is the decision, the rest are
+    /// propagated literals (or dummy levels created for already satisfied assumptions).
+    ///
+    /// ```
+    /// use minisat_rust::sat::{minisat, SolveRes, Solver};
+    /// use minisat_rust::sat::minisat::budget::Budget;
+    ///
+    /// let mut solver = minisat::CoreSolver::new(Default::default());
+    /// let a = solver.new_var(None, true);
+    /// let b = solver.new_var(None, true);
+    /// solver.add_clause(&[a.pos_lit()]);
+    /// solver.add_clause(&[a.neg_lit(), b.pos_lit()]);
+    ///
+    /// let mut budget = Budget::new();
+    /// budget.set_conflict_budget(0);
+    /// match solver.solve_limited(&budget, &[]) {
+    ///     SolveRes::Interrupted(_, solver) => {
+    ///         for (level, lits) in solver.assignment().iter_levels() {
+    ///             println!("{:?}: {:?}", level, lits);
+    ///         }
+    ///         let (level, lits) = solver.assignment().iter_levels().next().unwrap();
+    ///         assert!(level.is_ground());
+    ///         assert_eq!(lits, &[a.pos_lit(), b.pos_lit()]);
+    ///     }
+    ///     _ => panic!("Expected interrupted search"),
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter_levels(&self) -> DirIter<'_> {
+        self.all_levels_dir()
+    }
+
     #[inline]
     pub fn levels_above_rev(&self, target_level: DecisionLevel) -> RevIter {
         RevIter {
@@ -214,6 +327,51 @@ impl Assignment {
     }
 
 
+    // Value of 'p' taking into account only assignments made at 'level' or below.
+    pub fn value_at_level(&self, p: Lit, level: DecisionLevel) -> LBool {
+        let value = self.of_lit(p);
+        if value.is_undef() || self.vd[p.var_index()].level > level {
+            LBool::Undef
+        } else {
+            value
+        }
+    }
+
+
+    pub fn vars(&self) -> impl Iterator<Item = Var> {
+        (0..self.assign.len()).map(Var::from_index)
+    }
+
+    // Every variable with its value at ground level, 'Undef' if it is unassigned or assigned above.
+    pub fn ground_values(&self) -> impl Iterator<Item = (Var, LBool)> + '_ {
+        self.vars().map(move |v| (v, self.value_at_level(v.pos_lit(), GROUND_LEVEL)))
+    }
+
+    // Store assigned literals ordered by variable into 'model', reusing its allocation.
+    pub fn extract_model_into(&self, model: &mut Vec<Lit>) {
+        model.clear();
+        model.extend(self.model_view());
+    }
+
+    // Lazy view of the model: assigned literals ordered by variable, computed on demand.
+    pub fn model_view(&self) -> impl Iterator<Item = Lit> + '_ {
+        self.assign.iter().enumerate().filter_map(|(i, &value)| match value {
+            LBool::True => Some(Var::from_index(i).pos_lit()),
+            LBool::False => Some(Var::from_index(i).neg_lit()),
+            LBool::Undef => None,
+        })
+    }
+
+    pub fn pack_into(&self, packed: &mut PackedAssignment) {
+        packed.words.clear();
+        packed.words.resize(self.assign.len().div_ceil(PACKED_PER_WORD), 0);
+        packed.len = self.assign.len();
+        for (i, &value) in self.assign.iter().enumerate() {
+            packed.words[i / PACKED_PER_WORD] |= (value as u64) << (2 * (i % PACKED_PER_WORD));
+        }
+    }
+
+
     #[inline]
     pub fn vardata(&self, lit: Lit) -> &VarData {
         unsafe {
@@ -232,6 +390,59 @@ impl Assignment {
         }
     }
 
+    // Literals of the clause that forced 'v'. 'None' for unassigned variables, decisions, assumptions
+    // and variables whose reason was removed from the clause database since.
+    pub fn reason_of<'c>(&self, ca: &'c ClauseAllocator, v: Var) -> Option<&'c [Lit]> {
+        if v.index() >= self.assign.len() || self.is_undef(v) {
+            return None;
+        }
+
+        self.vd[v.index()].reason
+            .filter(|&cr| !ca.is_deleted(cr))
+            .map(|cr| ca.literals(cr))
+    }
+
+    // None of 'clauses' is falsified, and every literal assigned with a reason is implied by it
+    // through literals assigned earlier. Only decisions and ground level literals may have no
+    // reason, and ground level reasons may have been removed since.
+    #[cfg(debug_assertions)]
+    pub fn is_consistent<I: IntoIterator<Item = ClauseRef>>(&self, ca: &ClauseAllocator, clauses: I) -> bool {
+        let mut pos = vec![usize::MAX; self.assign.len()];
+        for (i, lit) in self.trail.iter().enumerate() {
+            pos[lit.var_index()] = i;
+        }
+
+        for (i, &lit) in self.trail.iter().enumerate() {
+            let vd = &self.vd[lit.var_index()];
+            if !self.is_assigned_pos(lit) {
+                return false;
+            }
+
+            match vd.reason.filter(|&cr| !vd.level.is_ground() || !ca.is_deleted(cr)) {
+                None => {
+                    let decision = i == 0 || self.vd[self.trail[i - 1].var_index()].level != vd.level;
+                    if !decision && !vd.level.is_ground() {
+                        return false;
+                    }
+                }
+
+                Some(cr) => {
+                    let c = ca.view(cr);
+                    if c.is_deleted() || !c.lits().contains(&lit) {
+                        return false;
+                    }
+                    let implied = c.lits().iter()
+                        .filter(|&&l| l != lit)
+                        .all(|&l| self.is_assigned_neg(l) && pos[l.var_index()] < i);
+                    if !implied {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        clauses.into_iter().all(|cr| !ca.view(cr).lits().iter().all(|&lit| self.is_assigned_neg(lit)))
+    }
 
     pub fn gc(&mut self, gc: &mut ClauseGC) {
         unsafe {
@@ -263,6 +474,44 @@ impl fmt::Debug for Assignment {
 }
 
 
+const PACKED_PER_WORD: usize = 32;
+
+// Snapshot of variable values with two bits per variable.
+#[derive(Default)]
+pub struct PackedAssignment {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl PackedAssignment {
+    pub fn new() -> PackedAssignment {
+        PackedAssignment::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn of_var(&self, v: Var) -> LBool {
+        let i = v.index();
+        assert!(i < self.len);
+        match (self.words[i / PACKED_PER_WORD] >> (2 * (i % PACKED_PER_WORD))) & 3 {
+            0 => LBool::False,
+            1 => LBool::True,
+            _ => LBool::Undef,
+        }
+    }
+
+    pub fn of_lit(&self, p: Lit) -> LBool {
+        p.apply_sign(self.of_var(p.var()))
+    }
+}
+
+
 pub struct DirIter<'a> {
     assign: &'a Assignment,
     target: usize,
@@ -319,3 +568,72 @@ impl<'a> Iterator for RevIter<'a> {
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::formula::util;
+
+    #[test]
+    fn test_packed_model() {
+        let mut assigns = Assignment::new();
+        let vars: Vec<Var> = (0..100).map(|_| assigns.new_var()).collect();
+        for (i, &v) in vars.iter().enumerate() {
+            if i % 3 != 2 {
+                assigns.assign_lit(v.sign_lit(i % 3 == 1), None);
+            }
+        }
+
+        let expected: Vec<Lit> = util::extract_model(&assigns).iter().map(|(v, s)| v.sign_lit(!*s)).collect();
+
+        let mut model = vec![vars[0].pos_lit(); 5];
+        assigns.extract_model_into(&mut model);
+        assert_eq!(model, expected);
+        assert_eq!(assigns.model_view().collect::<Vec<_>>(), expected);
+
+        let mut packed = PackedAssignment::new();
+        assigns.pack_into(&mut packed);
+        assert_eq!(packed.len(), vars.len());
+        for &v in vars.iter() {
+            assert_eq!(packed.of_var(v), assigns.assign[v.index()]);
+            assert_eq!(packed.of_lit(v.neg_lit()), assigns.of_lit(v.neg_lit()));
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_is_consistent() {
+        let mut ca = ClauseAllocator::with_capacity(1024);
+        let mut assigns = Assignment::new();
+        let [a, b, c] = [0, 1, 2].map(|_| assigns.new_var().pos_lit());
+        let (_, ab) = ca.alloc(&[b, !a], ClauseHeader::Clause { abstraction: None });
+        let (_, bc) = ca.alloc(&[b, c], ClauseHeader::Clause { abstraction: None });
+
+        // Decision 'a' propagates 'b':
+        assigns.new_decision_level();
+        assigns.assign_lit(a, None);
+        assigns.assign_lit(b, Some(ab));
+        assert!(assigns.is_consistent(&ca, vec![ab, bc]));
+
+        // A reason that doesn't imply its literal:
+        let mut broken = assigns.clone();
+        broken.assign_lit(!c, Some(bc));
+        assert!(!broken.is_consistent(&ca, vec![ab, bc]));
+
+        // A literal above ground level that is neither a decision nor propagated:
+        let mut broken = assigns.clone();
+        broken.assign_lit(c, None);
+        assert!(!broken.is_consistent(&ca, vec![ab, bc]));
+
+        // A falsified clause, as if elimination dropped a literal it shouldn't have:
+        let mut broken = Assignment::new();
+        for _ in 0..3 {
+            broken.new_var();
+        }
+        broken.assign_lit(!b, None);
+        broken.assign_lit(!c, None);
+        assert!(broken.is_consistent(&ca, vec![ab]));
+        assert!(!broken.is_consistent(&ca, vec![ab, bc]));
+    }
+}
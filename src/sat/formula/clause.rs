@@ -1,4 +1,5 @@
-use std::{fmt, mem, ptr, slice};
+use core::{fmt, mem, ptr, slice};
+use alloc::vec::Vec;
 use super::{allocator, Lit};
 pub use super::clause_header::*;
 
@@ -11,7 +12,11 @@ const FLAG_RELOCED : u32 = 0x08;
 pub const MIN_CLAUSE_SIZE : usize = 2;
 pub const MAX_CLAUSE_SIZE : usize = 0xFFFFFFFF >> FLAG_BITS;
 
+const CACHE_LINE : usize = 64;
 
+
+// Literals past 'prefix' are allocated right after it, so the field order has to stay as written.
+#[repr(C)]
 pub struct Clause {
     mark: u32,
     pub header: ClauseHeader,
@@ -35,6 +40,12 @@ impl Clause {
         unsafe { slice::from_raw_parts(self.prefix.as_ptr(), self.len()) }
     }
 
+    // All literals but the first one, i.e. the antecedents when the clause is a reason:
+    #[inline]
+    pub fn tail(&self) -> &[Lit] {
+        self.lits().split_first().map_or(&[], |(_, tail)| tail)
+    }
+
     #[inline]
     pub fn lits_mut(&mut self) -> &mut [Lit] {
         unsafe { slice::from_raw_parts_mut(self.prefix.as_mut_ptr(), self.len()) }
@@ -88,31 +99,109 @@ impl fmt::Debug for Clause {
 }
 
 
+/// Read-only access to a clause stored in a solver.
+///
+/// ```
+/// use minisat_rust::sat::{minisat, Solver};
+///
+/// let mut solver = minisat::CoreSolver::new(Default::default());
+/// let (a, b, c) = (solver.new_var(None, true), solver.new_var(None, true), solver.new_var(None, true));
+/// let clause = [a.pos_lit(), b.neg_lit(), c.pos_lit()];
+/// match solver.add_clause_res(&clause) {
+///     minisat::AddClauseRes::Added(cr) => {
+///         let view = solver.clause(cr).unwrap();
+///         assert_eq!(view.len(), 3);
+///         assert!(!view.is_learnt());
+///         for lit in view.lits() {
+///             assert!(clause.contains(lit));
+///         }
+///     }
+///     _ => panic!("Expected a stored clause"),
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ClauseView<'a> {
+    clause: &'a Clause,
+    fixed: bool, // Whether the activity is a fixed-point number (see 'ClauseHeader').
+}
+
+impl<'a> ClauseView<'a> {
+    pub fn new(clause: &'a Clause, fixed: bool) -> Self {
+        ClauseView { clause, fixed }
+    }
+
+    pub fn lits(&self) -> &'a [Lit] {
+        self.clause.lits()
+    }
+
+    pub fn len(&self) -> usize {
+        self.clause.len()
+    }
+
+    // Never true, stored clauses have at least 'MIN_CLAUSE_SIZE' literals.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_learnt(&self) -> bool {
+        matches!(self.clause.header, ClauseHeader::Learnt { .. })
+    }
+
+    // Activity and LBD are kept for learnt clauses only:
+    pub fn activity(&self) -> Option<f32> {
+        if self.is_learnt() { Some(self.clause.header.activity(self.fixed) as f32) } else { None }
+    }
+
+    pub fn lbd(&self) -> Option<u32> {
+        if self.is_learnt() { Some(self.clause.header.lbd()) } else { None }
+    }
+}
+
+impl fmt::Debug for ClauseView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.clause.fmt(f)
+    }
+}
+
+
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(transparent)]
 pub struct ClauseRef(allocator::Ref);
 
 
+#[derive(Clone)]
 pub struct ClauseAllocator {
     ra: allocator::RegionAllocator,
     lc: LegacyCounter,
-    pub extra_clause_field: bool
+    starts: Vec<u64>,      // Bit per 'Clause' alignment unit of the region, set where a clause begins.
+    gc_runs: u64,          // Number of garbage collections so far.
+    gc_reclaimed: usize,   // Bytes reclaimed by all garbage collections so far.
+    pub extra_clause_field: bool,
+    pub pack_lines: bool,  // Never split a clause header and its two watched literals between cache lines.
 }
 
 impl ClauseAllocator {
     pub fn with_capacity(capacity: usize) -> ClauseAllocator {
         ClauseAllocator {
-            ra: allocator::RegionAllocator::with_capacity(capacity, 16),
+            ra: allocator::RegionAllocator::with_capacity(capacity, CACHE_LINE),
             lc: LegacyCounter::new(),
+            starts: Vec::new(),
+            gc_runs: 0,
+            gc_reclaimed: 0,
             extra_clause_field: false,
+            pack_lines: false,
         }
     }
 
     pub fn gc(&mut self) -> ClauseGC {
         let dst = ClauseAllocator {
-            ra: allocator::RegionAllocator::with_capacity(self.lc.size - self.lc.wasted, 16),
+            ra: allocator::RegionAllocator::with_capacity(self.lc.size - self.lc.wasted, CACHE_LINE),
             lc: LegacyCounter::new(),
+            starts: Vec::with_capacity(self.starts.len()),
+            gc_runs: self.gc_runs + 1,
+            gc_reclaimed: self.gc_reclaimed,
             extra_clause_field: self.extra_clause_field,
+            pack_lines: self.pack_lines,
         };
         ClauseGC { src: self, dst }
     }
@@ -121,6 +210,7 @@ impl ClauseAllocator {
         let len = literals.len();
         assert!(len >= MIN_CLAUSE_SIZE);
         assert!(len <= MAX_CLAUSE_SIZE);
+        self.pad();
         unsafe {
             let (clause, cref) = self.ra.allocate_with_extra::<Clause, Lit>(len - MIN_CLAUSE_SIZE);
 
@@ -129,6 +219,7 @@ impl ClauseAllocator {
             ptr::copy_nonoverlapping(literals.as_ptr(), clause.prefix.as_mut_ptr(), len);
 
             self.lc.add(clause);
+            mark_start(&mut self.starts, cref);
             (clause, ClauseRef(cref))
         }
     }
@@ -136,6 +227,7 @@ impl ClauseAllocator {
     fn reloc(&mut self, src: &Clause) -> ClauseRef {
         let len = src.len();
         assert!(len >= MIN_CLAUSE_SIZE);
+        self.pad();
         unsafe {
             let (clause, cref) = self.ra.allocate_with_extra::<Clause, Lit>(len - MIN_CLAUSE_SIZE);
 
@@ -150,10 +242,18 @@ impl ClauseAllocator {
             }
 
             self.lc.add(clause);
+            mark_start(&mut self.starts, cref);
             ClauseRef(cref)
         }
     }
 
+    // 'Clause' itself is the header followed by the first two (watched) literals:
+    fn pad(&mut self) {
+        if self.pack_lines {
+            self.ra.pad_to_fit(mem::size_of::<Clause>(), CACHE_LINE);
+        }
+    }
+
     pub fn free(&mut self, cref: ClauseRef) {
         let clause = unsafe { self.ra.get_mut::<Clause>(cref.0) };
         assert!(!clause.is_deleted());
@@ -166,6 +266,23 @@ impl ClauseAllocator {
         (self.lc.wasted as f64) > (self.lc.size as f64) * gf
     }
 
+    pub fn allocated_bytes(&self) -> usize {
+        self.ra.allocated_bytes()
+    }
+
+    // Size of live and deleted clauses, and of deleted ones only, as weighed by 'check_garbage':
+    pub fn usage(&self) -> (usize, usize) {
+        (self.lc.size, self.lc.wasted)
+    }
+
+    pub fn gc_runs(&self) -> u64 {
+        self.gc_runs
+    }
+
+    pub fn gc_reclaimed(&self) -> usize {
+        self.gc_reclaimed
+    }
+
 
     #[inline]
     pub fn view(&self, cref: ClauseRef) -> &Clause {
@@ -186,6 +303,24 @@ impl ClauseAllocator {
     pub fn is_deleted(&self, cref: ClauseRef) -> bool {
         self.view(cref).is_deleted()
     }
+
+    // Whether a clause (maybe deleted) begins at 'cref', for references that may be stale. It may
+    // be another clause than the one referenced if a garbage collection ran since.
+    pub fn is_clause(&self, cref: ClauseRef) -> bool {
+        let align = mem::align_of::<Clause>();
+        let unit = cref.0 as usize / align;
+        cref.0 as usize % align == 0
+            && self.starts.get(unit / 64).is_some_and(|bits| bits & (1 << (unit % 64)) != 0)
+    }
+}
+
+
+fn mark_start(starts: &mut Vec<u64>, cref: allocator::Ref) {
+    let unit = cref as usize / mem::align_of::<Clause>();
+    if starts.len() <= unit / 64 {
+        starts.resize(unit / 64 + 1, 0);
+    }
+    starts[unit / 64] |= 1 << (unit % 64);
 }
 
 
@@ -196,17 +331,23 @@ pub struct ClauseGC<'a> {
 
 impl Drop for ClauseGC<'_> {
     fn drop(&mut self) {
-        debug!("|  Garbage collection:   {:12} bytes => {:12} bytes ({} -> {})            |",
+        debug!(target: "minisat::gc", "|  Garbage collection:   {:12} bytes => {:12} bytes ({} -> {})            |",
             self.src.lc.size,
             self.dst.lc.size,
             self.src.ra.allocated_bytes(),
             self.dst.ra.allocated_bytes()
         );
+        self.dst.gc_reclaimed += self.src.ra.allocated_bytes().saturating_sub(self.dst.ra.allocated_bytes());
         mem::swap(self.src, &mut self.dst);
     }
 }
 
 impl<'a> ClauseGC<'a> {
+    // Clause at its place before collection.
+    pub fn view(&self, cref: ClauseRef) -> &Clause {
+        self.src.view(cref)
+    }
+
     pub fn relocate(&mut self, cref: ClauseRef) -> Option<ClauseRef> {
         let c = self.src.edit(cref);
         if c.is_deleted() {
@@ -223,6 +364,7 @@ impl<'a> ClauseGC<'a> {
 }
 
 
+#[derive(Clone)]
 struct LegacyCounter {
     size: usize,
     wasted: usize,
@@ -249,3 +391,42 @@ impl LegacyCounter {
         self.wasted += Self::clause_size(clause);
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::formula::Var;
+
+    #[test]
+    fn test_pack_lines() {
+        let lits: Vec<Lit> = (0..40).map(|i| Var::from_index(i).pos_lit()).collect();
+        let mut ca = ClauseAllocator::with_capacity(1024);
+        ca.pack_lines = true;
+
+        let crs: Vec<ClauseRef> = (0..64).map(|i| ca.alloc(&lits[i % 7..(i % 7) + 2 + i % 5], ClauseHeader::Clause { abstraction: None }).1).collect();
+        let on_one_line = |cr: ClauseRef| (cr.0 as usize) % CACHE_LINE + mem::size_of::<Clause>() <= CACHE_LINE;
+        assert!(crs.iter().all(|&cr| on_one_line(cr)));
+
+        // Collection keeps the contents and the packing:
+        let before: Vec<Vec<Lit>> = crs.iter().map(|&cr| ca.literals(cr).to_vec()).collect();
+        assert!(crs.iter().all(|&cr| ca.is_clause(cr)));
+        assert!(!ca.is_clause(ClauseRef(crs[1].0 + 4)) && !ca.is_clause(ClauseRef(crs[1].0 + 1)));
+        assert!(!ca.is_clause(ClauseRef(ca.allocated_bytes() as u32 + 64)));
+        ca.free(crs[3]);
+        let crs: Vec<Option<ClauseRef>> = {
+            let mut gc = ca.gc();
+            crs.iter().map(|&cr| gc.relocate(cr)).collect()
+        };
+        for (i, cr) in crs.into_iter().enumerate() {
+            match cr {
+                None => assert_eq!(i, 3),
+                Some(cr) => {
+                    assert!(on_one_line(cr));
+                    assert!(ca.is_clause(cr));
+                    assert_eq!(ca.literals(cr), &before[i][..]);
+                }
+            }
+        }
+    }
+}
@@ -1,18 +1,79 @@
-use std::num;
+use core::num;
 
 
+// Two words, as in the original: the 'lbd' word of learnt clauses is never zero, which leaves zero
+// to tell other clauses apart, and these need no tag word of their own (see 'test_header_size').
 #[derive(Clone, Copy)]
 pub enum ClauseHeader {
     Clause { abstraction: Option<num::NonZeroU32> },
-    Learnt { activity: f32 }
+    // 'activity': bits of an 'f32', or a fixed-point number of 'FIXED_ACTIVITY_ONE' units if the
+    // clause database uses fixed-point activities.
+    // 'lbd': one more than the number of distinct decision levels when learnt, with 'PROTECTED_BIT'
+    // set for clauses that are never removed.
+    Learnt { activity: u32, lbd: num::NonZeroU32 }
 }
 
+// Fixed-point clause activity of 1.0, the initial increment:
+pub const FIXED_ACTIVITY_ONE: u32 = 1 << 10;
+
+const PROTECTED_BIT: u32 = 1 << 31;
+
 impl ClauseHeader {
-    pub fn activity(&self) -> f32 {
-        if let ClauseHeader::Learnt { activity } = self {
-            *activity
+    // LBD is capped below 'PROTECTED_BIT', which no clause gets anywhere near.
+    pub fn learnt(lbd: u32, protected: bool) -> Self {
+        let word = lbd.min(PROTECTED_BIT - 2) + 1;
+        let word = if protected { word | PROTECTED_BIT } else { word };
+        ClauseHeader::Learnt { activity: 0, lbd: num::NonZeroU32::new(word).unwrap() }
+    }
+
+    // Fixed-point activities convert exactly, so they compare the same way on every platform.
+    pub fn activity(&self, fixed: bool) -> f64 {
+        if let ClauseHeader::Learnt { activity, .. } = *self {
+            if fixed {
+                activity as f64 / FIXED_ACTIVITY_ONE as f64
+            } else {
+                f32::from_bits(activity) as f64
+            }
+        } else {
+            panic!("Learnt expected");
+        }
+    }
+
+    pub fn lbd(&self) -> u32 {
+        if let ClauseHeader::Learnt { lbd, .. } = self {
+            (lbd.get() & !PROTECTED_BIT) - 1
+        } else {
+            panic!("Learnt expected");
+        }
+    }
+
+    pub fn is_protected(&self) -> bool {
+        if let ClauseHeader::Learnt { lbd, .. } = self {
+            lbd.get() & PROTECTED_BIT != 0
         } else {
             panic!("Learnt expected");
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem;
+
+    #[test]
+    fn test_header_size() {
+        assert_eq!(mem::size_of::<ClauseHeader>(), 2 * mem::size_of::<u32>());
+    }
+
+    #[test]
+    fn test_learnt_packing() {
+        assert_eq!((ClauseHeader::learnt(0, false).lbd(), ClauseHeader::learnt(0, false).is_protected()), (0, false));
+
+        let header = ClauseHeader::learnt(7, true);
+        assert_eq!((header.lbd(), header.is_protected()), (7, true));
+        let header = ClauseHeader::learnt(u32::MAX, false);
+        assert_eq!((header.lbd(), header.is_protected()), (PROTECTED_BIT - 2, false));
+    }
+}
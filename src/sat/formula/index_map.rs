@@ -1,5 +1,5 @@
-use std::{marker, ops, slice};
-use vec_map;
+use core::{iter, marker, mem, ops, slice};
+use alloc::vec::Vec;
 use super::{Lit, Var};
 
 
@@ -23,7 +23,7 @@ impl Idx for Var {
 
     #[inline]
     fn unidx(idx: usize) -> Var {
-        Var(idx as u32)
+        Var::from_index(idx)
     }
 }
 
@@ -40,19 +40,92 @@ impl Idx for Lit {
 }
 
 
+// Dense map from small indices to values, with holes for absent keys.
+#[derive(Clone)]
+struct SlotMap<V> {
+    slots: Vec<Option<V>>,
+}
+
+impl<V> SlotMap<V> {
+    fn new() -> Self {
+        SlotMap { slots: Vec::new() }
+    }
+
+    fn with_capacity(n: usize) -> Self {
+        SlotMap { slots: Vec::with_capacity(n) }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.slots.reserve(additional);
+    }
+
+    #[inline]
+    fn insert(&mut self, idx: usize, v: V) -> Option<V> {
+        if idx >= self.slots.len() {
+            self.slots.resize_with(idx + 1, || None);
+        }
+        self.slots[idx].replace(v)
+    }
+
+    #[inline]
+    fn remove(&mut self, idx: usize) -> Option<V> {
+        self.slots.get_mut(idx).and_then(Option::take)
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> Option<&V> {
+        self.slots.get(idx).and_then(Option::as_ref)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> Option<&mut V> {
+        self.slots.get_mut(idx).and_then(Option::as_mut)
+    }
+
+    #[inline]
+    fn contains_key(&self, idx: usize) -> bool {
+        self.get(idx).is_some()
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.slots.clear();
+    }
+}
+
+
+#[derive(Clone)]
 pub struct IdxMap<K: Idx, V> {
-    map: vec_map::VecMap<V>,
+    map: SlotMap<V>,
     ph: marker::PhantomData<K>,
 }
 
 impl<K: Idx, V> IdxMap<K, V> {
     pub fn new() -> Self {
         IdxMap {
-            map: vec_map::VecMap::new(),
+            map: SlotMap::new(),
+            ph: marker::PhantomData,
+        }
+    }
+
+    // Room for keys with indices below 'n' without reallocating ('2 * n' keys for literals of
+    // 'n' variables):
+    pub fn with_capacity(n: usize) -> Self {
+        IdxMap {
+            map: SlotMap::with_capacity(n),
             ph: marker::PhantomData,
         }
     }
 
+    // Room for 'additional' keys following the largest index so far.
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.map.slots.capacity()
+    }
+
     #[inline]
     pub fn insert(&mut self, k: &K, v: V) -> Option<V> {
         self.map.insert(k.idx(), v)
@@ -71,7 +144,7 @@ impl<K: Idx, V> IdxMap<K, V> {
     #[inline]
     pub fn iter(&self) -> Iter<K, V> {
         Iter {
-            it: self.map.iter(),
+            it: self.map.slots.iter().enumerate(),
             ph: marker::PhantomData,
         }
     }
@@ -79,7 +152,7 @@ impl<K: Idx, V> IdxMap<K, V> {
     #[inline]
     pub fn iter_mut(&mut self) -> IterMut<K, V> {
         IterMut {
-            it: self.map.iter_mut(),
+            it: self.map.slots.iter_mut().enumerate(),
             ph: marker::PhantomData,
         }
     }
@@ -90,20 +163,20 @@ impl<'r, K: Idx, V> ops::Index<&'r K> for IdxMap<K, V> {
 
     #[inline]
     fn index(&self, k: &'r K) -> &V {
-        self.map.index(&k.idx())
+        self.map.get(k.idx()).expect("key not present")
     }
 }
 
 impl<'r, K: Idx, V> ops::IndexMut<&'r K> for IdxMap<K, V> {
     #[inline]
     fn index_mut(&mut self, k: &'r K) -> &mut V {
-        self.map.index_mut(&k.idx())
+        self.map.get_mut(k.idx()).expect("key not present")
     }
 }
 
 
 pub struct Iter<'a, K: Idx, V: 'a> {
-    it: vec_map::Iter<'a, V>,
+    it: iter::Enumerate<slice::Iter<'a, Option<V>>>,
     ph: marker::PhantomData<K>,
 }
 
@@ -112,13 +185,13 @@ impl<'a, K: Idx, V: 'a> Iterator for Iter<'a, K, V> {
 
     #[inline]
     fn next(&mut self) -> Option<(K, &'a V)> {
-        self.it.next().map(|(idx, v)| (Idx::unidx(idx), v))
+        self.it.find_map(|(idx, v)| v.as_ref().map(|v| (Idx::unidx(idx), v)))
     }
 }
 
 
 pub struct IterMut<'a, K: Idx, V: 'a> {
-    it: vec_map::IterMut<'a, V>,
+    it: iter::Enumerate<slice::IterMut<'a, Option<V>>>,
     ph: marker::PhantomData<K>,
 }
 
@@ -127,11 +200,12 @@ impl<'a, K: Idx, V: 'a> Iterator for IterMut<'a, K, V> {
 
     #[inline]
     fn next(&mut self) -> Option<(K, &'a mut V)> {
-        self.it.next().map(|(idx, v)| (Idx::unidx(idx), v))
+        self.it.find_map(|(idx, v)| v.as_mut().map(|v| (Idx::unidx(idx), v)))
     }
 }
 
 
+#[derive(Clone)]
 pub struct IdxVec<K: Idx, V> {
     vec: Vec<V>,
     ph: marker::PhantomData<K>,
@@ -145,6 +219,17 @@ impl<K: Idx, V: Default> IdxVec<K, V> {
         }
     }
 
+    pub fn with_capacity(n: usize) -> Self {
+        IdxVec {
+            vec: Vec::with_capacity(n),
+            ph: marker::PhantomData,
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.vec.reserve(additional);
+    }
+
     #[inline]
     pub fn init(&mut self, k: K) {
         while self.vec.len() <= k.idx() {
@@ -180,19 +265,25 @@ impl<K: Idx, V> ops::IndexMut<K> for IdxVec<K, V> {
 }
 
 
+#[derive(Clone)]
 pub struct IdxHeap<K: Idx> {
     heap: Vec<K>,
-    index: vec_map::VecMap<usize>,
+    index: SlotMap<usize>,
 }
 
 impl<K: Idx> IdxHeap<K> {
     pub fn new() -> Self {
         IdxHeap {
             heap: Vec::new(),
-            index: vec_map::VecMap::new(),
+            index: SlotMap::new(),
         }
     }
 
+    pub fn reserve(&mut self, additional: usize) {
+        self.heap.reserve(additional);
+        self.index.reserve(additional);
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.heap.len()
@@ -263,6 +354,12 @@ impl<K: Idx> IdxHeap<K> {
         }
     }
 
+    // Restore the heap property after keys of many elements have changed:
+    pub fn rebuild<F: Fn(&K, &K) -> bool>(&mut self, before: F) {
+        let heap = mem::take(&mut self.heap);
+        self.heapify_from(heap, before);
+    }
+
     #[inline]
     fn sift_up<F: Fn(&K, &K) -> bool>(&mut self, mut i: usize, before: F) {
         while i > 0 {
@@ -1,4 +1,6 @@
-use std::num;
+use core::num;
+use alloc::vec;
+use alloc::vec::Vec;
 use super::{assignment::Assignment, Lit, Var, VarMap};
 
 
@@ -32,6 +34,30 @@ pub fn satisfied_with_model(clause: &[Lit], model: &VarMap<bool>) -> bool {
 }
 
 
+pub fn model_from_lits(lits: &[Lit]) -> VarMap<bool> {
+    let mut model = VarMap::new();
+    for lit in lits {
+        model.insert(&lit.var(), !lit.sign());
+    }
+    model
+}
+
+
+// Give 'value' to variables below 'n_vars' missing from 'model', which stays ordered by variable:
+pub fn complete_model(model: &mut Vec<Lit>, n_vars: usize, value: bool) {
+    if model.len() < n_vars {
+        let mut present = vec![false; n_vars];
+        for lit in model.iter() {
+            present[lit.var().index()] = true;
+        }
+        for (i, _) in present.iter().enumerate().filter(|&(_, &p)| !p) {
+            model.push(Var::from_index(i).sign_lit(!value));
+        }
+        model.sort();
+    }
+}
+
+
 pub fn extract_model(assigns: &Assignment) -> VarMap<bool> {
     let mut model = VarMap::new();
     for lit in assigns.trail() {
@@ -1,31 +1,142 @@
-use crate::sat::{SolveRes, Solver, Stats};
-use crate::sat::formula::{util, Lit, Var};
+#[cfg(feature = "std")]
+use std::io;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use crate::sat::dimacs;
+use crate::sat::{AssumptionError, ClauseSource, SolveRes, Solver, Stats};
+use crate::sat::encoding::{self, AmoEncoding};
+use crate::sat::formula::{assignment::{Assignment, Checkpoint, CheckpointError, GROUND_LEVEL}, clause::{ClauseRef, ClauseView}, util, LBool, Lit, Var};
+#[cfg(feature = "std")]
+use crate::sat::formula::VarMap;
 use self::search::clause_db::ClauseDBSettings;
-pub use self::search::conflict::CCMinMode;
+pub use self::search::clause_db::DeletionHook;
+pub use self::search::conflict::{CCMinMode, Conflict, ConflictSides};
 use self::search::decision_heuristic::DecisionHeuristicSettings;
 pub use self::search::decision_heuristic::PhaseSaving;
+pub use self::search::{AddClauseRes, DecideError, DontCarePolarity, SolverParts, StepRes};
 use self::search::*;
 use self::search::simplify::elim_clauses::*;
 use self::search::simplify::*;
+pub use self::search::simplify::SimpProgress;
+pub use self::search::trace::{Trace, TraceEvent};
+pub use self::search::tractable::Fragment;
 use self::budget::Budget;
+use self::settings::SettingsError;
+#[cfg(feature = "std")]
+use std::time::Duration;
 
 pub mod budget;
+#[cfg(feature = "std")]
+pub mod cube;
+pub mod settings;
 mod search;
 
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct CoreSettings {
     pub heur: DecisionHeuristicSettings,
     pub db: ClauseDBSettings,
     pub ccmin_mode: CCMinMode,
+    pub minimize_with_binary: bool, // Post-pass of conflict clause minimization with binary clauses.
     pub search: SearchSettings,
     pub core: SearcherSettings,
 }
 
 
+#[cfg(feature = "std")]
+pub enum TimeoutRes<Solver> {
+    UnSAT(Stats),
+    SAT(Vec<Lit>, Stats),
+    TimedOut(Vec<Lit>, Solver), // Largest conflict-free partial assignment seen before the timeout.
+    Invalid(AssumptionError, Solver), // Assumptions rejected before solving (see 'AssumpsRes::Invalid').
+}
+
+
+pub enum ConsistencyRes {
+    Consistent(Vec<Lit>), // Literals implied by the units through propagation.
+    Conflict(Vec<Lit>),   // Units refuted by propagation (none if the formula itself is refuted).
+}
+
+
+pub enum ClauseAssumpsRes<Solver> {
+    UnSAT(Stats),              // Unsatisfiable regardless of assumption clauses.
+    Failed(Vec<usize>, Stats), // Indices of assumption clauses that can not hold together.
+    SAT(Vec<Lit>, Stats),
+    Interrupted(f64, Solver),
+}
+
+
+pub enum AssumpsRes<Solver> {
+    UnSAT(Stats),                     // Unsatisfiable regardless of assumptions.
+    Failed(Vec<Lit>, Stats),          // Assumptions that can not hold together.
+    SAT(Vec<Lit>, Stats),
+    Interrupted(f64, Solver),
+    Invalid(AssumptionError, Solver), // Rejected before solving, the solver is left as it was.
+}
+
+impl<Solver> AssumpsRes<Solver> {
+    fn into_solve_res(self) -> SolveRes<Solver> {
+        match self {
+            AssumpsRes::UnSAT(stats) | AssumpsRes::Failed(_, stats) => SolveRes::UnSAT(stats),
+            AssumpsRes::SAT(model, stats) => SolveRes::SAT(model, stats),
+            AssumpsRes::Interrupted(c, solver) => SolveRes::Interrupted(c, solver),
+            AssumpsRes::Invalid(err, solver) => SolveRes::Invalid(err, solver),
+        }
+    }
+}
+
+
+pub enum MinimalRes<Solver> {
+    UnSAT(Stats),
+    SAT(Vec<Lit>, Stats),             // Model with the fewest objective literals true.
+    Improving(Vec<Lit>, f64, Solver), // Interrupted while looking for a better model than this one.
+    Interrupted(f64, Solver),         // Interrupted before any model was found.
+}
+
+
+pub enum SoftRes<Solver> {
+    UnSAT(Stats),              // Hard constraints can not hold, whatever soft literals are dropped.
+    SAT(Vec<Lit>, u64, Stats), // Optimal model and the total weight of soft literals true in it.
+    Interrupted(f64, Solver),
+}
+
+
+// Limits set on a solver, counted from the start of each 'solve' call:
+#[derive(Clone, Copy, Default)]
+struct Limits {
+    conflicts: Option<u64>,
+    propagations: Option<u64>,
+    assumptions: Option<usize>,
+}
+
+impl Limits {
+    fn check_assumptions(&self, assumptions: &[Lit]) -> Result<(), AssumptionError> {
+        match self.assumptions {
+            Some(max) if assumptions.len() > max => Err(AssumptionError::TooMany(assumptions.len())),
+            _ => Ok(()),
+        }
+    }
+
+    fn budget(&self) -> Budget {
+        let mut budget = Budget::new().per_call();
+        if let Some(x) = self.conflicts {
+            budget = budget.conflicts(x);
+        }
+        if let Some(x) = self.propagations {
+            budget = budget.propagations(x);
+        }
+        budget
+    }
+}
+
+
+// Clones share nothing, so a solver loaded with a common prefix can be copied per branch.
+#[derive(Clone)]
 pub struct CoreSolver {
     ok: bool, // If FALSE, the constraints are already unsatisfiable. No part of the solver state may be used!
     ss: SearchSettings,
+    limits: Limits, // Budget of 'solve' calls.
     search: Searcher,
 }
 
@@ -42,44 +153,35 @@ impl Solver for CoreSolver {
         self.search.new_var(upol, dvar)
     }
 
+    fn reserve_vars(&mut self, n: usize) {
+        self.search.reserve_vars(n);
+    }
+
     fn add_clause(&mut self, clause: &[Lit]) -> bool {
-        if self.ok {
-            if let AddClauseRes::UnSAT = self.search.add_clause(clause) {
-                self.ok = false;
-            }
-        }
+        self.add_clause_res(clause);
         self.ok
     }
 
     fn preprocess(&mut self, _: &Budget) -> bool {
         if self.ok {
-            self.ok = self.search.preprocess();
+            self.ok = self.search.ground().preprocess();
         }
         self.ok
     }
 
-    fn solve_limited(self, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Self> {
+    fn simplify(&mut self) -> bool {
         if self.ok {
-            match self.search.search(&self.ss, budget, assumptions) {
-                SearchRes::UnSAT(stats) => SolveRes::UnSAT(stats),
+            self.ok = self.search.ground().simplify();
+        }
+        self.ok
+    }
 
-                SearchRes::SAT(assigns, stats) => {
-                    let model = util::extract_model(&assigns);
-                    SolveRes::SAT(model.iter().map(|(v, s)| v.sign_lit(!*s)).collect(), stats)
-                }
+    fn solve_limited(self, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Self> {
+        self.solve_limited_assumps(budget, assumptions).into_solve_res()
+    }
 
-                SearchRes::Interrupted(c, s) => SolveRes::Interrupted(
-                    c,
-                    CoreSolver {
-                        ok: true,
-                        ss: self.ss,
-                        search: s,
-                    },
-                ),
-            }
-        } else {
-            SolveRes::UnSAT(self.search.stats())
-        }
+    fn verify_model(&self, model: &[Lit]) -> bool {
+        self.ok && self.search.satisfied_by(&util::model_from_lits(model))
     }
 
     fn stats(&self) -> Stats {
@@ -87,19 +189,642 @@ impl Solver for CoreSolver {
     }
 }
 
+impl ClauseSource for CoreSolver {
+    fn clauses(&self) -> Vec<Vec<Lit>> {
+        if self.ok { self.search.problem_clauses() } else { vec![Vec::new()] }
+    }
+}
+
 impl CoreSolver {
     pub fn new(settings: CoreSettings) -> Self {
+        let mut ss = settings.search;
+        ss.restart.validate();
         CoreSolver {
             ok: true,
-            ss: settings.search,
+            ss,
+            limits: Limits::default(),
             search: Searcher::new(
                 settings.core,
                 settings.db,
                 settings.heur,
                 settings.ccmin_mode,
+                settings.minimize_with_binary,
+            ),
+        }
+    }
+
+    // Solver with variables 'x0' .. 'x{n_vars - 1}' (i.e. 'Var::from_index(0..n_vars)') and 'clauses' added.
+    pub fn from_clauses(settings: CoreSettings, n_vars: usize, clauses: &[&[Lit]]) -> Self {
+        let mut solver = Self::new(settings);
+        load_clauses(&mut solver, n_vars, clauses);
+        solver
+    }
+
+    // Like 'add_clause', but tells what became of the clause: a reference to it if it was stored,
+    // 'Consumed' if it was satisfied or turned into a unit.
+    pub fn add_clause_res(&mut self, clause: &[Lit]) -> AddClauseRes {
+        extend_vars(self, clause);
+        if !self.ok {
+            return AddClauseRes::UnSAT;
+        }
+
+        let res = self.search.ground().add_clause(clause);
+        if let AddClauseRes::UnSAT = res {
+            self.ok = false;
+        }
+        res
+    }
+
+    // Clause 'cr' if it is still in the solver. Search and simplification delete clauses and
+    // garbage collection moves them, so afterwards an old reference gives 'None' or another clause.
+    pub fn clause(&self, cr: ClauseRef) -> Option<ClauseView<'_>> {
+        self.search.clause(cr)
+    }
+
+    pub fn assignment(&self) -> &Assignment {
+        self.search.assigns()
+    }
+
+    pub fn vars(&self) -> impl Iterator<Item = Var> {
+        self.assignment().vars()
+    }
+
+    // Every variable with its value fixed by the formula so far (see 'Assignment::ground_values'),
+    // unlike a model, which is only there after a satisfiable solve.
+    pub fn assignments(&self) -> impl Iterator<Item = (Var, LBool)> + '_ {
+        self.assignment().ground_values()
+    }
+
+    // Length of the longest problem clause added so far (see 'Stats::max_clause_len').
+    pub fn max_clause_len(&self) -> usize {
+        self.search.max_clause_len()
+    }
+
+    // Remove all clauses, learnt ones included, and start over as a new solver with the same
+    // variables (and their decision flags and user polarities).
+    pub fn reset(&mut self) {
+        self.ok = true;
+        self.search.reset(false);
+    }
+
+    // Like 'reset', but variable activities and saved phases carry over to warm-start a related
+    // instance. They never affect correctness, but they only help if every variable keeps its
+    // meaning in the new instance; otherwise they steer search by an unrelated formula.
+    pub fn reset_keep_heuristics(&mut self) {
+        self.ok = true;
+        self.search.reset(true);
+    }
+
+    // Literals fixed at ground level, i.e. implied by the formula alone, in the order they were
+    // derived. Translate them with 'backward_subst' like model literals.
+    pub fn root_units(&self) -> Vec<Lit> {
+        self.assignment().trail_at(GROUND_LEVEL).to_vec()
+    }
+
+    // Limit conflicts of every following 'solve' call, like 'setConfBudget' of MiniSat. 'None'
+    // removes the limit.
+    pub fn set_conflict_budget(&mut self, x: Option<u64>) {
+        self.limits.conflicts = x;
+    }
+
+    pub fn set_propagation_budget(&mut self, x: Option<u64>) {
+        self.limits.propagations = x;
+    }
+
+    // Reject calls with more assumptions than this ('None' removes the limit).
+    pub fn set_assumption_limit(&mut self, x: Option<usize>) {
+        self.limits.assumptions = x;
+    }
+
+    // Like 'solve_limited', but failed assumptions are returned. Directly contradictory
+    // assumptions fail without any search.
+    pub fn solve_limited_assumps(self, budget: &Budget, assumptions: &[Lit]) -> AssumpsRes<Self> {
+        if !self.ok {
+            return AssumpsRes::UnSAT(self.stats());
+        }
+        if let Err(err) = self.limits.check_assumptions(assumptions) {
+            return AssumpsRes::Invalid(err, self);
+        }
+        if let Some(lit) = contradiction(assumptions) {
+            return AssumpsRes::Failed(vec![lit, !lit], self.stats());
+        }
+
+        match self.search.search(&self.ss, budget, assumptions) {
+            SearchRes::UnSAT(stats) => AssumpsRes::UnSAT(stats),
+            SearchRes::AssumpsConfl(failed, stats) => AssumpsRes::Failed(failed, stats),
+
+            SearchRes::SAT(assigns, stats) => {
+                let mut model = Vec::with_capacity(assigns.number_of_vars());
+                assigns.extract_model_into(&mut model);
+                complete_model(&self.ss, &mut model, assigns.number_of_vars());
+                AssumpsRes::SAT(model, stats)
+            }
+
+            SearchRes::Interrupted(c, s) => AssumpsRes::Interrupted(
+                c,
+                CoreSolver {
+                    ok: true,
+                    ss: self.ss,
+                    limits: self.limits,
+                    search: *s,
+                },
+            ),
+        }
+    }
+
+    // Incremental solve that keeps the solver for further calls. Assumptions up to the first one
+    // that differs from the previous call stay assigned in between, so repeated queries over a long
+    // common prefix don't propagate it again. Anything else done with the solver backtracks them.
+    pub fn solve_under(&mut self, budget: &Budget, assumptions: &[Lit]) -> StepRes {
+        if !self.ok {
+            return StepRes::UnSAT;
+        }
+
+        let mut res = self.search.search_step(&self.ss, budget, assumptions);
+        match res {
+            StepRes::UnSAT => self.ok = false,
+            StepRes::SAT(ref mut model) => complete_model(&self.ss, model, self.n_vars()),
+            _ => {}
+        }
+        res
+    }
+
+    // Literals true in every model, found by refuting the opposite of each literal of a model with
+    // 'solve_under'. Models found on the way rule out the literals they flip, and every backbone
+    // literal is added as a unit. If 'budget' runs out, only the part found so far is returned. It
+    // is empty if the formula is unsatisfiable.
+    pub fn backbone(&mut self, budget: &Budget) -> Vec<Lit> {
+        let mut candidates =
+            match self.solve_under(budget, &[]) {
+                StepRes::SAT(model) => model,
+                _ => { return Vec::new(); }
+            };
+
+        let mut backbone = Vec::new();
+        while let Some(lit) = candidates.pop() {
+            match self.solve_under(budget, &[!lit]) {
+                StepRes::SAT(mut model) => {
+                    model.sort();
+                    candidates.retain(|l| model.binary_search(l).is_ok());
+                }
+                StepRes::AssumpsConfl(_) => {
+                    self.add_clause(&[lit]);
+                    backbone.push(lit);
+                }
+                StepRes::UnSAT => { return Vec::new(); }
+                StepRes::Interrupted(_) => { break; }
+            }
+        }
+        backbone.sort();
+        backbone
+    }
+
+    // Solve without assumptions within the budget set on the solver.
+    pub fn solve(self) -> SolveRes<Self> {
+        let budget = self.limits.budget();
+        self.solve_limited(&budget, &[])
+    }
+
+    // Replay DRAT proof against the current clauses, checking that every added clause is RUP.
+    // Variable 'i' of the proof is 'Var::from_index(i - 1)', as numbered by 'dimacs::parse' into an
+    // empty solver. Returns the number of the first step (counting from 0) that isn't RUP or
+    // deletes a clause that isn't there.
+    #[cfg(feature = "std")]
+    pub fn replay_proof<R: io::Read>(&mut self, reader: R) -> io::Result<Option<usize>> {
+        let mut failed = None;
+        let mut index = 0;
+        dimacs::parse_proof(reader, |step| {
+            if failed.is_some() {
+                return;
+            }
+
+            match step {
+                dimacs::ProofStep::Add(raw) => {
+                    let lits = self.proof_lits(&raw);
+                    // Everything follows from an empty clause:
+                    if self.ok && !self.search.ground().is_rup(&lits) {
+                        failed = Some(index);
+                    } else {
+                        self.add_clause(&lits);
+                    }
+                }
+
+                dimacs::ProofStep::Delete(raw) => {
+                    let lits = self.proof_lits(&raw);
+                    if self.ok && !self.search.ground().remove_clause_lits(&lits) {
+                        failed = Some(index);
+                    }
+                }
+            }
+            index += 1;
+        })?;
+        Ok(failed)
+    }
+
+    #[cfg(feature = "std")]
+    fn proof_lits(&mut self, raw: &[i32]) -> Vec<Lit> {
+        raw.iter()
+            .map(|&lit_id| {
+                let index = (lit_id.abs() - 1) as usize;
+                while self.n_vars() <= index {
+                    self.new_var(None, true);
+                }
+                Var::from_index(index).sign_lit(lit_id < 0)
+            })
+            .collect()
+    }
+
+    // Solve assuming that at least one literal of each of 'assumption_clauses' is true. Every clause
+    // is guarded by a fresh selector variable 's' as '(~s | clause)' and selectors are assumed.
+    // Selectors stay in the solver, so an interrupted solver has them as extra variables.
+    pub fn solve_limited_with_clauses(mut self, budget: &Budget, assumption_clauses: &[&[Lit]]) -> ClauseAssumpsRes<Self> {
+        if !self.ok {
+            return ClauseAssumpsRes::UnSAT(self.stats());
+        }
+
+        for clause in assumption_clauses.iter() {
+            extend_vars(&mut self, clause);
+        }
+        let n_vars = self.n_vars();
+        let first_selector = Var::from_index(n_vars);
+        let mut selectors = Vec::with_capacity(assumption_clauses.len());
+        for clause in assumption_clauses.iter() {
+            let s = self.new_var(None, false);
+            let mut lits = clause.to_vec();
+            lits.push(s.neg_lit());
+            self.add_clause(&lits);
+            selectors.push(s.pos_lit());
+        }
+
+        match self.search.search(&self.ss, budget, &selectors) {
+            SearchRes::UnSAT(stats) => ClauseAssumpsRes::UnSAT(stats),
+
+            SearchRes::AssumpsConfl(failed, stats) => {
+                let indices = (0..selectors.len()).filter(|&i| failed.contains(&selectors[i])).collect();
+                ClauseAssumpsRes::Failed(indices, stats)
+            }
+
+            SearchRes::SAT(assigns, stats) => {
+                let mut model = assigns.model_view().filter(|lit| lit.var() < first_selector).collect();
+                complete_model(&self.ss, &mut model, n_vars);
+                ClauseAssumpsRes::SAT(model, stats)
+            }
+
+            SearchRes::Interrupted(c, s) => ClauseAssumpsRes::Interrupted(
+                c,
+                CoreSolver {
+                    ok: true,
+                    ss: self.ss,
+                    limits: self.limits,
+                    search: *s,
+                },
             ),
         }
     }
+
+    // Solve under 'hard' assumptions maximizing the total weight of true 'soft' literals. Failed
+    // soft assumptions are relaxed core by core (WPM1): every soft clause of a core gets a fresh
+    // relaxation variable, at most one of them may be true, and the minimum weight of the core is
+    // split off the heavier clauses. Selectors and relaxation variables stay in the solver.
+    pub fn solve_limited_soft(mut self, budget: &Budget, hard: &[Lit], soft: &[(Lit, u64)]) -> SoftRes<Self> {
+        if !self.ok {
+            return SoftRes::UnSAT(self.stats());
+        }
+
+        extend_vars(&mut self, hard);
+        extend_vars(&mut self, &soft.iter().map(|&(lit, _)| lit).collect::<Vec<Lit>>());
+        let n_vars = self.n_vars();
+        let first_aux = Var::from_index(n_vars);
+
+        // Soft clauses with their weights and selectors enabling them when assumed:
+        let mut softs: Vec<(Vec<Lit>, u64, Lit)> = Vec::with_capacity(soft.len());
+        for &(lit, weight) in soft.iter().filter(|&&(_, weight)| weight > 0) {
+            let s = self.add_selected_clause(&[lit]);
+            softs.push((vec![lit], weight, s));
+        }
+
+        loop {
+            let mut assumptions = hard.to_vec();
+            assumptions.extend(softs.iter().map(|&(_, _, s)| s));
+
+            match self.search.search_step(&self.ss, budget, &assumptions) {
+                StepRes::UnSAT => return SoftRes::UnSAT(self.stats()),
+
+                StepRes::SAT(model) => {
+                    let mut model: Vec<Lit> = model.into_iter().filter(|lit| lit.var() < first_aux).collect();
+                    complete_model(&self.ss, &mut model, n_vars);
+                    let weight = soft.iter().filter(|&(lit, _)| model.contains(lit)).map(|&(_, weight)| weight).sum();
+                    return SoftRes::SAT(model, weight, self.stats());
+                }
+
+                StepRes::Interrupted(c) => return SoftRes::Interrupted(c, self),
+
+                StepRes::AssumpsConfl(failed) => {
+                    let core: Vec<usize> = (0..softs.len()).filter(|&i| failed.contains(&softs[i].2)).collect();
+                    let min_weight =
+                        match core.iter().map(|&i| softs[i].1).min() {
+                            None => return SoftRes::UnSAT(self.stats()), // Hard assumptions failed by themselves.
+                            Some(w) => w,
+                        };
+
+                    let mut relax = Vec::with_capacity(core.len());
+                    for &i in core.iter() {
+                        let (clause, weight, s) = softs[i].clone();
+                        self.add_clause(&[!s]);
+                        if weight > min_weight {
+                            let s = self.add_selected_clause(&clause);
+                            softs.push((clause.clone(), weight - min_weight, s));
+                        }
+
+                        let r = self.new_var(None, true).pos_lit();
+                        relax.push(r);
+                        let mut relaxed = clause;
+                        relaxed.push(r);
+                        let s = self.add_selected_clause(&relaxed);
+                        softs[i] = (relaxed, min_weight, s);
+                    }
+                    encoding::at_most_one(&mut self, &relax, AmoEncoding::Sequential);
+                }
+            }
+        }
+    }
+
+    // Add '(~s | clause)' for a fresh selector 's' and return 's'.
+    fn add_selected_clause(&mut self, clause: &[Lit]) -> Lit {
+        let s = self.new_var(None, false);
+        let mut lits = clause.to_vec();
+        lits.push(s.neg_lit());
+        self.add_clause(&lits);
+        s.pos_lit()
+    }
+
+    // Solve minimizing the number of true 'objective' literals. Every model found is followed by a
+    // search for one with fewer of them true, until the selector of that bound is in the failed
+    // assumptions: the last model is minimal then. Bounds are only active while their selectors
+    // are assumed, and each one is retired by a unit '~s' once a tighter bound replaces it, so the
+    // solver returned on interruption keeps its models.
+    pub fn solve_minimal(mut self, budget: &Budget, objective: &[Lit]) -> MinimalRes<Self> {
+        if !self.ok {
+            return MinimalRes::UnSAT(self.stats());
+        }
+
+        extend_vars(&mut self, objective);
+        let n_vars = self.n_vars();
+        let first_aux = Var::from_index(n_vars);
+        let mut objective = objective.to_vec();
+        objective.sort();
+        objective.dedup();
+
+        let mut best = Vec::new();
+        let mut selector: Option<Lit> = None;
+        loop {
+            let assumptions: Vec<Lit> = selector.into_iter().collect();
+            match self.search.search_step(&self.ss, budget, &assumptions) {
+                StepRes::UnSAT => return MinimalRes::UnSAT(self.stats()),
+                StepRes::AssumpsConfl(_) => break,
+
+                StepRes::Interrupted(c) => {
+                    return match selector {
+                        Some(s) => {
+                            self.add_clause(&[!s]);
+                            MinimalRes::Improving(best, c, self)
+                        }
+                        None => MinimalRes::Interrupted(c, self),
+                    };
+                }
+
+                StepRes::SAT(model) => {
+                    let mut model: Vec<Lit> = model.into_iter().filter(|lit| lit.var() < first_aux).collect();
+                    complete_model(&self.ss, &mut model, n_vars);
+                    let k = model.iter().filter(|lit| objective.binary_search(lit).is_ok()).count();
+                    best = model;
+                    if k == 0 {
+                        break;
+                    }
+                    if let Some(s) = selector {
+                        self.add_clause(&[!s]);
+                    }
+                    selector = Some(self.add_selected_at_most(&objective, k - 1));
+                }
+            }
+        }
+
+        let stats = self.stats();
+        MinimalRes::SAT(best, stats)
+    }
+
+    // Add "at most 'k' of 'lits' are true" for a fresh selector 's' and return 's': while 's' is
+    // true, every literal implies a proxy, and proxies are bounded by 'encoding::at_most_k'.
+    fn add_selected_at_most(&mut self, lits: &[Lit], k: usize) -> Lit {
+        let s = self.new_var(None, false).pos_lit();
+        let mut proxies = Vec::with_capacity(lits.len());
+        for &lit in lits.iter() {
+            let p = self.new_var(None, true).pos_lit();
+            self.add_clause(&[!s, !lit, p]);
+            proxies.push(p);
+        }
+        encoding::at_most_k(self, &proxies, k);
+        s
+    }
+
+    #[cfg(feature = "std")]
+    pub fn solve_with_timeout(mut self, timeout: Duration, assumptions: &[Lit]) -> TimeoutRes<Self> {
+        let mut budget = Budget::new();
+        budget.set_time_budget(timeout);
+        self.search.track_best_assignment();
+        match self.solve_limited(&budget, assumptions) {
+            SolveRes::UnSAT(stats) => TimeoutRes::UnSAT(stats),
+            SolveRes::SAT(model, stats) => TimeoutRes::SAT(model, stats),
+            SolveRes::Interrupted(_, mut solver) => {
+                let partial = solver.search.take_best_assignment();
+                TimeoutRes::TimedOut(partial, solver)
+            }
+            SolveRes::Invalid(err, mut solver) => {
+                solver.search.take_best_assignment();
+                TimeoutRes::Invalid(err, solver)
+            }
+        }
+    }
+
+    // Bias branching towards 'v': variables with higher activity are picked first.
+    pub fn set_var_activity(&mut self, v: Var, act: f64) {
+        self.search.set_var_activity(v, act);
+    }
+
+    pub fn var_activity(&self, v: Var) -> f64 {
+        self.search.var_activity(v)
+    }
+
+    // Compact clause storage now. Returns allocated bytes before and after.
+    pub fn collect_garbage(&mut self) -> (usize, usize) {
+        self.search.ground().collect_garbage()
+    }
+
+    // Fraction of wasted clause memory that triggers garbage collection, as adapted with 'adaptive_gc':
+    pub fn garbage_frac(&self) -> f64 {
+        self.search.garbage_frac()
+    }
+
+    // Replace 'garbage_frac' of the settings. Adaptation with 'adaptive_gc' goes on from this value.
+    pub fn set_garbage_frac(&mut self, frac: f64) -> Result<(), SettingsError> {
+        if 0.0 < frac && frac <= 1.0 {
+            self.search.set_garbage_frac(frac);
+            Ok(())
+        } else {
+            Err(SettingsError { setting: "gc-frac", expected: "a value in (0, 1]" })
+        }
+    }
+
+    // Bytes taken by clauses in storage, and by the deleted ones among them that the next garbage
+    // collection reclaims.
+    pub fn allocator_stats(&self) -> (usize, usize) {
+        self.search.allocator_stats()
+    }
+
+    // Decide unassigned variables from 'vars' before any other variable, in the given order:
+    pub fn set_decision_prefix(&mut self, vars: &[Var]) {
+        self.search.set_decision_prefix(vars);
+    }
+
+    // Keep the implication graph partition of the last analyzed conflict (see 'ConflictSides').
+    pub fn record_conflict_sides(&mut self, enable: bool) {
+        self.search.record_conflict_sides(enable);
+    }
+
+    pub fn last_conflict_sides(&self) -> Option<&ConflictSides> {
+        self.search.last_conflict_sides()
+    }
+
+    // Clause that forced the current value of 'v' (see 'Assignment::reason_of').
+    pub fn reason_of(&self, v: Var) -> Option<&[Lit]> {
+        self.search.reason_of(v)
+    }
+
+    // Conflict clause minimization in effect: with 'CCMinMode::Adaptive' it is 'Deep' until the
+    // solver downgrades it to 'Basic'.
+    pub fn ccmin_mode(&self) -> CCMinMode {
+        self.search.ccmin_mode()
+    }
+
+    // Learnt clause and backjump level for 'confl', a clause with all literals false and some at the
+    // current decision level, or 'None' if it is not such a clause (see 'Searcher::analyze_conflict').
+    // Levels above ground come from 'decide' or from assumptions kept by 'solve_under'.
+    pub fn analyze_conflict(&mut self, confl: &[Lit]) -> Option<Conflict> {
+        self.search.analyze_conflict(confl)
+    }
+
+    // Decide 'lit' on a new level and propagate it, returning the conflicting clause if any. The
+    // decisions stay until a checkpoint is restored or anything but 'solve_under' is called.
+    // Unknown or assigned literals are rejected without changing anything.
+    pub fn decide(&mut self, lit: Lit) -> Result<Option<ClauseRef>, DecideError> {
+        self.search.decide(lit)
+    }
+
+    // Current decision level and trail position, for an external search to return to with 'restore'.
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.search.checkpoint()
+    }
+
+    pub fn restore(&mut self, cp: Checkpoint) -> Result<(), CheckpointError> {
+        self.search.restore(cp)
+    }
+
+    // Check whether 'units' are consistent with the formula by unit propagation alone, without
+    // search. Nothing is learnt, and the assignment is rolled back to the ground level afterwards.
+    pub fn check_units(&mut self, units: &[Lit]) -> ConsistencyRes {
+        if !self.ok {
+            return ConsistencyRes::Conflict(Vec::new());
+        }
+
+        match self.search.ground().check_units(units) {
+            Ok(implied) => ConsistencyRes::Consistent(implied),
+            Err(core) => {
+                if core.is_empty() {
+                    self.ok = false;
+                }
+                ConsistencyRes::Conflict(core)
+            }
+        }
+    }
+
+    // Progress estimate at the end of the latest search: where it was interrupted, or where it
+    // found a model or a contradiction. Zero before the first search.
+    pub fn progress(&self) -> f64 {
+        self.search.progress()
+    }
+
+    // Horn or 2-SAT if the latest 'preprocess' found the formula to be one and decided it (only
+    // with 'detect_tractable').
+    pub fn tractable_fragment(&self) -> Option<Fragment> {
+        self.search.tractable_fragment()
+    }
+
+    // Problem clauses containing 'lit'. Occurrence lists are built on the first query and dropped
+    // whenever clauses are added or relocated, so solvers that never ask pay nothing.
+    pub fn clauses_with(&self, lit: Lit) -> Vec<&[Lit]> {
+        self.search.clauses_with(lit)
+    }
+
+    // Write learnt clauses with LBD not above 'max_lbd' in DIMACS format, with variables numbered
+    // by 'backward_subst'. They are only sound for instances with the same variable numbering.
+    #[cfg(feature = "std")]
+    pub fn export_learnts<W: io::Write>(&self, writer: W, max_lbd: u32, backward_subst: &VarMap<i32>) -> io::Result<()> {
+        dimacs::write_clauses(writer, &self.search.learnts_upto_lbd(max_lbd), backward_subst)
+    }
+
+    // Add clauses written by 'export_learnts' as learnt clauses. Returns the number of clauses read.
+    #[cfg(feature = "std")]
+    pub fn import_learnts<R: io::Read>(&mut self, reader: R, backward_subst: &VarMap<i32>) -> io::Result<usize> {
+        let clauses = dimacs::parse_clauses(reader, backward_subst)?;
+        for clause in clauses.iter() {
+            if !self.ok {
+                break;
+            }
+            if let AddClauseRes::UnSAT = self.search.ground().add_learnt(clause) {
+                self.ok = false;
+            }
+        }
+        Ok(clauses.len())
+    }
+
+    // Consume the solver, keeping its clauses, assignment and variable activities for inspection.
+    pub fn into_parts(self) -> SolverParts {
+        self.search.into_parts()
+    }
+
+    // Call 'hook' with literals of every clause deleted from now on: learnt clauses dropped by
+    // 'reduce', satisfied clauses removed by 'simplify' and clauses removed by preprocessing.
+    // Garbage collection only moves clauses, so it doesn't call it. Copies of the solver start
+    // without a hook.
+    pub fn on_clause_deleted(&mut self, hook: Option<DeletionHook>) {
+        self.search.set_deletion_hook(hook);
+    }
+
+    // Write decisions, conflicts, restarts and garbage collections of further search to 'out'.
+    // The returned handle finishes the trace (see 'finish_trace') even after a solve that consumed
+    // the solver.
+    #[cfg(feature = "std")]
+    pub fn record_trace<W: io::Write + Send + 'static>(&mut self, out: W) -> Trace {
+        let trace = Trace::record(Box::new(out));
+        self.search.set_trace(Some(trace.clone()));
+        trace
+    }
+
+    // Check further search against a trace written by 'record_trace'. Panics at the first event
+    // that differs, so that nondeterminism is caught where it shows up.
+    #[cfg(feature = "std")]
+    pub fn replay_trace<R: io::Read + Send + 'static>(&mut self, input: R) -> Trace {
+        let trace = Trace::replay(Box::new(input));
+        self.search.set_trace(Some(trace.clone()));
+        trace
+    }
+
+    // Stop recording or replaying. Fails with the first IO error of the trace, and for a replayed
+    // one if it has events the search didn't get to.
+    #[cfg(feature = "std")]
+    pub fn finish_trace(&mut self) -> io::Result<()> {
+        self.search.take_trace().map_or(Ok(()), |trace| trace.finish())
+    }
 }
 
 
@@ -107,6 +832,9 @@ pub struct SimpSettings {
     pub core: CoreSettings,
     pub simp: SimplificatorSettings,
     pub extend_model: bool, // Flag to indicate whether the user needs to look at the full model.
+    pub reversible_elim: bool, // Keep clauses of eliminated variables so they can be reintroduced by later clauses.
+    pub keep_removed: bool, // Keep clauses removed by elimination for 'verify_model' to check, as 'reversible_elim' does anyway.
+    pub persist_simp: bool, // Keep the simplificator after 'preprocess' to run elimination again before every solve.
 }
 
 impl Default for SimpSettings {
@@ -115,6 +843,9 @@ impl Default for SimpSettings {
             core: Default::default(),
             simp: Default::default(),
             extend_model: true,
+            reversible_elim: false,
+            keep_removed: false,
+            persist_simp: false,
         }
     }
 }
@@ -124,6 +855,8 @@ pub struct SimpSolver {
     core: CoreSolver,
     elimclauses: ElimClauses,
     simp: Option<Simplificator>,
+    persist_simp: bool,
+    rounds: Vec<SimpProgress>, // Reductions of every round of the latest 'preprocess'.
 }
 
 impl Solver for SimpSolver {
@@ -143,7 +876,21 @@ impl Solver for SimpSolver {
         v
     }
 
+    fn reserve_vars(&mut self, n: usize) {
+        self.core.reserve_vars(n);
+        if let Some(ref mut simp) = self.simp {
+            simp.reserve_vars(n);
+        }
+    }
+
     fn add_clause(&mut self, ps: &[Lit]) -> bool {
+        extend_vars(self, ps);
+        for lit in ps.iter() {
+            if self.elimclauses.can_reintroduce(lit.var()) && !self.reintroduce_var(lit.var()) {
+                return false;
+            }
+        }
+
         match self.simp {
             None => self.core.add_clause(ps),
             Some(ref mut simp) => {
@@ -159,38 +906,132 @@ impl Solver for SimpSolver {
     }
 
     fn preprocess(&mut self, budget: &Budget) -> bool {
-        if !self.core.preprocess(budget) {
-            return false;
+        self.preprocess_with_progress(budget, |_| {})
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.core.simplify()
+    }
+
+    fn solve_limited(self, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Self> {
+        self.solve_limited_assumps(budget, assumptions).into_solve_res()
+    }
+
+    fn verify_model(&self, model: &[Lit]) -> bool {
+        let model = util::model_from_lits(model);
+        self.core.ok && self.core.search.satisfied_by(&model) && self.elimclauses.satisfied_by(&model)
+    }
+
+    fn stats(&self) -> Stats {
+        self.core.search.stats()
+    }
+}
+
+impl ClauseSource for SimpSolver {
+    // Eliminated variables are gone, so the formula is only equisatisfiable to the one added.
+    fn clauses(&self) -> Vec<Vec<Lit>> {
+        self.core.clauses()
+    }
+}
+
+impl SimpSolver {
+    pub fn new(settings: SimpSettings) -> Self {
+        let mut core = CoreSolver::new(settings.core);
+        Simplificator::on(&mut core.search);
+        SimpSolver {
+            core,
+            elimclauses: ElimClauses::new(settings.extend_model, settings.reversible_elim, settings.keep_removed),
+            simp: Some(Simplificator::new(settings.simp)),
+            persist_simp: settings.persist_simp,
+            rounds: Vec::new(),
         }
+    }
 
-        let turn_off_elim = true;
-        let result =
-            if let Some(ref mut simp) = self.simp {
-                match simp.eliminate(&mut self.core.search, budget, &mut self.elimclauses) {
-                    Ok(()) => { true }
-                    Err(()) => {
-                        self.core.ok = false;
-                        false
+    // Undo elimination of 'v' by adding back all clauses removed with it. Only possible if the
+    // solver was created with 'reversible_elim'. Returns false if the formula became UNSAT.
+    pub fn reintroduce_var(&mut self, v: Var) -> bool {
+        match self.elimclauses.reintroduce(v) {
+            None => self.core.ok,
+            Some(clauses) => {
+                if let Some(ref mut simp) = self.simp {
+                    simp.reintroduce_var(&self.core.search, v);
+                }
+                self.core.search.set_decision_var(v, true);
+
+                for clause in clauses.iter() {
+                    if !self.add_clause(clause) {
+                        return false;
                     }
                 }
+                self.core.ok
+            }
+        }
+    }
 
-                // TODO:
-                //                if !turn_off_elim && self.core.search.db.ca.checkGarbage(self.core.search.settings.garbage_frac) {
-                //                    simp.garbage_collect(&mut self.core.search);
-                //                }
-            } else {
-                return true;
-            };
+    pub fn from_clauses(settings: SimpSettings, n_vars: usize, clauses: &[&[Lit]]) -> Self {
+        let mut solver = Self::new(settings);
+        load_clauses(&mut solver, n_vars, clauses);
+        solver
+    }
 
-        if turn_off_elim {
-            self.simp_off();
-        }
+    pub fn assignment(&self) -> &Assignment {
+        self.core.assignment()
+    }
 
-        self.elimclauses.log_size();
-        result
+    pub fn vars(&self) -> impl Iterator<Item = Var> {
+        self.core.vars()
     }
 
-    fn solve_limited(mut self, budget: &Budget, assumptions: &[Lit]) -> SolveRes<Self> {
+    // Eliminated variables are 'Undef' until a model is extended to them.
+    pub fn assignments(&self) -> impl Iterator<Item = (Var, LBool)> + '_ {
+        self.core.assignments()
+    }
+
+    pub fn max_clause_len(&self) -> usize {
+        self.core.max_clause_len()
+    }
+
+    // Includes units derived by 'preprocess', but not values of eliminated variables: these are
+    // only fixed by model extension.
+    pub fn root_units(&self) -> Vec<Lit> {
+        self.core.root_units()
+    }
+
+    pub fn set_conflict_budget(&mut self, x: Option<u64>) {
+        self.core.set_conflict_budget(x);
+    }
+
+    pub fn set_propagation_budget(&mut self, x: Option<u64>) {
+        self.core.set_propagation_budget(x);
+    }
+
+    pub fn set_assumption_limit(&mut self, x: Option<usize>) {
+        self.core.set_assumption_limit(x);
+    }
+
+    // Eliminated variables among assumptions are reintroduced if elimination is reversible, and
+    // rejected otherwise. All assumptions are checked before any variable is reintroduced.
+    pub fn solve_limited_assumps(mut self, budget: &Budget, assumptions: &[Lit]) -> AssumpsRes<Self> {
+        if !self.core.ok {
+            return AssumpsRes::UnSAT(self.stats());
+        }
+        if let Err(err) = self.core.limits.check_assumptions(assumptions) {
+            return AssumpsRes::Invalid(err, self);
+        }
+        let fixed = assumptions.iter().find(|lit| self.is_eliminated(lit.var()) && !self.elimclauses.can_reintroduce(lit.var()));
+        if let Some(lit) = fixed {
+            return AssumpsRes::Invalid(AssumptionError::Eliminated(lit.var()), self);
+        }
+        if let Some(lit) = contradiction(assumptions) {
+            return AssumpsRes::Failed(vec![lit, !lit], self.stats());
+        }
+
+        for lit in assumptions.iter() {
+            if self.elimclauses.can_reintroduce(lit.var()) && !self.reintroduce_var(lit.var()) {
+                return AssumpsRes::UnSAT(self.stats());
+            }
+        }
+
         match self.simp {
             Some(mut simp) => {
                 match simp.solve_limited(
@@ -200,12 +1041,15 @@ impl Solver for SimpSolver {
                     &mut self.elimclauses,
                     assumptions,
                 ) {
-                    SearchRes::UnSAT(stats) => SolveRes::UnSAT(stats),
+                    SearchRes::UnSAT(stats) => AssumpsRes::UnSAT(stats),
+                    SearchRes::AssumpsConfl(failed, stats) => AssumpsRes::Failed(failed, stats),
 
                     SearchRes::SAT(assigns, stats) => {
                         let mut model = util::extract_model(&assigns);
                         self.elimclauses.extend_model(&mut model);
-                        SolveRes::SAT(model.iter().map(|(v, s)| v.sign_lit(!*s)).collect(), stats)
+                        let mut model = model.iter().map(|(v, s)| v.sign_lit(!*s)).collect();
+                        complete_model(&self.core.ss, &mut model, assigns.number_of_vars());
+                        AssumpsRes::SAT(model, stats)
                     }
 
                     SearchRes::Interrupted(c, s) => {
@@ -213,16 +1057,19 @@ impl Solver for SimpSolver {
                         //        if turn_off_simp {
                         //            self.simp_off();
                         //        }
-                        SolveRes::Interrupted(
+                        AssumpsRes::Interrupted(
                             c,
                             SimpSolver {
                                 core: CoreSolver {
                                     ok: true,
                                     ss: self.core.ss,
-                                    search: s,
+                                    limits: self.core.limits,
+                                    search: *s,
                                 },
                                 elimclauses: self.elimclauses,
                                 simp: Some(simp),
+                                persist_simp: self.persist_simp,
+                                rounds: self.rounds,
                             },
                         )
                     }
@@ -230,43 +1077,163 @@ impl Solver for SimpSolver {
             }
 
             _ => match self.core.search.search(&self.core.ss, budget, assumptions) {
-                SearchRes::UnSAT(stats) => SolveRes::UnSAT(stats),
+                SearchRes::UnSAT(stats) => AssumpsRes::UnSAT(stats),
+                SearchRes::AssumpsConfl(failed, stats) => AssumpsRes::Failed(failed, stats),
 
                 SearchRes::SAT(assigns, stats) => {
                     let mut model = util::extract_model(&assigns);
                     self.elimclauses.extend_model(&mut model);
-                    SolveRes::SAT(model.iter().map(|(v, s)| v.sign_lit(!*s)).collect(), stats)
+                    let mut model = model.iter().map(|(v, s)| v.sign_lit(!*s)).collect();
+                    complete_model(&self.core.ss, &mut model, assigns.number_of_vars());
+                    AssumpsRes::SAT(model, stats)
                 }
 
-                SearchRes::Interrupted(c, s) => SolveRes::Interrupted(
+                SearchRes::Interrupted(c, s) => AssumpsRes::Interrupted(
                     c,
                     SimpSolver {
                         core: CoreSolver {
                             ok: true,
                             ss: self.core.ss,
-                            search: s,
+                            limits: self.core.limits,
+                            search: *s,
                         },
                         elimclauses: self.elimclauses,
                         simp: None,
+                        persist_simp: self.persist_simp,
+                        rounds: self.rounds,
                     },
                 ),
             },
         }
     }
 
-    fn stats(&self) -> Stats {
-        self.core.search.stats()
+    pub fn solve(self) -> SolveRes<Self> {
+        let budget = self.core.limits.budget();
+        self.solve_limited(&budget, &[])
     }
-}
 
-impl SimpSolver {
-    pub fn new(settings: SimpSettings) -> Self {
-        let mut core = CoreSolver::new(settings.core);
-        Simplificator::on(&mut core.search);
-        SimpSolver {
-            core,
-            elimclauses: ElimClauses::new(settings.extend_model),
-            simp: Some(Simplificator::new(settings.simp)),
+    // Partial assignment of a timed out search doesn't cover eliminated variables.
+    #[cfg(feature = "std")]
+    pub fn solve_with_timeout(mut self, timeout: Duration, assumptions: &[Lit]) -> TimeoutRes<Self> {
+        let mut budget = Budget::new();
+        budget.set_time_budget(timeout);
+        self.core.search.track_best_assignment();
+        match self.solve_limited(&budget, assumptions) {
+            SolveRes::UnSAT(stats) => TimeoutRes::UnSAT(stats),
+            SolveRes::SAT(model, stats) => TimeoutRes::SAT(model, stats),
+            SolveRes::Interrupted(_, mut solver) => {
+                let partial = solver.core.search.take_best_assignment();
+                TimeoutRes::TimedOut(partial, solver)
+            }
+            SolveRes::Invalid(err, mut solver) => {
+                solver.core.search.take_best_assignment();
+                TimeoutRes::Invalid(err, solver)
+            }
+        }
+    }
+
+    // Same as 'preprocess', reporting progress of variable elimination to 'progress' along the way.
+    // Elimination stops early when 'budget' runs out of time or eliminated variables.
+    pub fn preprocess_with_progress<F: FnMut(&SimpProgress)>(&mut self, budget: &Budget, progress: F) -> bool {
+        if !self.core.preprocess(budget) {
+            return false;
+        }
+
+        let turn_off_elim = !self.persist_simp;
+        let result =
+            if let Some(ref mut simp) = self.simp {
+                let res = simp.eliminate(&mut self.core.search, budget, &mut self.elimclauses, progress);
+                self.rounds = simp.rounds().to_vec();
+                match res {
+                    Ok(()) => { true }
+                    Err(()) => {
+                        self.core.ok = false;
+                        false
+                    }
+                }
+
+                // TODO:
+                //                if !turn_off_elim && self.core.search.db.ca.checkGarbage(self.core.search.settings.garbage_frac) {
+                //                    simp.garbage_collect(&mut self.core.search);
+                //                }
+            } else {
+                return true;
+            };
+
+        if turn_off_elim {
+            self.simp_off();
+        }
+
+        self.elimclauses.log_size();
+        result
+    }
+
+    // Reductions made by each subsumption and elimination round of the latest 'preprocess' (see
+    // 'SimplificatorSettings::max_preprocess_rounds'). The last round made none unless the cap was hit.
+    pub fn preprocess_rounds(&self) -> &[SimpProgress] {
+        &self.rounds
+    }
+
+    // Known only while the simplificator is kept (see 'persist_simp') or elimination is reversible.
+    pub fn is_eliminated(&self, v: Var) -> bool {
+        self.simp.as_ref().is_some_and(|simp| simp.is_eliminated(v)) || self.elimclauses.is_eliminated(v)
+    }
+
+    pub fn set_var_activity(&mut self, v: Var, act: f64) {
+        self.core.set_var_activity(v, act);
+    }
+
+    pub fn var_activity(&self, v: Var) -> f64 {
+        self.core.var_activity(v)
+    }
+
+    pub fn set_decision_prefix(&mut self, vars: &[Var]) {
+        self.core.set_decision_prefix(vars);
+    }
+
+    pub fn reason_of(&self, v: Var) -> Option<&[Lit]> {
+        self.core.reason_of(v)
+    }
+
+    pub fn progress(&self) -> f64 {
+        self.core.progress()
+    }
+
+    pub fn tractable_fragment(&self) -> Option<Fragment> {
+        self.core.tractable_fragment()
+    }
+
+    pub fn on_clause_deleted(&mut self, hook: Option<DeletionHook>) {
+        self.core.on_clause_deleted(hook);
+    }
+
+    #[cfg(feature = "std")]
+    pub fn record_trace<W: io::Write + Send + 'static>(&mut self, out: W) -> Trace {
+        self.core.record_trace(out)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn replay_trace<R: io::Read + Send + 'static>(&mut self, input: R) -> Trace {
+        self.core.replay_trace(input)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn finish_trace(&mut self) -> io::Result<()> {
+        self.core.finish_trace()
+    }
+
+    pub fn set_garbage_frac(&mut self, frac: f64) -> Result<(), SettingsError> {
+        self.core.set_garbage_frac(frac)
+    }
+
+    pub fn allocator_stats(&self) -> (usize, usize) {
+        self.core.allocator_stats()
+    }
+
+    pub fn collect_garbage(&mut self) -> (usize, usize) {
+        match self.simp {
+            Some(ref mut simp) => simp.garbage_collect(&mut self.core.search),
+            None => self.core.collect_garbage(),
         }
     }
 
@@ -277,3 +1244,69 @@ impl SimpSolver {
         }
     }
 }
+
+
+fn complete_model(ss: &SearchSettings, model: &mut Vec<Lit>, n_vars: usize) {
+    if let Some(value) = ss.dont_care.value() {
+        util::complete_model(model, n_vars, value);
+    }
+}
+
+// Allocate missing variables up to the largest one in 'clause', so that literals of variables not
+// created with 'new_var' are safe to use:
+fn extend_vars<S: Solver>(solver: &mut S, clause: &[Lit]) {
+    if let Some(max) = clause.iter().map(|lit| lit.var()).max() {
+        while Var::from_index(solver.n_vars()) <= max {
+            solver.new_var(None, true);
+        }
+    }
+}
+
+// Literal 'lit' such that both 'lit' and '!lit' are among 'lits'.
+fn contradiction(lits: &[Lit]) -> Option<Lit> {
+    let mut sorted = lits.to_vec();
+    sorted.sort();
+    sorted.windows(2).find(|w| w[1] == !w[0]).map(|w| w[0])
+}
+
+fn load_clauses<S: Solver>(solver: &mut S, n_vars: usize, clauses: &[&[Lit]]) {
+    solver.reserve_vars(n_vars);
+    for _ in 0..n_vars {
+        solver.new_var(None, true);
+    }
+
+    for clause in clauses {
+        if !solver.add_clause(clause) {
+            break;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pigeonhole principle: 'n + 1' pigeons do not fit into 'n' holes. Only needs 'alloc', so it
+    // runs in builds without the 'std' feature too.
+    #[test]
+    fn test_pigeonhole() {
+        let n = 6;
+        let mut solver = CoreSolver::new(Default::default());
+        let p: Vec<Vec<Lit>> = (0..n + 1)
+            .map(|_| (0..n).map(|_| solver.new_var(None, true).pos_lit()).collect())
+            .collect();
+        for pigeon in p.iter() {
+            solver.add_clause(pigeon);
+        }
+        for hole in 0..n {
+            let lits: Vec<Lit> = p.iter().map(|pigeon| pigeon[hole]).collect();
+            encoding::at_most_one(&mut solver, &lits, AmoEncoding::Pairwise);
+        }
+
+        match solver.solve() {
+            SolveRes::UnSAT(stats) => { assert!(stats.restarts > 0); }
+            _ => panic!("Pigeonhole formula should be unsatisfiable"),
+        }
+    }
+}
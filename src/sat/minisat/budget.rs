@@ -1,11 +1,49 @@
-use std::sync::atomic;
+use core::convert::TryFrom;
+use core::sync::atomic;
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+
+// Reading the clock costs more than a decision, so the deadline is only checked once in this many
+// budget checks (the first one included):
+#[cfg(feature = "std")]
+const DEADLINE_POLL_INTERVAL: u32 = 64;
+
+// Shared flag to stop solving from another thread (see 'Budget::with_interrupt'):
+#[derive(Clone, Default)]
+pub struct InterruptHandle(Arc<atomic::AtomicBool>);
+
+impl InterruptHandle {
+    pub fn new() -> InterruptHandle {
+        InterruptHandle::default()
+    }
+
+    pub fn interrupt(&self) {
+        self.0.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.0.store(false, atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(atomic::Ordering::Relaxed)
+    }
+}
 
 
 // Resource contraints:
 pub struct Budget {
     conflict_budget: i64,    // -1 means no budget.
     propagation_budget: i64, // -1 means no budget.
-    asynch_interrupt: atomic::AtomicBool,
+    per_call: bool,          // Count conflicts and propagations from the start of each solve call, not from solver creation.
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>, // There is no clock without 'std', so no time limit either.
+    #[cfg(feature = "std")]
+    deadline_polls: atomic::AtomicU32, // Budget checks since the clock was last read.
+    elim_budget: i64,        // Variables eliminated per preprocessing call, -1 means no budget.
+    asynch_interrupt: InterruptHandle,
 }
 
 impl Budget {
@@ -13,22 +51,151 @@ impl Budget {
         Budget {
             conflict_budget: -1,
             propagation_budget: -1,
-            asynch_interrupt: atomic::AtomicBool::new(false),
+            per_call: false,
+            #[cfg(feature = "std")]
+            deadline: None,
+            #[cfg(feature = "std")]
+            deadline_polls: atomic::AtomicU32::new(0),
+            elim_budget: -1,
+            asynch_interrupt: InterruptHandle::new(),
         }
     }
 
+    // Builder-style limits. Solving stops as soon as any of them is exceeded:
+    pub fn conflicts(mut self, x: u64) -> Budget {
+        self.conflict_budget = clamp(x);
+        self
+    }
+
+    pub fn propagations(mut self, x: u64) -> Budget {
+        self.propagation_budget = clamp(x);
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn time(mut self, timeout: Duration) -> Budget {
+        self.set_time_budget(timeout);
+        self
+    }
+
+    pub fn eliminations(mut self, x: u64) -> Budget {
+        self.elim_budget = clamp(x);
+        self
+    }
+
+    pub fn per_call(mut self) -> Budget {
+        self.per_call = true;
+        self
+    }
+
+    pub fn with_interrupt(mut self, handle: &InterruptHandle) -> Budget {
+        self.asynch_interrupt = handle.clone();
+        self
+    }
+
+    pub fn set_conflict_budget(&mut self, x: i64) {
+        self.conflict_budget = x;
+    }
+
+    pub fn set_propagation_budget(&mut self, x: i64) {
+        self.propagation_budget = x;
+    }
+
+    // Wall-clock limit counted from now:
+    #[cfg(feature = "std")]
+    pub fn set_time_budget(&mut self, timeout: Duration) {
+        self.deadline = Some(Instant::now() + timeout);
+        self.deadline_polls.store(0, atomic::Ordering::Relaxed);
+    }
+
+    pub fn set_elim_budget(&mut self, x: i64) {
+        self.elim_budget = x;
+    }
+
+    pub fn set_per_call(&mut self, per_call: bool) {
+        self.per_call = per_call;
+    }
+
+    pub fn is_per_call(&self) -> bool {
+        self.per_call
+    }
+
+    pub fn conflict_limit(&self) -> Option<u64> {
+        if self.conflict_budget < 0 { None } else { Some(self.conflict_budget as u64) }
+    }
+
     pub fn within(&self, conflicts: u64, propagations: u64) -> bool {
-        !self.asynch_interrupt.load(atomic::Ordering::Relaxed)
+        !self.interrupted()
             && (self.conflict_budget < 0 || conflicts < self.conflict_budget as u64)
             && (self.propagation_budget < 0 || propagations < self.propagation_budget as u64)
+            && self.before_deadline()
     }
 
     pub fn interrupted(&self) -> bool {
-        self.asynch_interrupt.load(atomic::Ordering::Relaxed)
+        self.asynch_interrupt.is_interrupted()
+    }
+
+    // Preprocessing does not have conflicts, so only time and eliminated variables are limited:
+    pub fn within_elim(&self, eliminated: u64) -> bool {
+        !self.interrupted()
+            && (self.elim_budget < 0 || eliminated < self.elim_budget as u64)
+            && self.before_deadline()
     }
 
     pub fn off(&mut self) {
         self.conflict_budget = -1;
         self.propagation_budget = -1;
+        self.elim_budget = -1;
+        #[cfg(feature = "std")]
+        {
+            self.deadline = None;
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn before_deadline(&self) -> bool {
+        match self.deadline {
+            None => true,
+            Some(deadline) => {
+                let polls = self.deadline_polls.fetch_add(1, atomic::Ordering::Relaxed);
+                if polls % DEADLINE_POLL_INTERVAL != 0 {
+                    return true;
+                }
+                // Once passed, the deadline stays passed:
+                let before = Instant::now() < deadline;
+                if !before {
+                    self.deadline_polls.store(0, atomic::Ordering::Relaxed);
+                }
+                before
+            }
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn before_deadline(&self) -> bool {
+        true
+    }
+}
+
+
+// Budgets beyond 'i64::MAX' can't be reached anyway, and must not wrap to "no budget":
+fn clamp(x: u64) -> i64 {
+    i64::try_from(x).unwrap_or(i64::MAX)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_budgets() {
+        assert_eq!(Budget::new().conflict_limit(), None);
+        assert_eq!(Budget::new().conflicts(10).conflict_limit(), Some(10));
+        assert_eq!(Budget::new().conflicts(u64::MAX).conflict_limit(), Some(i64::MAX as u64));
+        assert_eq!(Budget::new().conflicts(1 << 63).conflict_limit(), Some(i64::MAX as u64));
+        assert!(Budget::new().conflicts(u64::MAX).propagations(u64::MAX).within(i64::MAX as u64 - 1, 0));
+        assert!(!Budget::new().propagations(u64::MAX).within(0, i64::MAX as u64));
+        assert!(!Budget::new().eliminations(u64::MAX).within_elim(i64::MAX as u64));
     }
 }
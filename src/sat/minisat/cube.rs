@@ -0,0 +1,104 @@
+use std::sync::{atomic, Mutex};
+use std::thread;
+use crate::sat::{SolveRes, Solver, Stats};
+use crate::sat::formula::{Lit, Var};
+use super::budget::Budget;
+use super::{CoreSettings, CoreSolver};
+
+
+const LOOKAHEAD_CONFLICTS: u64 = 100; // Conflicts spent to settle variable activities before splitting.
+
+
+pub enum CubeRes {
+    UnSAT(Stats),
+    SAT(Vec<Lit>, Stats),
+}
+
+
+// Split the search space of 'solver' into at least 'n_cubes' cubes (2^k for the smallest fitting
+// 'k') over the most active variables after a short run on a copy of it. Every total assignment
+// agrees with exactly one cube. If the short run already decides the formula, the only cube is
+// empty.
+pub fn split(solver: &CoreSolver, n_cubes: usize) -> Vec<Vec<Lit>> {
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(LOOKAHEAD_CONFLICTS as i64);
+    let parts =
+        match solver.clone().solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, probe) => probe.into_parts(),
+            _ => { return vec![Vec::new()]; }
+        };
+
+    let mut vars: Vec<Var> = (0..parts.assigns.number_of_vars())
+        .map(Var::from_index)
+        .filter(|&v| parts.assigns.is_undef(v))
+        .collect();
+    vars.sort_by(|a, b| parts.activity[b].partial_cmp(&parts.activity[a]).unwrap().then(a.cmp(b)));
+
+    let mut k = 0;
+    while (1 << k) < n_cubes && k < vars.len() {
+        k += 1;
+    }
+
+    (0..1usize << k)
+        .map(|mask| (0..k).map(|i| vars[i].sign_lit((mask >> i) & 1 == 1)).collect())
+        .collect()
+}
+
+
+// Solve 'cubes' of the formula held by 'solver' on up to 'threads' threads, each cube with a
+// fresh solver built from the shared clauses. The formula is satisfiable iff some cube is, and the
+// model returned is the one of the first satisfiable cube, however the work was scheduled.
+pub fn solve_cubes(settings: &CoreSettings, solver: &CoreSolver, cubes: &[Vec<Lit>], threads: usize) -> CubeRes {
+    if !solver.ok {
+        return CubeRes::UnSAT(solver.stats());
+    }
+
+    // Problem clauses and ground units, read by all threads:
+    let parts = solver.clone().into_parts();
+    let n_vars = parts.assigns.number_of_vars();
+    let units: Vec<[Lit; 1]> = parts.assigns.trail().iter().map(|&lit| [lit]).collect();
+    let clauses: Vec<&[Lit]> = parts.clauses.iter().map(|c| &c[..]).chain(units.iter().map(|u| &u[..])).collect();
+
+    let next = atomic::AtomicUsize::new(0);
+    let first_sat = atomic::AtomicUsize::new(usize::MAX);
+    let results = Mutex::new(Vec::with_capacity(cubes.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, atomic::Ordering::Relaxed);
+                if i >= cubes.len() {
+                    break;
+                }
+                // Cubes after a satisfiable one can't change the answer:
+                if i > first_sat.load(atomic::Ordering::Relaxed) {
+                    continue;
+                }
+
+                let cube_solver = CoreSolver::from_clauses(settings.clone(), n_vars, &clauses);
+                let res =
+                    match cube_solver.solve_limited(&Budget::new(), &cubes[i]) {
+                        SolveRes::SAT(model, stats) => {
+                            first_sat.fetch_min(i, atomic::Ordering::Relaxed);
+                            (i, Some(model), stats)
+                        }
+                        SolveRes::UnSAT(stats) => (i, None, stats),
+                        SolveRes::Interrupted(_, _) => unreachable!("Unlimited budget interrupted"),
+                        SolveRes::Invalid(err, _) => unreachable!("Cube rejected: {:?}", err),
+                    };
+                results.lock().unwrap().push(res);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(i, _, _)| i);
+
+    let mut total = Stats::default();
+    for (_, _, stats) in results.iter() {
+        total.merge(stats);
+    }
+    match results.into_iter().find_map(|(_, model, _)| model) {
+        Some(model) => CubeRes::SAT(model, total),
+        None => CubeRes::UnSAT(total),
+    }
+}
@@ -1,22 +1,43 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use core::iter;
+use alloc::vec::Vec;
 use crate::sat;
-use crate::sat::formula::{assignment::*, clause::*, LBool, Lit, LitMap, Var};
+use crate::sat::formula::{assignment::*, clause::*, util::{satisfied_with_assignment, satisfied_with_model}, LBool, Lit, LitMap, Var, VarMap};
 use self::backtrack::BacktrackableFormula;
 use self::conflict::{AnalyzeContext, CCMinMode, Conflict};
 use self::decision_heuristic::{DecisionHeuristic, DecisionHeuristicSettings};
+use self::trace::{Trace, TraceEvent};
 use self::util::*;
 use super::budget::Budget;
 
+mod activity;
 mod backtrack;
 pub mod conflict;
 pub mod clause_db;
 pub mod decision_heuristic;
 mod luby;
 pub mod simplify;
+pub mod trace;
+pub mod tractable;
 mod random;
 mod util;
 mod watches;
 
 
+const MIN_RESTART_FIRST: f64 = 1.0;
+const MIN_RESTART_INC: f64 = 1.1;
+
+// Adaptive garbage collection aims to reclaim this fraction of clause memory on every run, moving
+// the trigger within the bounds below:
+const GC_TARGET_RECLAIM: f64 = 0.3;
+const GC_MIN_FRAC: f64 = 0.05;
+const GC_MAX_FRAC: f64 = 0.5;
+
+// With 'unlock_before_reduce', restart before 'reduce' once more than this share of learnt clauses
+// is locked as reasons:
+const UNLOCK_PRESSURE: f64 = 0.25;
+
 #[derive(Clone, Copy, Debug)]
 pub struct RestartStrategy {
     pub luby_restart: bool,
@@ -35,14 +56,30 @@ impl Default for RestartStrategy {
 }
 
 impl RestartStrategy {
-    pub fn conflicts_to_go(&self, restarts: u32) -> u64 {
-        let rest_base = if self.luby_restart {
-            luby::luby(self.restart_inc, restarts)
-        } else {
-            self.restart_inc.powi(restarts as i32)
-        };
+    // Clamp limits that would make the search restart before every conflict and never finish.
+    pub fn validate(&mut self) {
+        if self.restart_first.is_nan() || self.restart_first < MIN_RESTART_FIRST {
+            warn!(target: "minisat::search", "restart_first = {} is too small, using {}", self.restart_first, MIN_RESTART_FIRST);
+            self.restart_first = MIN_RESTART_FIRST;
+        }
 
-        (rest_base * self.restart_first) as u64
+        if self.restart_inc.is_nan() || self.restart_inc < MIN_RESTART_INC {
+            warn!(target: "minisat::search", "restart_inc = {} is too small, using {}", self.restart_inc, MIN_RESTART_INC);
+            self.restart_inc = MIN_RESTART_INC;
+        }
+    }
+
+    // Conflict limits of successive restarts. Luby values are powers of 2, so the one for
+    // 'restart_inc' has the same exponent.
+    pub fn limits(&self) -> impl Iterator<Item = u64> {
+        let RestartStrategy { luby_restart, restart_first, restart_inc } = *self;
+        let mut luby = luby::LubyIterator::new();
+        let mut restarts = 0;
+        iter::from_fn(move || {
+            let exp = if luby_restart { luby.next()?.trailing_zeros() as i32 } else { restarts };
+            restarts += 1;
+            Some((luby::powi(restart_inc, exp) * restart_first) as u64)
+        })
     }
 }
 
@@ -54,6 +91,7 @@ pub struct LearningStrategy {
     pub size_inc: f64, // The limit for learnt clauses is multiplied with this factor each restart.
     pub size_adjust_start_confl: i32,
     pub size_adjust_inc: f64,
+    pub max_learnts_abs: Option<usize>, // Hard cap on the number of learnt clauses, regardless of the growth schedule.
 }
 
 impl Default for LearningStrategy {
@@ -64,6 +102,7 @@ impl Default for LearningStrategy {
             size_inc: 1.1,
             size_adjust_start_confl: 100,
             size_adjust_inc: 1.5,
+            max_learnts_abs: None,
         }
     }
 }
@@ -108,9 +147,23 @@ impl LearningGuard {
     pub fn border(&self) -> f64 {
         self.max_learnts
     }
+
+    pub fn exceeded(&self, learnts: usize, assigns: usize) -> bool {
+        (learnts as f64) >= self.max_learnts + (assigns as f64)
+            || matches!(self.settings.max_learnts_abs, Some(cap) if learnts > cap)
+    }
 }
 
 
+// Reasons for 'Searcher::decide' to reject a literal:
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DecideError {
+    UnknownVar(Var), // Not created by 'new_var'.
+    Assigned(Lit),   // Its variable has a value already.
+}
+
+
+#[derive(Clone)]
 struct SimplifyGuard {
     simp_db_assigns: Option<usize>, // Number of top-level assignments since last execution of 'simplify()'.
     simp_db_props: u64,
@@ -135,12 +188,55 @@ impl SimplifyGuard {
 }
 
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 struct Stats {
     solves: u64,
     starts: u64,
     decisions: u64,
     conflicts: u64,
+    max_trail: usize,        // Largest number of assigned variables seen after propagation.
+    last_conflict_props: u64, // Propagations counter at the moment of the latest conflict.
+    max_learnts: usize,       // Largest number of learnt clauses kept at once.
+    restart_unassigns: u64,   // Literals unassigned by restarts.
+    max_locked: usize,        // Most learnt clauses locked as reasons at a 'reduce'.
+    unlock_restarts: u64,     // Restarts forced by 'unlock_before_reduce'.
+    progress: f64,            // Progress estimate at the end of the latest search.
+    learnts: u64,             // Clauses learnt from conflicts, units included.
+    learnt_literals: u64,
+    learnt_lbd: u64,          // Sum of LBD over learnt clauses.
+    max_learnt_len: usize,
+}
+
+impl Stats {
+    fn learn(&mut self, len: usize, lbd: u32) {
+        self.learnts += 1;
+        self.learnt_literals += len as u64;
+        self.learnt_lbd += lbd as u64;
+        self.max_learnt_len = self.max_learnt_len.max(len);
+    }
+}
+
+
+// Value of variables left unassigned in a model (non-decision variables not forced by any clause):
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum DontCarePolarity {
+    #[default]
+    Omit, // Leave them out of the model.
+    False,
+    True,
+    LastAssigned, // The value a variable had when it was last assigned, left out if it never was.
+}
+
+impl DontCarePolarity {
+    // Value of every variable that is still missing from the model ('LastAssigned' is filled in
+    // during search, where the last values are known):
+    pub fn value(self) -> Option<bool> {
+        match self {
+            DontCarePolarity::Omit | DontCarePolarity::LastAssigned => None,
+            DontCarePolarity::False => Some(false),
+            DontCarePolarity::True => Some(true),
+        }
+    }
 }
 
 
@@ -148,9 +244,11 @@ struct Stats {
 pub struct SearchSettings {
     pub restart: RestartStrategy,
     pub learn: LearningStrategy,
+    pub dont_care: DontCarePolarity,
 }
 
 
+#[derive(Clone, Copy, Debug)]
 pub enum AddClauseRes {
     UnSAT,
     Consumed,
@@ -160,14 +258,43 @@ pub enum AddClauseRes {
 
 pub enum SearchRes {
     UnSAT(sat::Stats),
+    AssumpsConfl(Vec<Lit>, sat::Stats), // Subset of assumptions that can not hold together.
     SAT(Assignment, sat::Stats),
-    Interrupted(f64, Searcher),
+    Interrupted(f64, Box<Searcher>), // Boxed, as the searcher is much larger than the other variants.
+}
+
+// Final state of a searcher with clauses copied out of the clause allocator (see 'Searcher::into_parts').
+pub struct SolverParts {
+    pub clauses: Vec<Vec<Lit>>, // Live problem clauses.
+    pub learnts: Vec<Vec<Lit>>, // Live learnt clauses.
+    pub assigns: Assignment,    // Reasons of the assignment refer to clauses that no longer exist.
+    pub activity: VarMap<f64>,  // Branching activity of variables.
+}
+
+// Result of 'Searcher::search_step' that leaves the searcher at ground level, ready for another call:
+#[derive(Debug)]
+pub enum StepRes {
+    UnSAT,
+    AssumpsConfl(Vec<Lit>),
+    SAT(Vec<Lit>),
+    Interrupted(f64),
 }
 
 
+#[derive(Clone)]
 pub struct SearcherSettings {
     pub garbage_frac: f64, // The fraction of wasted memory allowed before a garbage collection is triggered.
     pub use_rcheck: bool, // Check if a clause is already implied. Prett costly, and subsumes subsumptions :)
+    pub learning: bool,   // Learn clauses from conflicts. Otherwise search is DPLL with chronological backtracking.
+    pub trail_saving: bool, // Keep decisions that would be taken again at restarts (see 'Searcher::reuse_level').
+    pub detect_tractable: bool, // Decide Horn and 2-SAT formulas with polynomial algorithms instead of search.
+    pub adaptive_gc: bool, // Adjust 'garbage_frac' after every collection depending on the memory it reclaimed.
+    pub check_watches: bool, // Verify watches after garbage collection and elimination (in debug builds only).
+    pub simplify_on_add: bool, // Clean problem clauses added so far whenever an added clause yields new ground units.
+    pub shuffle_literals: Option<u64>, // Seed to permute literals of problem clauses, and so their initial watches.
+    pub quiet: bool, // Never log the search statistics table, whatever the log level ('minisat::search' target).
+    pub packed_gc: bool, // Keep clause headers on one cache line with watched literals, collect learnt clauses by activity.
+    pub unlock_before_reduce: bool, // Restart before 'reduce' if many learnt clauses are locked as reasons (see 'UNLOCK_PRESSURE').
 }
 
 impl Default for SearcherSettings {
@@ -175,6 +302,16 @@ impl Default for SearcherSettings {
         SearcherSettings {
             garbage_frac: 0.20,
             use_rcheck: false,
+            learning: true,
+            trail_saving: false,
+            detect_tractable: false,
+            adaptive_gc: false,
+            check_watches: false,
+            simplify_on_add: false,
+            shuffle_literals: None,
+            quiet: false,
+            packed_gc: false,
+            unlock_before_reduce: false,
         }
     }
 }
@@ -194,20 +331,58 @@ pub struct SearchCtx {
     db: clause_db::ClauseDB,
     heur: DecisionHeuristic,
     analyze: AnalyzeContext,
+    bumped: Vec<Var>, // Variables to bump after the current conflict analysis.
+    lbd: LbdStamps,
     simp: SimplifyGuard,
+    trace: Option<Trace>,
+}
+
+// A trace is bound to one line of search, so copies start without it.
+impl Clone for SearchCtx {
+    fn clone(&self) -> Self {
+        SearchCtx {
+            stats: self.stats.clone(),
+            db: self.db.clone(),
+            heur: self.heur.clone(),
+            analyze: self.analyze.clone(),
+            bumped: self.bumped.clone(),
+            lbd: self.lbd.clone(),
+            simp: self.simp.clone(),
+            trace: None,
+        }
+    }
 }
 
 impl SearchCtx {
-    pub fn new(db_set: clause_db::ClauseDBSettings, heur_set: DecisionHeuristicSettings, ccmin_mode: CCMinMode) -> Self {
+    pub fn new(
+        db_set: clause_db::ClauseDBSettings,
+        heur_set: DecisionHeuristicSettings,
+        ccmin_mode: CCMinMode,
+        minimize_with_binary: bool,
+    ) -> Self {
         SearchCtx {
             stats: Stats::default(),
-            db: clause_db::ClauseDB::new(db_set),
+            db: clause_db::ClauseDB::new(db_set, heur_set.deterministic),
             heur: DecisionHeuristic::new(heur_set),
-            analyze: AnalyzeContext::new(ccmin_mode),
+            analyze: AnalyzeContext::new(ccmin_mode, minimize_with_binary),
+            bumped: Vec::new(),
+            lbd: LbdStamps::new(),
             simp: SimplifyGuard::new(),
+            trace: None,
         }
     }
 
+    fn trace<F: FnOnce() -> TraceEvent>(&mut self, event: F) {
+        if let Some(ref mut trace) = self.trace {
+            trace.event(event());
+        }
+    }
+
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.heur.reserve_vars(n);
+        self.analyze.reserve_vars(n);
+    }
+
     pub fn init_var(&mut self, v: Var, upol: Option<bool>, dvar: bool) {
         self.heur.init_var(v, upol, dvar);
         self.analyze.init_var(v);
@@ -236,18 +411,26 @@ impl SearchCtx {
         Ok(self.heur.pick_branch_lit(assigns))
     }
 
-    fn analyze(&mut self, assigns: &Assignment, ca: &mut ClauseAllocator, confl: ClauseRef) -> conflict::Conflict {
-        self.analyze.analyze(assigns, ca,
+    fn analyze(&mut self, bt: &mut backtrack::BacktrackableFormula, confl: ClauseRef) -> conflict::Conflict {
+        self.bumped.clear();
+        let conflict = self.analyze.analyze(&bt.assigns, &mut bt.ca, &bt.watches,
             confl,
             {
-                let heur = &mut self.heur;
-                move |v| heur.bump_activity(&v)
+                let bumped = &mut self.bumped;
+                move |v| bumped.push(v)
             },
             {
                 let db = &mut self.db;
                 move |ca, c| db.bump_activity(ca, c)
             }
-        )
+        );
+        self.heur.bump_activities(&self.bumped);
+        match conflict {
+            Conflict::Learned(_, _, ref clause) => self.heur.bump_learnt(clause),
+            Conflict::Unit(_, unit) => self.heur.bump_learnt(&[unit]),
+            Conflict::Ground => {}
+        }
+        conflict
     }
 
     fn cancel_until(&mut self, assigns: &Assignment, level: DecisionLevel) {
@@ -260,25 +443,31 @@ impl SearchCtx {
         }
     }
 
-    fn handle_conflict(&mut self, learnt: &mut LearningGuard, bt: &mut BacktrackableFormula, confl: ClauseRef)
+    fn handle_conflict(&mut self, learnt: &mut LearningGuard, bt: &mut BacktrackableFormula, confl: ClauseRef, quiet: bool)
         -> Option<(DecisionLevel, Lit, Option<ClauseRef>)>
     {
         self.stats.conflicts += 1;
 
         let res =
-            match self.analyze(&bt.assigns, &mut bt.ca, confl) {
+            match self.analyze(bt, confl) {
                 Conflict::Ground => {
+                    self.trace(|| TraceEvent::Conflict(Vec::new()));
                     return None;
                 }
 
                 Conflict::Unit(level, unit) => {
+                    self.trace(|| TraceEvent::Conflict(vec![unit]));
+                    self.stats.learn(1, 1);
                     self.cancel_until(&bt.assigns, level);
                     (level, unit, None)
                 }
 
                 Conflict::Learned(level, lit, clause) => {
+                    self.trace(|| TraceEvent::Conflict(clause.clone()));
+                    let lbd = self.lbd.lbd(&bt.assigns, &clause);
+                    self.stats.learn(clause.len(), lbd);
                     self.cancel_until(&bt.assigns, level);
-                    let cr = self.db.learn_clause(&mut bt.ca, &clause[..]);
+                    let cr = self.db.learn_clause(&mut bt.ca, &clause[..], lbd);
                     (level, lit, Some(cr))
                 }
             };
@@ -286,8 +475,9 @@ impl SearchCtx {
         self.heur.decay_activity();
         self.db.decay_activity();
 
-        if learnt.bump() {
+        if learnt.bump() && !quiet {
             info!(
+                target: "minisat::search",
                 "| {:9} | {:7} {:8} {:8} | {:8} {:8} {:6.0} | {:6.3} % |",
                 self.stats.conflicts,
                 self.heur.dec_vars - bt.assigns.number_of_ground_assigns(),
@@ -305,10 +495,18 @@ impl SearchCtx {
 }
 
 
+#[derive(Clone)]
 pub struct Searcher {
     settings: SearcherSettings,
     bt: backtrack::BacktrackableFormula,
-    ctx: SearchCtx
+    ctx: SearchCtx,
+    best: Option<Vec<Lit>>, // Largest conflict-free trail seen so far (only if tracking is on).
+    best_len: usize, // Length of the best trail, copied to 'best' only before it is undone.
+    flipped: Vec<DecisionLevel>, // Levels with flipped decisions when learning is off.
+    fragment: Option<tractable::Fragment>, // Tractable fragment found by the latest 'preprocess'.
+    gc_frac: f64, // Garbage fraction that triggers collection, 'garbage_frac' unless 'adaptive_gc' is on.
+    shuffle: Option<random::Random>, // Generator for 'shuffle_literals'.
+    kept: Vec<Lit>, // Assumptions of the last 'search_step' still assigned at the levels right above ground.
 }
 
 impl Searcher {
@@ -317,11 +515,23 @@ impl Searcher {
         db_set: clause_db::ClauseDBSettings,
         heur_set: DecisionHeuristicSettings,
         ccmin_mode: CCMinMode,
+        minimize_with_binary: bool,
     ) -> Self {
+        let gc_frac = settings.garbage_frac;
+        let shuffle = settings.shuffle_literals.map(random::Random::from_u64);
+        let mut bt = backtrack::BacktrackableFormula::new();
+        bt.ca.pack_lines = settings.packed_gc;
         Searcher {
             settings,
-            bt: backtrack::BacktrackableFormula::new(),
-            ctx: SearchCtx::new(db_set, heur_set, ccmin_mode)
+            bt,
+            ctx: SearchCtx::new(db_set, heur_set, ccmin_mode, minimize_with_binary),
+            best: None,
+            best_len: 0,
+            flipped: Vec::new(),
+            fragment: None,
+            gc_frac,
+            shuffle,
+            kept: Vec::new(),
         }
     }
 
@@ -333,112 +543,451 @@ impl Searcher {
         self.ctx.db.stats.num_clauses
     }
 
+    pub fn max_clause_len(&self) -> usize {
+        self.ctx.db.stats.max_clause_len
+    }
+
+    // Check that 'model' satisfies top-level assignments and all problem clauses:
+    pub fn satisfied_by(&self, model: &VarMap<bool>) -> bool {
+        let units_ok = self.bt.assigns.trail_at(GROUND_LEVEL).iter()
+            .all(|&lit| satisfied_with_model(&[lit], model));
+
+        units_ok && self.ctx.db.clauses().iter()
+            .all(|&cr| self.bt.ca.is_deleted(cr) || satisfied_with_model(self.bt.ca.literals(cr), model))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn track_best_assignment(&mut self) {
+        if self.best.is_none() {
+            self.best = Some(Vec::new());
+            self.best_len = 0;
+        }
+    }
+
+    // Stop tracking and return the largest partial assignment found:
+    #[cfg(feature = "std")]
+    pub fn take_best_assignment(&mut self) -> Vec<Lit> {
+        self.save_best();
+        self.best.take().unwrap_or_default()
+    }
+
+    // Copy the best trail before backtracking undoes it. The trail only grows in between, so its
+    // first 'best_len' literals are still the ones that were conflict-free.
+    fn save_best(&mut self) {
+        if let Some(ref mut best) = self.best {
+            if self.best_len > best.len() {
+                best.clear();
+                best.extend_from_slice(&self.bt.assigns.trail()[..self.best_len]);
+            }
+        }
+    }
+
+    pub fn assigns(&self) -> &Assignment {
+        &self.bt.assigns
+    }
+
+    // Problem or learnt clause 'cr' if it is still in the database:
+    pub fn clause(&self, cr: ClauseRef) -> Option<ClauseView<'_>> {
+        if self.bt.ca.is_clause(cr) && !self.bt.ca.is_deleted(cr) {
+            Some(ClauseView::new(self.bt.ca.view(cr), self.ctx.db.is_fixed()))
+        } else {
+            None
+        }
+    }
+
+    pub fn reason_of(&self, v: Var) -> Option<&[Lit]> {
+        self.bt.assigns.reason_of(&self.bt.ca, v)
+    }
+
+    pub fn ccmin_mode(&self) -> CCMinMode {
+        self.ctx.analyze.ccmin_mode()
+    }
+
+    // Learn from 'confl' as search does on a conflict, bumping activities on the way, for a search
+    // loop driven from the outside (e.g. with theory lemmas). 'confl' has to have all literals false
+    // and some at the current decision level, as if propagation ran into it, otherwise 'None' is
+    // returned. It is not added to the formula, and the assignment is left as it is: keeping the
+    // lemma, backjumping and asserting the result is up to the caller.
+    pub fn analyze_conflict(&mut self, confl: &[Lit]) -> Option<Conflict> {
+        let assigns = &self.bt.assigns;
+        let is_false = |lit: Lit| lit.var() < Var::from_index(assigns.number_of_vars()) && assigns.is_assigned_neg(lit);
+        if !confl.iter().all(|&lit| is_false(lit)) {
+            return None;
+        }
+
+        if assigns.is_ground_level() {
+            Some(Conflict::Ground)
+        } else if !confl.iter().any(|&lit| assigns.vardata(lit).level == assigns.current_level()) {
+            None
+        } else if let [lit] = *confl {
+            Some(Conflict::Unit(GROUND_LEVEL, lit))
+        } else {
+            let (_, cr) = self.bt.ca.alloc(confl, ClauseHeader::Clause { abstraction: None });
+            let conflict = self.ctx.analyze(&mut self.bt, cr);
+            self.bt.ca.free(cr);
+            Some(conflict)
+        }
+    }
+
+    // Decide 'lit' on a new level and propagate it, returning the conflicting clause if any. Together
+    // with 'checkpoint' and 'restore' this drives search from the outside.
+    pub fn decide(&mut self, lit: Lit) -> Result<Option<ClauseRef>, DecideError> {
+        if lit.var() >= Var::from_index(self.bt.assigns.number_of_vars()) {
+            return Err(DecideError::UnknownVar(lit.var()));
+        }
+        if !self.bt.assigns.is_undef(lit.var()) {
+            return Err(DecideError::Assigned(lit));
+        }
+
+        self.bt.push_decision(lit);
+        Ok(self.bt.propagate())
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.bt.assigns.checkpoint()
+    }
+
+    // Undo decisions made after 'cp' was taken. Clauses learnt meanwhile are kept, and assumptions
+    // kept by 'search_step' above its level are released. A checkpoint that can't be returned to
+    // changes nothing.
+    pub fn restore(&mut self, cp: Checkpoint) -> Result<(), CheckpointError> {
+        self.bt.assigns.check_checkpoint(cp)?;
+        self.save_best();
+        self.ctx.cancel_until(&self.bt.assigns, cp.level());
+        self.bt.assigns.restore(cp)?;
+        self.kept.truncate(cp.level().offset_from_ground());
+        Ok(())
+    }
+
+    pub fn progress(&self) -> f64 {
+        self.ctx.stats.progress
+    }
+
+    pub fn clauses_with(&self, lit: Lit) -> Vec<&[Lit]> {
+        self.ctx.db.clauses_with(&self.bt.ca, lit)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn set_trace(&mut self, trace: Option<Trace>) {
+        self.ctx.trace = trace;
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.ctx.trace.take()
+    }
+
+    pub fn set_deletion_hook(&mut self, hook: Option<clause_db::DeletionHook>) {
+        self.ctx.db.on_clause_deleted = hook;
+    }
+
+    // Live problem clauses, preceded by literals fixed at ground level as unit clauses.
+    pub fn problem_clauses(&self) -> Vec<Vec<Lit>> {
+        let ca = &self.bt.ca;
+        let units = self.bt.assigns.trail_at(GROUND_LEVEL).iter().map(|&lit| vec![lit]);
+        let clauses = self.ctx.db.clauses().iter().filter(|&&cr| !ca.is_deleted(cr)).map(|&cr| ca.literals(cr).to_vec());
+        units.chain(clauses).collect()
+    }
+
+    pub fn into_parts(mut self) -> SolverParts {
+        self.release_assumptions();
+        let ca = &self.bt.ca;
+        let copy = |crs: &[ClauseRef]| -> Vec<Vec<Lit>> {
+            crs.iter().filter(|&&cr| !ca.is_deleted(cr)).map(|&cr| ca.literals(cr).to_vec()).collect()
+        };
+
+        SolverParts {
+            clauses: copy(self.ctx.db.clauses()),
+            learnts: copy(self.ctx.db.learnts()),
+            assigns: self.bt.assigns,
+            activity: self.ctx.heur.into_activity(),
+        }
+    }
+
+    // Drop all clauses, assignments and statistics, keeping variables. Activities and saved
+    // phases are kept too if 'keep_heuristics'.
+    pub fn reset(&mut self, keep_heuristics: bool) {
+        let n_vars = self.number_of_vars();
+        self.bt = backtrack::BacktrackableFormula::new();
+        self.bt.ca.pack_lines = self.settings.packed_gc;
+        for _ in 0..n_vars {
+            self.bt.new_var();
+        }
+
+        self.ctx.stats = Stats::default();
+        let hook = self.ctx.db.on_clause_deleted.take();
+        self.ctx.db = clause_db::ClauseDB::new(self.ctx.db.settings.clone(), self.ctx.db.is_fixed());
+        self.ctx.db.on_clause_deleted = hook;
+        self.ctx.heur.reset(keep_heuristics);
+        self.ctx.heur.rebuild_order_heap(&self.bt.assigns);
+        self.ctx.bumped.clear();
+        self.ctx.simp = SimplifyGuard::new();
+        if self.best.is_some() {
+            self.best = Some(Vec::new());
+        }
+        self.best_len = 0;
+        self.flipped.clear();
+        self.fragment = None;
+        self.gc_frac = self.settings.garbage_frac;
+        self.shuffle = self.settings.shuffle_literals.map(random::Random::from_u64);
+        self.kept.clear();
+    }
+
+    // Room for 'n' more variables in per-variable state, so that 'new_var' does not reallocate it.
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.bt.reserve_vars(n);
+        self.ctx.reserve_vars(n);
+    }
+
     pub fn new_var(&mut self, upol: Option<bool>, dvar: bool) -> Var {
         let v = self.bt.new_var();
         self.ctx.init_var(v, upol, dvar);
         v
     }
 
-    pub fn add_clause(&mut self, clause: &[Lit]) -> AddClauseRes {
-        // TODO: it should be here to work identical to original MiniSat. Probably not the best place.
-        if self.settings.use_rcheck && is_implied(&mut self.bt, &mut self.ctx.heur, &clause) {
-            return AddClauseRes::Consumed;
-        }
+    pub fn set_decision_var(&mut self, v: Var, dvar: bool) {
+        self.ctx.heur.set_decision_var(v, dvar);
+    }
 
-        let ps = {
-            let mut ps = clause.to_vec();
+    pub fn set_var_activity(&mut self, v: Var, act: f64) {
+        self.ctx.heur.set_activity(&v, act);
+    }
 
-            // Check if clause is satisfied and remove false/duplicate literals:
-            ps.sort();
-            ps.dedup();
-            ps.retain(|&lit| !self.bt.assigns.is_assigned_neg(lit));
+    pub fn var_activity(&self, v: Var) -> f64 {
+        self.ctx.heur.activity(v)
+    }
 
-            {
-                let mut prev = None;
-                for &lit in ps.iter() {
-                    if self.bt.assigns.is_assigned_pos(lit) || prev == Some(!lit) {
-                        return AddClauseRes::Consumed;
-                    }
-                    prev = Some(lit);
-                }
-            }
+    pub fn set_decision_prefix(&mut self, vars: &[Var]) {
+        self.ctx.heur.set_decision_prefix(vars);
+    }
 
-            ps
-        };
+    pub fn record_conflict_sides(&mut self, enable: bool) {
+        self.ctx.analyze.record_sides(enable);
+    }
 
-        match &ps[..] {
-            [] => { AddClauseRes::UnSAT }
+    pub fn last_conflict_sides(&self) -> Option<&conflict::ConflictSides> {
+        self.ctx.analyze.last_sides()
+    }
+
+    // Sort the clause and remove false and duplicate literals. Returns 'None' if it is satisfied.
+    fn prepare_clause(&self, clause: &[Lit]) -> Option<Vec<Lit>> {
+        let mut ps = clause.to_vec();
+        ps.sort();
+        ps.dedup();
+        ps.retain(|&lit| !self.bt.assigns.is_assigned_neg(lit));
+
+        let mut prev = None;
+        for &lit in ps.iter() {
+            if self.bt.assigns.is_assigned_pos(lit) || prev == Some(!lit) {
+                return None;
+            }
+            prev = Some(lit);
+        }
+
+        Some(ps)
+    }
 
-            [unit] => {
-                self.bt.assigns.assign_lit(*unit, None);
-                match self.bt.propagate() {
-                    None => AddClauseRes::Consumed,
-                    Some(_) => AddClauseRes::UnSAT,
+    // Clauses added later are cleaned against 'unit' by 'prepare_clause'. With 'simplify_on_add',
+    // the ones added earlier are cleaned too, at the cost of a pass over all clauses per unit. As
+    // propagation is at fixpoint, a clause left unsatisfied still has both watches unassigned.
+    fn add_unit(&mut self, unit: Lit) -> AddClauseRes {
+        self.bt.assigns.assign_lit(unit, None);
+        match self.bt.propagate() {
+            None => {
+                if self.settings.simplify_on_add {
+                    self.remove_satisfied();
                 }
+                AddClauseRes::Consumed
             }
+            Some(_) => AddClauseRes::UnSAT,
+        }
+    }
 
-            lits => {
-                let cr = self.ctx.db.add_clause(&mut self.bt.ca, lits);
-                self.bt.attach(cr);
-                AddClauseRes::Added(cr)
+    // Literals of learnt clauses with LBD not above 'max_lbd'.
+    #[cfg(feature = "std")]
+    pub fn learnts_upto_lbd(&self, max_lbd: u32) -> Vec<Vec<Lit>> {
+        self.ctx.db.learnts().iter()
+            .filter(|&&cr| !self.bt.ca.is_deleted(cr))
+            .map(|&cr| self.bt.ca.view(cr))
+            .filter(|c| c.header.lbd() <= max_lbd)
+            .map(|c| c.lits().to_vec())
+            .collect()
+    }
+
+    // If the formula left at ground level is Horn or 2-SAT, decide it with a polynomial algorithm:
+    // 'Some' of a model of its unassigned variables, or of 'None' if it is UNSAT. Returns 'None'
+    // if the formula belongs to neither fragment.
+    fn tractable_model(&mut self) -> Option<Option<VarMap<bool>>> {
+        let mut clauses = Vec::new();
+        for &cr in self.ctx.db.clauses().iter() {
+            let c = self.bt.ca.view(cr);
+            if !c.is_deleted() && !satisfied_with_assignment(c.lits(), &self.bt.assigns) {
+                clauses.push(c.lits().iter().cloned().filter(|&lit| self.bt.assigns.is_undef(lit.var())).collect());
             }
         }
+
+        self.fragment = tractable::detect(&clauses);
+        self.fragment.map(|fragment| tractable::solve(fragment, &clauses))
     }
 
-    pub fn preprocess(&mut self) -> bool {
-        if let None = self.bt.propagate() {
-            self.try_simplify();
-            true
-        } else {
-            false
+    // Decide a Horn or 2-SAT formula without search. The model is assigned at ground level, as
+    // the searcher is done after it.
+    fn search_tractable(&mut self) -> Option<LoopRes> {
+        self.cancel_until(GROUND_LEVEL);
+        if self.bt.propagate().is_some() {
+            return Some(LoopRes::UnSAT);
+        }
+
+        let model = self.tractable_model()?;
+        self.ctx.stats.solves += 1;
+        match model {
+            None => Some(LoopRes::UnSAT),
+            Some(model) => {
+                for (v, &value) in model.iter() {
+                    self.bt.assigns.assign_lit(v.sign_lit(!value), None);
+                }
+                self.ctx.stats.progress = progress_estimate(&self.bt.assigns);
+                Some(LoopRes::SAT)
+            }
         }
     }
 
-    pub fn search(self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> SearchRes {
-        info!("============================[ Search Statistics ]==============================");
-        info!("| Conflicts |          ORIGINAL         |          LEARNT          | Progress |");
-        info!("|           |    Vars  Clauses Literals |    Limit  Clauses Lit/Cl |          |");
-        info!("===============================================================================");
+    pub fn tractable_fragment(&self) -> Option<tractable::Fragment> {
+        self.fragment
+    }
+
+    // Unassigned variables that were assigned before, with their last values:
+    fn last_assigned(&self) -> impl Iterator<Item = Lit> + '_ {
+        self.bt.assigns.vars()
+            .filter(move |&v| self.bt.assigns.is_undef(v))
+            .filter_map(move |v| self.ctx.heur.last_assigned(v))
+    }
+
+    pub fn search(mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> SearchRes {
+        self.release_assumptions();
+        #[cfg(debug_assertions)]
+        self.check_assignment();
+
+        let quiet = self.settings.quiet;
+        if !quiet {
+            info!(target: "minisat::search", "============================[ Search Statistics ]==============================");
+            info!(target: "minisat::search", "| Conflicts |          ORIGINAL         |          LEARNT          | Progress |");
+            info!(target: "minisat::search", "|           |    Vars  Clauses Literals |    Limit  Clauses Lit/Cl |          |");
+            info!(target: "minisat::search", "===============================================================================");
+        }
 
         let res = self.search_internal(ss, budget, assumptions);
 
-        info!("===============================================================================");
+        if !quiet {
+            info!(target: "minisat::search", "===============================================================================");
+        }
         res
     }
 
     fn search_internal(mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> SearchRes {
+        let res =
+            if self.settings.detect_tractable && assumptions.is_empty() {
+                self.search_tractable()
+            } else {
+                None
+            };
+
+        match res.unwrap_or_else(|| self.run(ss, budget, assumptions)) {
+            LoopRes::SAT => {
+                let stats = self.stats();
+                // The searcher is done, so its assignment can take the last values:
+                if ss.dont_care == DontCarePolarity::LastAssigned {
+                    for lit in self.last_assigned().collect::<Vec<_>>() {
+                        self.bt.assigns.assign_lit(lit, None);
+                    }
+                }
+                SearchRes::SAT(self.bt.assigns, stats)
+            }
+
+            LoopRes::AssumpsConfl(confl) => {
+                let failed = confl.iter().map(|(lit, _)| !lit).collect();
+                SearchRes::AssumpsConfl(failed, self.stats())
+            }
+
+            LoopRes::UnSAT => SearchRes::UnSAT(self.stats()),
+            LoopRes::Interrupted(c) => SearchRes::Interrupted(c, Box::new(self)),
+            LoopRes::Restart => unreachable!(),
+        }
+    }
+
+    // Same as 'search', but keeps the searcher for further calls with other assumptions. Levels
+    // of assumptions shared with the previous call are kept, so only the rest are propagated.
+    pub fn search_step(&mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> StepRes {
+        let shared = self.kept.iter().zip(assumptions.iter()).take_while(|(a, b)| a == b).count();
+        let level = self.assumption_level(shared);
+        self.cancel_until(level);
+        self.kept.clear();
+
+        match self.run(ss, budget, assumptions) {
+            LoopRes::SAT => {
+                let mut model = Vec::with_capacity(self.bt.assigns.number_of_vars());
+                self.bt.assigns.extract_model_into(&mut model);
+                if ss.dont_care == DontCarePolarity::LastAssigned {
+                    model.extend(self.last_assigned());
+                    model.sort();
+                }
+                self.keep_assumptions(assumptions);
+                StepRes::SAT(model)
+            }
+
+            LoopRes::AssumpsConfl(confl) => {
+                self.keep_assumptions(assumptions);
+                StepRes::AssumpsConfl(confl.iter().map(|(lit, _)| !lit).collect())
+            }
+
+            LoopRes::UnSAT => StepRes::UnSAT,
+            LoopRes::Interrupted(c) => StepRes::Interrupted(c),
+            LoopRes::Restart => unreachable!(),
+        }
+    }
+
+    // Search with restarts until any final result.
+    fn run(&mut self, ss: &SearchSettings, budget: &Budget, assumptions: &[Lit]) -> LoopRes {
         self.ctx.stats.solves += 1;
         let mut learnt = LearningGuard::new(ss.learn);
         learnt.reset(self.ctx.db.stats.num_clauses);
 
-        let mut curr_restarts = 0;
-        loop {
-            let conflicts_to_go = ss.restart.conflicts_to_go(curr_restarts);
-            match self.search_loop(conflicts_to_go, budget, &mut learnt, assumptions) {
+        // Budget is checked against counters since this point if it is per-call:
+        let base =
+            if budget.is_per_call() {
+                (self.ctx.stats.conflicts, self.bt.propagations())
+            } else {
+                (0, 0)
+            };
+
+        // Chronological backtracking is complete only without restarts:
+        self.flipped.clear();
+        let mut limits = ss.restart.limits();
+        let res = loop {
+            let conflicts_to_go =
+                if self.settings.learning { limits.next().unwrap_or(u64::MAX) } else { u64::MAX };
+            match self.search_loop(conflicts_to_go, budget, base, &mut learnt, assumptions) {
                 LoopRes::Restart => {
-                    curr_restarts += 1;
+                    self.ctx.trace(|| TraceEvent::Restart);
                 }
 
-                LoopRes::SAT => {
-                    let stats = self.stats();
-                    return SearchRes::SAT(self.bt.assigns, stats);
+                LoopRes::Interrupted(c) => {
+                    break LoopRes::Interrupted(c);
                 }
 
-                LoopRes::UnSAT => {
-                    return SearchRes::UnSAT(self.stats());
+                res => {
+                    self.ctx.stats.progress = progress_estimate(&self.bt.assigns);
+                    break res;
                 }
+            }
+        };
 
-                LoopRes::AssumpsConfl(_) => {
-                    // TODO: implement properly
-                    self.cancel_until(GROUND_LEVEL);
-                    return SearchRes::UnSAT(self.stats());
-                }
+        self.ctx.heur.decay_priorities();
 
-                LoopRes::Interrupted(c) => {
-                    return SearchRes::Interrupted(c, self);
-                }
-            }
+        if let Some(ref mut trace) = self.ctx.trace {
+            trace.flush();
         }
+        res
     }
 
     // Description:
@@ -453,32 +1002,64 @@ impl Searcher {
         &mut self,
         nof_conflicts: u64,
         budget: &Budget,
+        (base_conflicts, base_propagations): (u64, u64),
         learnt: &mut LearningGuard,
         assumptions: &[Lit],
     ) -> LoopRes {
         self.ctx.stats.starts += 1;
 
-        let confl_limit = self.ctx.stats.conflicts + nof_conflicts;
+        let confl_limit = self.ctx.stats.conflicts.saturating_add(nof_conflicts);
+
+        // Per-call conflict budget is exact, so it may cut a streak of conflicts short:
+        let budget_confl_limit =
+            match budget.conflict_limit() {
+                Some(limit) if budget.is_per_call() => base_conflicts.saturating_add(limit),
+                _ => u64::MAX,
+            };
+
         loop {
-            if !self.propagate_learn_backtrack(learnt) {
-                return LoopRes::UnSAT;
+            if let Some(res) = self.propagate_learn_backtrack(learnt, budget_confl_limit, assumptions) {
+                return res;
+            }
+
+            if self.best.is_some() {
+                self.best_len = self.best_len.max(self.bt.assigns.number_of_assigns());
             }
 
-            if !budget.within(self.ctx.stats.conflicts, self.bt.propagations()) {
+            if !budget.within(self.ctx.stats.conflicts - base_conflicts, self.bt.propagations() - base_propagations) {
                 let progress_estimate = progress_estimate(&self.bt.assigns);
+                self.ctx.stats.progress = progress_estimate;
                 self.cancel_until(GROUND_LEVEL);
                 return LoopRes::Interrupted(progress_estimate);
             }
 
             if self.ctx.stats.conflicts >= confl_limit {
-                self.cancel_until(GROUND_LEVEL);
+                // Problem clauses are only simplified at ground level, which levels kept for
+                // assumptions would otherwise never let the search get back to:
+                let level =
+                    if self.settings.trail_saving && !self.simplify_due() {
+                        self.reuse_level(assumptions.len())
+                    } else {
+                        GROUND_LEVEL
+                    };
+                self.ctx.stats.restart_unassigns += self.bt.assigns.trail_above(level).len() as u64;
+                self.cancel_until(level);
                 return LoopRes::Restart;
             }
 
             // Simplify the set of problem clauses:
             self.try_simplify();
 
-            if (self.ctx.db.number_of_learnts() as f64) >= learnt.border() + (self.bt.assigns.number_of_assigns() as f64) {
+            if learnt.exceeded(self.ctx.db.number_of_learnts(), self.bt.assigns.number_of_assigns()) {
+                // Clauses locked as reasons can't go, deep trails may lock a good part of them:
+                let locked = self.ctx.db.number_of_locked(&self.bt.ca, &self.bt.assigns);
+                self.ctx.stats.max_locked = self.ctx.stats.max_locked.max(locked);
+                if self.settings.unlock_before_reduce && locked as f64 > UNLOCK_PRESSURE * self.ctx.db.number_of_learnts() as f64 {
+                    self.ctx.stats.unlock_restarts += 1;
+                    self.ctx.stats.restart_unassigns += self.bt.assigns.trail_above(GROUND_LEVEL).len() as u64;
+                    self.cancel_until(GROUND_LEVEL);
+                }
+
                 // Reduce the set of learnt clauses:
                 {
                     let watches = &mut self.bt.watches;
@@ -494,17 +1075,38 @@ impl Searcher {
                 Err(confl) => { return LoopRes::AssumpsConfl(confl) }
                 Ok(None) => { return LoopRes::SAT } // Model found:
                 Ok(Some(next)) => {
+                    self.ctx.trace(|| TraceEvent::Decision(next));
                     self.bt.push_decision(next);
                 }
             }
         }
     }
 
-    fn propagate_learn_backtrack(&mut self, learnt: &mut LearningGuard) -> bool {
-        while let Some(confl) = self.bt.propagate() {
-            match self.ctx.handle_conflict(learnt, &mut self.bt, confl) {
-                None => { return false; }
+    fn propagate_learn_backtrack(&mut self, learnt: &mut LearningGuard, confl_limit: u64, assumptions: &[Lit]) -> Option<LoopRes> {
+        while self.ctx.stats.conflicts < confl_limit {
+            let confl = self.bt.propagate();
+            self.ctx.stats.max_trail = self.ctx.stats.max_trail.max(self.bt.assigns.number_of_assigns());
+            let confl =
+                match confl {
+                    None => { break; }
+                    Some(confl) => confl,
+                };
+            self.ctx.stats.last_conflict_props = self.bt.propagations();
+
+            if !self.settings.learning {
+                self.ctx.stats.conflicts += 1;
+                self.ctx.trace(|| TraceEvent::Conflict(Vec::new()));
+                if let Some(res) = self.flip_last_decision(assumptions) {
+                    return Some(res);
+                }
+                continue;
+            }
+
+            match self.ctx.handle_conflict(learnt, &mut self.bt, confl, self.settings.quiet) {
+                None => { return Some(LoopRes::UnSAT); }
                 Some((level, lit, reason)) => {
+                    self.ctx.stats.max_learnts = self.ctx.stats.max_learnts.max(self.ctx.db.number_of_learnts());
+                    self.save_best();
                     self.bt.assigns.backtrack_to(level);
                     self.bt.assigns.assign_lit(lit, reason);
                     for &cr in reason.iter() {
@@ -513,25 +1115,54 @@ impl Searcher {
                 }
             }
         }
-        true
+        None
+    }
+
+    // Undo decisions that were already flipped, then flip the latest one that was not. Levels of
+    // assumptions are never flipped: running into them means that the assumptions failed.
+    fn flip_last_decision(&mut self, assumptions: &[Lit]) -> Option<LoopRes> {
+        loop {
+            let level = self.bt.assigns.current_level();
+            if level.is_ground() {
+                return Some(LoopRes::UnSAT);
+            }
+            if level.offset_from_ground() <= assumptions.len() {
+                let mut confl = LitMap::new();
+                for &p in assumptions.iter() {
+                    confl.insert(&!p, ());
+                }
+                return Some(LoopRes::AssumpsConfl(confl));
+            }
+
+            let decision = self.bt.assigns.trail_at(level)[0];
+            self.cancel_until(level.below());
+            if self.flipped.last() == Some(&level) {
+                self.flipped.pop();
+            } else {
+                self.ctx.trace(|| TraceEvent::Decision(!decision));
+                self.bt.push_decision(!decision);
+                self.flipped.push(level);
+                return None;
+            }
+        }
     }
 
     // Description:
     //   Simplify the clause database according to the current top-level assigment. Currently, the only
     //   thing done here is the removal of satisfied clauses, but more things can be put here.
     fn try_simplify(&mut self) {
-        if !self.bt.is_ground_level()
-            || self.ctx.simp.skip(self.bt.assigns.number_of_assigns(), self.bt.propagations())
-        {
-            return;
+        if self.bt.is_ground_level() && self.simplify_due() {
+            self.simplify_db();
         }
+    }
 
-        {
-            let watches = &mut self.bt.watches;
-            self.ctx.db.remove_satisfied(&mut self.bt.ca, &self.bt.assigns, move |c| {
-                watches.unwatch_clause_lazy(c);
-            });
-        }
+    // There are new ground assignments, and enough propagations were made since the last time:
+    fn simplify_due(&self) -> bool {
+        !self.ctx.simp.skip(self.bt.assigns.number_of_ground_assigns(), self.bt.propagations())
+    }
+
+    fn simplify_db(&mut self) {
+        self.remove_satisfied();
 
         //        // TODO: why if?
         //        if self.db.settings.remove_satisfied {
@@ -567,20 +1198,139 @@ impl Searcher {
         ); // (shouldn't depend on stats really, but it will do for now)
     }
 
+    // Remove satisfied clauses and strip literals false at ground level from the rest.
+    fn remove_satisfied(&mut self) {
+        let watches = &mut self.bt.watches;
+        self.ctx.db.remove_satisfied(&mut self.bt.ca, &self.bt.assigns, move |c| {
+            watches.unwatch_clause_lazy(c);
+        });
+    }
+
+    // Level to restart from instead of the ground one: the heuristic would pick decisions of all
+    // levels up to it again right away, as they are more active than any unassigned variable, so
+    // there is no point in undoing and propagating them once more. Assumptions are always kept, so
+    // restarts go to ground level instead when the problem clauses are due to be simplified.
+    fn reuse_level(&mut self, assumptions: usize) -> DecisionLevel {
+        let next = self.ctx.heur.next_activity(&self.bt.assigns);
+        let mut reuse = GROUND_LEVEL;
+        for (level, lits) in self.bt.assigns.all_levels_dir() {
+            if level.offset_from_ground() > assumptions {
+                match next {
+                    Some(act) if self.ctx.heur.activity(lits[0].var()) > act => {}
+                    _ => { break; }
+                }
+            }
+            reuse = level;
+        }
+        reuse
+    }
+
+    // Level right above the first 'n' assumptions (ground level for none). They must be assigned.
+    fn assumption_level(&self, n: usize) -> DecisionLevel {
+        self.bt.assigns.all_levels_dir()
+            .map(|(level, _)| level)
+            .take_while(|level| level.offset_from_ground() <= n)
+            .last()
+            .unwrap_or(GROUND_LEVEL)
+    }
+
+    // Backtrack to the levels of assumptions made before the search stopped, which are not
+    // propagated again by the next 'search_step'.
+    fn keep_assumptions(&mut self, assumptions: &[Lit]) {
+        let n = assumptions.len().min(self.bt.assigns.current_level().offset_from_ground());
+        let level = self.assumption_level(n);
+        self.cancel_until(level);
+        self.kept = assumptions[..n].to_vec();
+    }
+
+    // Everything but 'search_step' and the search driven from outside ('decide', 'restore' and
+    // 'analyze_conflict') starts from here:
+    pub fn ground(&mut self) -> Ground<'_> {
+        self.release_assumptions();
+        Ground(self)
+    }
+
+    fn release_assumptions(&mut self) {
+        if !self.bt.assigns.is_ground_level() {
+            self.cancel_until(GROUND_LEVEL);
+        }
+        self.kept.clear();
+    }
+
     // Revert to the state at given level (keeping all assignment at 'level' but not beyond).
     fn cancel_until(&mut self, target_level: DecisionLevel) {
+        self.save_best();
         self.ctx.cancel_until(&self.bt.assigns, target_level);
         self.bt.assigns.backtrack_to(target_level);
     }
 
 
     fn try_garbage_collect(&mut self) {
-        if self.bt.ca.check_garbage(self.settings.garbage_frac) {
-            self.gc();
+        if !self.bt.ca.check_garbage(self.gc_frac) {
+            return;
+        }
+
+        let before = self.bt.ca.allocated_bytes();
+        self.gc();
+        #[cfg(debug_assertions)]
+        self.check_watches();
+
+        if self.settings.adaptive_gc && before > 0 {
+            // Waste comes in bursts (e.g. from 'reduce'), so a run may reclaim much more than the
+            // trigger. Collect earlier then, and later if runs reclaim too little to pay off:
+            let reclaimed = 1.0 - self.bt.ca.allocated_bytes() as f64 / before as f64;
+            let factor = if reclaimed < GC_TARGET_RECLAIM { 1.25 } else { 0.8 };
+            self.gc_frac = (self.gc_frac * factor).clamp(GC_MIN_FRAC, GC_MAX_FRAC);
+        }
+    }
+
+    pub fn garbage_frac(&self) -> f64 {
+        self.gc_frac
+    }
+
+    // Takes effect at the next check for garbage, and after 'reset' as well.
+    pub fn set_garbage_frac(&mut self, frac: f64) {
+        self.settings.garbage_frac = frac;
+        self.gc_frac = frac;
+    }
+
+    pub fn allocator_stats(&self) -> (usize, usize) {
+        self.bt.ca.usage()
+    }
+
+    // Panic if a problem clause is falsified or an assigned literal doesn't follow from its reason:
+    #[cfg(debug_assertions)]
+    fn check_assignment(&self) {
+        let ca = &self.bt.ca;
+        let clauses = self.ctx.db.clauses().iter().cloned().filter(|&cr| !ca.is_deleted(cr));
+        assert!(self.bt.assigns.is_consistent(ca, clauses), "Inconsistent assignment");
+    }
+
+    // Panic if watches are broken (see 'Watches::check_invariants'), when 'check_watches' is on:
+    #[cfg(debug_assertions)]
+    pub fn check_watches(&self) {
+        if !self.settings.check_watches {
+            return;
+        }
+
+        let db = &self.ctx.db;
+        let clauses = db.clauses().iter().chain(db.learnts().iter()).cloned().filter(|&cr| !self.bt.ca.is_deleted(cr));
+        if let Err(msg) = self.bt.watches.check_invariants(&self.bt.ca, &self.bt.assigns, clauses) {
+            panic!("Broken watches: {}", msg);
         }
     }
 
     fn gc(&mut self) -> ClauseGC {
+        self.ctx.trace(|| TraceEvent::GarbageCollection);
+        if self.settings.packed_gc {
+            // Database order goes first, watches and reasons only pick up the new references:
+            let mut gc = self.bt.ca.gc();
+            self.ctx.db.gc_packed(&mut gc);
+            self.bt.watches.gc(&mut gc);
+            self.bt.assigns.gc(&mut gc);
+            return gc;
+        }
+
         let mut gc = self.bt.gc();
         self.ctx.db.gc(&mut gc);
         gc
@@ -597,6 +1347,192 @@ impl Searcher {
             propagations: self.bt.propagations(),
             tot_literals: self.ctx.analyze.tot_literals,
             del_literals: self.ctx.analyze.max_literals - self.ctx.analyze.tot_literals,
+            gc_runs: self.bt.ca.gc_runs(),
+            gc_reclaimed_bytes: self.bt.ca.gc_reclaimed() as u64,
+            max_trail_size: self.ctx.stats.max_trail as u64,
+            max_learnts: self.ctx.stats.max_learnts as u64,
+            restart_unassigns: self.ctx.stats.restart_unassigns,
+            max_locked: self.ctx.stats.max_locked as u64,
+            unlock_restarts: self.ctx.stats.unlock_restarts,
+            progress: self.ctx.stats.progress,
+            learnts: self.ctx.stats.learnts,
+            learnt_literals: self.ctx.stats.learnt_literals,
+            learnt_lbd: self.ctx.stats.learnt_lbd,
+            max_learnt_len: self.ctx.stats.max_learnt_len as u64,
+            max_clause_len: self.max_clause_len() as u64,
+            propagations_since_last_conflict: self.bt.propagations() - self.ctx.stats.last_conflict_props,
+        }
+    }
+}
+
+
+// The searcher at ground level, with assumptions kept by 'search_step' and decisions made by
+// 'decide' released. Whatever changes clauses or works at ground level goes through it (see
+// 'Searcher::ground'), so that nothing runs under stale assumptions.
+pub struct Ground<'a>(&'a mut Searcher);
+
+impl<'a> Ground<'a> {
+    pub fn add_clause(&mut self, clause: &[Lit]) -> AddClauseRes {
+        // TODO: it should be here to work identical to original MiniSat. Probably not the best place.
+        if self.0.settings.use_rcheck && is_implied(&mut self.0.bt, &mut self.0.ctx.heur, &clause) {
+            return AddClauseRes::Consumed;
+        }
+
+        let mut ps =
+            match self.0.prepare_clause(clause) {
+                None => { return AddClauseRes::Consumed; }
+                Some(ps) => ps,
+            };
+
+        // The first two literals get watched:
+        if let Some(ref mut rand) = self.0.shuffle {
+            for i in (1..ps.len()).rev() {
+                ps.swap(i, rand.irand(i + 1));
+            }
+        }
+
+        match &ps[..] {
+            [] => { AddClauseRes::UnSAT }
+            [unit] => { self.0.add_unit(*unit) }
+            lits => {
+                let cr = self.0.ctx.db.add_clause(&mut self.0.bt.ca, lits);
+                self.0.bt.attach(cr);
+                AddClauseRes::Added(cr)
+            }
+        }
+    }
+
+    // Add a clause implied by the problem clauses as a learnt one, so that 'reduce' may drop it
+    // later. Its LBD is unknown, so the length is taken as a bound.
+    #[cfg(feature = "std")]
+    pub fn add_learnt(&mut self, clause: &[Lit]) -> AddClauseRes {
+        let ps =
+            match self.0.prepare_clause(clause) {
+                None => { return AddClauseRes::Consumed; }
+                Some(ps) => ps,
+            };
+
+        match &ps[..] {
+            [] => { AddClauseRes::UnSAT }
+            [unit] => { self.0.add_unit(*unit) }
+            lits => {
+                let cr = self.0.ctx.db.learn_clause(&mut self.0.bt.ca, lits, lits.len() as u32);
+                self.0.bt.attach(cr);
+                AddClauseRes::Added(cr)
+            }
         }
     }
+
+    // Assign 'units' on a single level above the ground one and propagate them, without any search.
+    // Returns literals they imply, or a subset of them that propagation alone refutes (empty if
+    // the formula itself is refuted). Back at ground level in either case.
+    pub fn check_units(&mut self, units: &[Lit]) -> Result<Vec<Lit>, Vec<Lit>> {
+        if self.0.bt.propagate().is_some() {
+            return Err(Vec::new());
+        }
+
+        self.0.bt.assigns.new_decision_level();
+        let mut conflict = None;
+        for &unit in units.iter() {
+            match self.0.bt.assigns.of_lit(unit) {
+                LBool::True => {}
+                LBool::False => {
+                    conflict = Some(self.0.ctx.analyze.analyze_final(&self.0.bt.ca, &self.0.bt.assigns, !unit));
+                    break;
+                }
+                LBool::Undef => {
+                    self.0.bt.assigns.assign_lit(unit, None);
+                    if let Some(confl) = self.0.bt.propagate() {
+                        conflict = Some(self.0.ctx.analyze.analyze_final_clause(&self.0.bt.ca, &self.0.bt.assigns, confl));
+                        break;
+                    }
+                }
+            }
+        }
+
+        let res =
+            match conflict {
+                Some(confl) => Err(confl.iter().map(|(lit, _)| !lit).collect()),
+                None => {
+                    let assigns = &self.0.bt.assigns;
+                    Ok(assigns.trail_above(GROUND_LEVEL).iter()
+                        .filter(|&&lit| assigns.vardata(!lit).reason.is_some())
+                        .cloned()
+                        .collect())
+                }
+            };
+        self.0.cancel_until(GROUND_LEVEL);
+        res
+    }
+
+    // Reverse unit propagation: assigning all literals of 'clause' to false leads to a conflict.
+    #[cfg(feature = "std")]
+    pub fn is_rup(&mut self, clause: &[Lit]) -> bool {
+        is_implied(&mut self.0.bt, &mut self.0.ctx.heur, clause)
+    }
+
+    // Remove the problem clause consisting of 'clause' literals (except ones that are false at
+    // ground level). Returns false if there is no such clause. A reason for a ground assignment is
+    // kept, and tautologies and clauses satisfied at ground level are never stored, so deleting
+    // them succeeds without removing anything.
+    #[cfg(feature = "std")]
+    pub fn remove_clause_lits(&mut self, clause: &[Lit]) -> bool {
+        let mut ps = clause.to_vec();
+        ps.sort();
+        ps.dedup();
+        if ps.windows(2).any(|w| w[0] == !w[1]) || ps.iter().any(|&lit| self.0.bt.assigns.is_assigned_pos(lit)) {
+            return true;
+        }
+        ps.retain(|&lit| !self.0.bt.assigns.is_assigned_neg(lit));
+
+        let found = self.0.ctx.db.clauses().iter().cloned().find(|&cr| {
+            if self.0.bt.ca.is_deleted(cr) || self.0.bt.ca.view(cr).len() < ps.len() {
+                return false;
+            }
+            let mut lits = self.0.bt.ca.literals(cr).to_vec();
+            lits.retain(|&lit| !self.0.bt.assigns.is_assigned_neg(lit));
+            lits.sort();
+            lits == ps
+        });
+
+        match found {
+            Some(cr) => {
+                if !self.0.bt.assigns.is_reason_for(cr, self.0.bt.ca.literals(cr)[0]) {
+                    self.0.bt.lazy_detach(cr);
+                    self.0.ctx.db.remove_clause(&mut self.0.bt.ca, cr);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn preprocess(&mut self) -> bool {
+        if let None = self.0.bt.propagate() {
+            self.0.try_simplify();
+            !self.0.settings.detect_tractable || self.0.tractable_model().map_or(true, |model| model.is_some())
+        } else {
+            false
+        }
+    }
+
+    // Unconditionally simplify the clause database at ground level (unlike 'try_simplify' that
+    // is rate-limited by the number of propagations since the last call).
+    pub fn simplify(&mut self) -> bool {
+        if self.0.bt.propagate().is_none() {
+            self.0.simplify_db();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Compact clause storage regardless of the amount of garbage. Returns allocated bytes before and after.
+    pub fn collect_garbage(&mut self) -> (usize, usize) {
+        let before = self.0.bt.ca.allocated_bytes();
+        self.0.gc();
+        #[cfg(debug_assertions)]
+        self.0.check_watches();
+        (before, self.0.bt.ca.allocated_bytes())
+    }
 }
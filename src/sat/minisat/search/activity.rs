@@ -0,0 +1,228 @@
+use alloc::vec::Vec;
+use crate::sat::formula::{Var, VarMap};
+use super::random;
+
+
+// Activity of 1.0 (the initial increment) in fixed-point mode:
+const FIXED_ONE: u64 = 1 << 32;
+// Fixed-point factors (decay, bump scale) are stored with this many fractional bits:
+pub const FRACTION_BITS: u32 = 16;
+// Activities and the increment are shifted down by 'RESCALE_BITS' once they exceed these:
+const ACTIVITY_LIMIT: u64 = 1 << 62;
+const INC_LIMIT: u64 = 1 << 44;
+const RESCALE_BITS: u32 = 20;
+
+
+// Variable activities together with the increment for bumps, as floats (as in MiniSat) or as
+// fixed-point integers. The latter give the same results whatever the rounding of floating-point
+// operations on the platform. 'boost' is the part of activity set by the user (see 'set').
+#[derive(Clone)]
+pub enum Activity {
+    Float {
+        inc: f64,
+        act: VarMap<f64>,
+        boost: VarMap<f64>,
+    },
+    Fixed {
+        inc: u64,
+        act: VarMap<u64>,
+        boost: VarMap<i64>,
+    },
+}
+
+impl Activity {
+    pub fn new(fixed: bool) -> Activity {
+        if fixed {
+            Activity::Fixed { inc: FIXED_ONE, act: VarMap::new(), boost: VarMap::new() }
+        } else {
+            Activity::Float { inc: 1.0, act: VarMap::new(), boost: VarMap::new() }
+        }
+    }
+
+    // Forget all activities, 'init' has to be called for every variable again:
+    pub fn clear(&mut self) {
+        *self = Activity::new(self.is_fixed());
+    }
+
+    fn is_fixed(&self) -> bool {
+        match *self {
+            Activity::Float { .. } => false,
+            Activity::Fixed { .. } => true,
+        }
+    }
+
+    pub fn reserve(&mut self, n: usize) {
+        match *self {
+            Activity::Float { ref mut act, .. } => act.reserve(n),
+            Activity::Fixed { ref mut act, .. } => act.reserve(n),
+        }
+    }
+
+    // Initial activity of 'v', random and tiny compared to a single bump if 'rand' is given:
+    pub fn init(&mut self, v: &Var, rand: Option<&mut random::Random>) {
+        match *self {
+            Activity::Float { ref mut act, .. } => {
+                act.insert(v, rand.map_or(0.0, |r| r.drand() * 0.00001));
+            }
+            Activity::Fixed { ref mut act, .. } => {
+                act.insert(v, rand.map_or(0, |r| r.irand((FIXED_ONE / 100000) as usize) as u64));
+            }
+        }
+    }
+
+    // The order of the decision heuristic: 'a' is picked before 'b'.
+    #[inline]
+    pub fn before(&self, a: &Var, b: &Var) -> bool {
+        match *self {
+            Activity::Float { ref act, .. } => act[a] > act[b],
+            Activity::Fixed { ref act, .. } => act[a] > act[b],
+        }
+    }
+
+    // Activities outside are in units of the initial increment in both modes, so that a value set
+    // means the same number of bumps whether activities are fixed-point or not:
+    pub fn get(&self, v: &Var) -> f64 {
+        match *self {
+            Activity::Float { ref act, .. } => act[v],
+            Activity::Fixed { ref act, .. } => from_fixed(act[v]),
+        }
+    }
+
+    // Overwrite activity of 'v', tracking the change as a boost if 'track' (see 'get' for units):
+    pub fn set(&mut self, v: &Var, value: f64, track: bool) {
+        match *self {
+            Activity::Float { ref mut act, ref mut boost, .. } => {
+                if track {
+                    let b = boost.get(v).cloned().unwrap_or(0.0) + value - act[v];
+                    boost.insert(v, b);
+                }
+                act[v] = value;
+            }
+            Activity::Fixed { ref mut act, ref mut boost, .. } => {
+                let value = ((value * FIXED_ONE as f64) as u64).min(ACTIVITY_LIMIT);
+                if track {
+                    let b = boost.get(v).cloned().unwrap_or(0) + (value as i64 - act[v] as i64);
+                    boost.insert(v, b);
+                }
+                act[v] = value;
+            }
+        }
+    }
+
+    pub fn into_float(self) -> VarMap<f64> {
+        match self {
+            Activity::Float { act, .. } => act,
+            Activity::Fixed { act, .. } => {
+                let mut res = VarMap::new();
+                for (v, &a) in act.iter() {
+                    res.insert(&v, from_fixed(a));
+                }
+                res
+            }
+        }
+    }
+
+    // Add 'scale' times the increment to activity of 'v', rescaling everything if it grows too large:
+    pub fn bump(&mut self, v: &Var, scale: f64) {
+        match *self {
+            Activity::Float { ref mut inc, ref mut act, ref mut boost } => {
+                let new = act[v] + *inc * scale;
+                // NaN or infinite activities would silently break the order of the heap:
+                assert!(new.is_finite(), "Activity of variable {:?} is not finite ({})", v, new);
+                if new > 1e100 {
+                    *inc *= 1e-100;
+                    for (_, a) in act.iter_mut() {
+                        *a *= 1e-100;
+                    }
+                    for (_, b) in boost.iter_mut() {
+                        *b *= 1e-100;
+                    }
+                    act[v] = new * 1e-100;
+                } else {
+                    act[v] = new;
+                }
+            }
+            Activity::Fixed { inc, ref mut act, .. } => {
+                let step = if scale == 1.0 { inc } else { fixed_mul(inc, to_fixed(scale)) };
+                act[v] = act[v].saturating_add(step);
+                if act[v] > ACTIVITY_LIMIT {
+                    self.rescale();
+                }
+            }
+        }
+    }
+
+    // Grow the increment by '1 / var_decay', so that earlier bumps weigh less:
+    pub fn decay(&mut self, var_decay: f64) {
+        match *self {
+            Activity::Float { ref mut inc, .. } => {
+                *inc *= 1.0 / var_decay;
+                assert!(inc.is_finite(),
+                        "Variable activity increment is not finite (var_decay = {}, expected to be in (0, 1])", var_decay);
+            }
+            Activity::Fixed { ref mut inc, .. } => {
+                let decay = to_fixed(var_decay);
+                assert!(decay > 0,
+                        "Variable activity increment is not finite (var_decay = {}, expected to be in (0, 1])", var_decay);
+                *inc = (((*inc as u128) << FRACTION_BITS) / decay as u128).min(u64::MAX as u128) as u64;
+                if *inc > INC_LIMIT {
+                    self.rescale();
+                }
+            }
+        }
+    }
+
+    // Keep a 'factor' share of every boost, taking the rest away from activity:
+    pub fn decay_boosts(&mut self, factor: f64) {
+        match *self {
+            Activity::Float { ref mut act, ref mut boost, .. } => {
+                for (v, b) in boost.iter_mut() {
+                    let decayed = *b * factor;
+                    act[&v] -= *b - decayed;
+                    *b = decayed;
+                }
+            }
+            Activity::Fixed { ref mut act, ref mut boost, .. } => {
+                let factor = to_fixed(factor) as i128;
+                for (v, b) in boost.iter_mut() {
+                    let decayed = ((*b as i128 * factor) >> FRACTION_BITS) as i64;
+                    act[&v] = (act[&v] as i64 - (*b - decayed)).max(0) as u64;
+                    *b = decayed;
+                }
+            }
+        }
+    }
+
+    pub fn boosted(&self) -> Vec<Var> {
+        match *self {
+            Activity::Float { ref boost, .. } => boost.iter().map(|(v, _)| v).collect(),
+            Activity::Fixed { ref boost, .. } => boost.iter().map(|(v, _)| v).collect(),
+        }
+    }
+
+    fn rescale(&mut self) {
+        if let Activity::Fixed { ref mut inc, ref mut act, ref mut boost } = *self {
+            *inc = (*inc >> RESCALE_BITS).max(1);
+            for (_, a) in act.iter_mut() {
+                *a >>= RESCALE_BITS;
+            }
+            for (_, b) in boost.iter_mut() {
+                *b >>= RESCALE_BITS;
+            }
+        }
+    }
+}
+
+
+pub fn to_fixed(x: f64) -> u64 {
+    (x * (1u64 << FRACTION_BITS) as f64 + 0.5) as u64 // Rounded, as there is no 'f64::round' without std.
+}
+
+// Fixed-point activity as a multiple of the initial increment:
+fn from_fixed(x: u64) -> f64 {
+    x as f64 / FIXED_ONE as f64
+}
+
+fn fixed_mul(x: u64, factor: u64) -> u64 {
+    ((x as u128 * factor as u128) >> FRACTION_BITS).min(u64::MAX as u128) as u64
+}
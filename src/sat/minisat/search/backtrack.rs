@@ -2,6 +2,7 @@ use crate::sat::formula::{assignment::*, clause::*, Lit, Var};
 use super::watches::Watches;
 
 
+#[derive(Clone)]
 pub struct BacktrackableFormula {
     pub ca: ClauseAllocator,
     pub assigns: Assignment, // The current assignments.
@@ -17,6 +18,11 @@ impl BacktrackableFormula {
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.assigns.reserve_vars(n);
+        self.watches.reserve_vars(n);
+    }
+
     pub fn new_var(&mut self) -> Var {
         let v = self.assigns.new_var();
         self.watches.init_var(v);
@@ -1,10 +1,23 @@
-use std::cmp::Ordering;
-use crate::sat::formula::{assignment::Assignment, clause::*, util::*, Lit};
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use alloc::{boxed::Box, vec, vec::Vec};
+use crate::sat::formula::{assignment::Assignment, clause::*, util::*, Lit, LitMap};
+use super::activity::{to_fixed, FRACTION_BITS};
 
 
+// Fixed-point clause activities and the increment are shifted down by 'FIXED_RESCALE_BITS' once
+// they exceed these:
+const FIXED_ACTIVITY_LIMIT: u32 = 1 << 30;
+const FIXED_INC_LIMIT: u32 = 1 << 20;
+const FIXED_RESCALE_BITS: u32 = 10;
+
+
+#[derive(Clone)]
 pub struct ClauseDBSettings {
     pub remove_satisfied: bool, // Indicates whether possibly inefficient linear scan for satisfied clauses should be performed in 'simplify'.
     pub clause_decay: f64,
+    pub core_lbd_cutoff: u32, // Learnt clauses with LBD not above this are never removed ('2', the default, keeps glue clauses too; '0' keeps only binary ones, as in MiniSat).
+    pub protect_first_learnts: usize, // The first this many learnt clauses are never removed by 'reduce' either.
 }
 
 impl Default for ClauseDBSettings {
@@ -12,6 +25,8 @@ impl Default for ClauseDBSettings {
         ClauseDBSettings {
             remove_satisfied: true,
             clause_decay: 0.999,
+            core_lbd_cutoff: 2,
+            protect_first_learnts: 0,
         }
     }
 }
@@ -23,12 +38,13 @@ pub struct Stats {
     pub num_learnts: usize,
     pub clauses_literals: u64,
     pub learnts_literals: u64,
+    pub max_clause_len: usize, // Longest problem clause added, even if it is gone since.
 }
 
 impl Stats {
     fn add(&mut self, clause: &Clause) {
         match clause.header {
-            ClauseHeader::Learnt { activity: _ } => {
+            ClauseHeader::Learnt { .. } => {
                 self.num_learnts += 1;
                 self.learnts_literals += clause.len() as u64;
             }
@@ -36,13 +52,14 @@ impl Stats {
             ClauseHeader::Clause { abstraction: _ } => {
                 self.num_clauses += 1;
                 self.clauses_literals += clause.len() as u64;
+                self.max_clause_len = self.max_clause_len.max(clause.len());
             }
         }
     }
 
     fn del(&mut self, clause: &Clause) {
         match clause.header {
-            ClauseHeader::Learnt { activity: _ } => {
+            ClauseHeader::Learnt { .. } => {
                 self.num_learnts -= 1;
                 self.learnts_literals -= clause.len() as u64;
             }
@@ -56,22 +73,58 @@ impl Stats {
 }
 
 
+// Called with literals of every clause removed from the database for good, original or learnt.
+// Clauses only moved by garbage collection are not reported.
+pub type DeletionHook = Box<dyn FnMut(&[Lit]) + Send>;
+
+// Amount to bump the next clause with, as a float (as in MiniSat) or as a fixed-point integer in
+// deterministic mode (see 'FIXED_ACTIVITY_ONE').
+#[derive(Clone, Copy)]
+enum ClauseInc {
+    Float(f64),
+    Fixed(u32),
+}
+
 pub struct ClauseDB {
     pub settings: ClauseDBSettings,
-    cla_inc: f64,            // Amount to bump next clause with.
+    cla_inc: ClauseInc,
     clauses: Vec<ClauseRef>, // List of problem clauses.
     learnts: Vec<ClauseRef>, // List of learnt clauses.
+    learnt_count: usize,     // Number of clauses learnt so far, including removed ones.
+    occurs: RefCell<Option<LitMap<Vec<ClauseRef>>>>, // Occurrence lists of problem clauses, built on first query.
     pub stats: Stats,
+    pub on_clause_deleted: Option<DeletionHook>,
+}
+
+// The hook belongs to the user of the original database, so copies start without it.
+impl Clone for ClauseDB {
+    fn clone(&self) -> Self {
+        ClauseDB {
+            settings: self.settings.clone(),
+            cla_inc: self.cla_inc,
+            clauses: self.clauses.clone(),
+            learnts: self.learnts.clone(),
+            learnt_count: self.learnt_count,
+            occurs: self.occurs.clone(),
+            stats: self.stats,
+            on_clause_deleted: None,
+        }
+    }
 }
 
 impl ClauseDB {
-    pub fn new(settings: ClauseDBSettings) -> ClauseDB {
+    // Clause activities are fixed-point numbers if 'fixed', so that they don't depend on rounding
+    // of floating-point operations.
+    pub fn new(settings: ClauseDBSettings, fixed: bool) -> ClauseDB {
         ClauseDB {
             settings,
-            cla_inc: 1.0,
+            cla_inc: if fixed { ClauseInc::Fixed(FIXED_ACTIVITY_ONE) } else { ClauseInc::Float(1.0) },
             clauses: Vec::new(),
             learnts: Vec::new(),
+            learnt_count: 0,
+            occurs: RefCell::new(None),
             stats: Stats::default(),
+            on_clause_deleted: None,
         }
     }
 
@@ -87,11 +140,14 @@ impl ClauseDB {
         let (c, cr) = ca.alloc(literals, header);
         self.stats.add(c);
         self.clauses.push(cr);
+        *self.occurs.get_mut() = None;
         cr
     }
 
-    pub fn learn_clause<'c>(&mut self, ca: &mut ClauseAllocator, literals: &[Lit]) -> ClauseRef {
-        let header = ClauseHeader::Learnt { activity: 0.0 };
+    pub fn learn_clause<'c>(&mut self, ca: &mut ClauseAllocator, literals: &[Lit], lbd: u32) -> ClauseRef {
+        let protected = self.learnt_count < self.settings.protect_first_learnts;
+        self.learnt_count += 1;
+        let header = ClauseHeader::learnt(lbd, protected);
         let (c, cr) = ca.alloc(literals, header);
         self.stats.add(c);
         self.learnts.push(cr);
@@ -99,7 +155,50 @@ impl ClauseDB {
         cr
     }
 
+    pub fn is_fixed(&self) -> bool {
+        matches!(self.cla_inc, ClauseInc::Fixed(_))
+    }
+
+    pub fn clauses(&self) -> &[ClauseRef] {
+        &self.clauses
+    }
+
+    pub fn learnts(&self) -> &[ClauseRef] {
+        &self.learnts
+    }
+
+    // Problem clauses containing 'lit'. Clauses deleted or shrunk since the occurrence lists were
+    // built are filtered out here, so only additions and relocations have to drop them.
+    pub fn clauses_with<'c>(&self, ca: &'c ClauseAllocator, lit: Lit) -> Vec<&'c [Lit]> {
+        let mut occurs = self.occurs.borrow_mut();
+        let occurs = occurs.get_or_insert_with(|| {
+            let mut occurs: LitMap<Vec<ClauseRef>> = LitMap::new();
+            for &cr in self.clauses.iter() {
+                if ca.is_deleted(cr) {
+                    continue;
+                }
+                for &l in ca.view(cr).lits() {
+                    if occurs.get(&l).is_none() {
+                        occurs.insert(&l, Vec::new());
+                    }
+                    occurs[&l].push(cr);
+                }
+            }
+            occurs
+        });
+
+        match occurs.get(&lit) {
+            Some(crs) => crs.iter()
+                .filter(|&&cr| !ca.is_deleted(cr))
+                .map(|&cr| ca.view(cr).lits())
+                .filter(|lits| lits.contains(&lit))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
     pub fn remove_clause(&mut self, ca: &mut ClauseAllocator, cr: ClauseRef) {
+        report_deleted(&mut self.on_clause_deleted, ca.view(cr));
         self.stats.del(ca.view(cr));
         ca.free(cr);
     }
@@ -117,24 +216,40 @@ impl ClauseDB {
     }
 
     pub fn bump_activity(&mut self, ca: &mut ClauseAllocator, cr: ClauseRef) {
-        let new = {
+        let rescale = {
             let c = ca.edit(cr);
-            if let ClauseHeader::Learnt { ref mut activity } = c.header {
-                let new = *activity as f64 + self.cla_inc;
-                *activity = new as f32;
-                new
+            if let ClauseHeader::Learnt { ref mut activity, .. } = c.header {
+                match self.cla_inc {
+                    ClauseInc::Float(inc) => {
+                        let new = f32::from_bits(*activity) as f64 + inc;
+                        *activity = (new as f32).to_bits();
+                        new > 1e20
+                    }
+                    ClauseInc::Fixed(inc) => {
+                        *activity = activity.saturating_add(inc);
+                        *activity > FIXED_ACTIVITY_LIMIT || inc > FIXED_INC_LIMIT
+                    }
+                }
             } else {
                 return;
             }
         };
 
-        if new > 1e20 {
-            self.cla_inc *= 1e-20;
+        if rescale {
+            self.cla_inc = match self.cla_inc {
+                ClauseInc::Float(inc) => ClauseInc::Float(inc * 1e-20),
+                ClauseInc::Fixed(inc) => ClauseInc::Fixed((inc >> FIXED_RESCALE_BITS).max(1)),
+            };
+            let fixed = self.is_fixed();
             for &cri in self.learnts.iter() {
                 let c = ca.edit(cri);
-                if let ClauseHeader::Learnt { ref mut activity } = c.header {
-                    let scaled = (*activity as f64) * 1e-20;
-                    *activity = scaled as f32;
+                if let ClauseHeader::Learnt { ref mut activity, .. } = c.header {
+                    if fixed {
+                        *activity >>= FIXED_RESCALE_BITS;
+                    } else {
+                        let scaled = (f32::from_bits(*activity) as f64) * 1e-20;
+                        *activity = (scaled as f32).to_bits();
+                    }
                 } else {
                     panic!("Expected learnt");
                 }
@@ -142,46 +257,79 @@ impl ClauseDB {
         }
     }
 
+    // Grow the increment by '1 / clause_decay'. A fixed-point increment that grows too large is
+    // rescaled along with activities at the next bump.
     pub fn decay_activity(&mut self) {
-        self.cla_inc *= 1.0 / self.settings.clause_decay;
+        match self.cla_inc {
+            ClauseInc::Float(ref mut inc) => {
+                *inc *= 1.0 / self.settings.clause_decay;
+                assert!(inc.is_finite(),
+                        "Clause activity increment is not finite (clause_decay = {}, expected to be in (0, 1])", self.settings.clause_decay);
+            }
+            ClauseInc::Fixed(ref mut inc) => {
+                let decay = to_fixed(self.settings.clause_decay);
+                assert!(decay > 0,
+                        "Clause activity increment is not finite (clause_decay = {}, expected to be in (0, 1])", self.settings.clause_decay);
+                *inc = (((*inc as u64) << FRACTION_BITS) / decay).min(u32::MAX as u64) as u32;
+            }
+        }
     }
 
     pub fn number_of_learnts(&self) -> usize {
         self.learnts.len()
     }
 
+    // Learnt clauses that are reasons for the current assignment, so 'reduce' has to keep them.
+    pub fn number_of_locked(&self, ca: &ClauseAllocator, assigns: &Assignment) -> usize {
+        self.learnts.iter()
+            .filter(|&&cr| !ca.is_deleted(cr) && assigns.is_reason_for(cr, ca.view(cr).prefix[0]))
+            .count()
+    }
+
     // Description:
     //   Remove half of the learnt clauses, minus the clauses locked by the current assignment. Locked
-    //   clauses are clauses that are reason to some assignment. Binary clauses are never removed.
+    //   clauses are clauses that are reason to some assignment. Binary and glue clauses (LBD not
+    //   above 'core_lbd_cutoff') are never removed, and neither are protected ones (the first
+    //   'protect_first_learnts' clauses learnt).
     pub fn reduce<F: FnMut(&Clause) -> ()>(
         &mut self,
         ca: &mut ClauseAllocator,
         assigns: &Assignment,
         mut notify: F,
     ) {
+        let cutoff = self.settings.core_lbd_cutoff;
+        let fixed = self.is_fixed();
+        let is_core = move |c: &Clause| c.len() == 2 || c.header.lbd() <= cutoff || c.header.is_protected();
+
         self.learnts.sort_by(|&rx, &ry| {
             let x = ca.view(rx);
             let y = ca.view(ry);
 
-            if x.len() == 2 && y.len() == 2 {
+            if is_core(x) && is_core(y) {
                 Ordering::Equal
-            } else if x.len() == 2 {
+            } else if is_core(x) {
                 Ordering::Greater
-            } else if y.len() == 2 {
+            } else if is_core(y) {
                 Ordering::Less
             } else {
-                let x_activity = x.header.activity();
-                let y_activity = y.header.activity();
+                let x_activity = x.header.activity(fixed);
+                let y_activity = y.header.activity(fixed);
                 x_activity.partial_cmp(&y_activity).unwrap()
             }
         });
 
-        // Don't delete binary or locked clauses. From the rest, delete clauses from the first half
+        // Don't delete binary, glue or locked clauses. From the rest, delete clauses from the first half
         // and clauses with activity smaller than 'extra_lim':
         {
             let index_lim = self.learnts.len() / 2;
-            let extra_lim = self.cla_inc / self.learnts.len() as f64; // Remove any clause below this activity
+            // Remove any clause below this activity:
+            let extra_lim =
+                match self.cla_inc {
+                    ClauseInc::Float(inc) => inc / self.learnts.len() as f64,
+                    ClauseInc::Fixed(inc) => (inc as usize / self.learnts.len().max(1)) as f64 / FIXED_ACTIVITY_ONE as f64,
+                };
             let stats = &mut self.stats;
+            let hook = &mut self.on_clause_deleted;
 
             let mut i = 0;
             self.learnts.retain(move |&cr| {
@@ -192,11 +340,12 @@ impl ClauseDB {
 
                 let remove = {
                     let c = ca.view(cr);
-                    let remove = c.len() > 2 && !assigns.is_reason_for(cr, c.prefix[0])
-                        && (i < index_lim || (c.header.activity() as f64) < extra_lim);
+                    let remove = !is_core(c) && !assigns.is_reason_for(cr, c.prefix[0])
+                        && (i < index_lim || c.header.activity(fixed) < extra_lim);
 
                     if remove {
                         notify(c);
+                        report_deleted(hook, c);
                         stats.del(c);
                     }
 
@@ -216,6 +365,7 @@ impl ClauseDB {
 
     fn retain_clause<F: FnMut(&Clause) -> ()>(
         stats: &mut Stats,
+        hook: &mut Option<DeletionHook>,
         ca: &mut ClauseAllocator,
         assigns: &Assignment,
         notify: &mut F,
@@ -225,6 +375,7 @@ impl ClauseDB {
             false
         } else if satisfied_with_assignment(ca.view(cr).lits(), assigns) {
             notify(ca.view(cr));
+            report_deleted(hook, ca.view(cr));
             stats.del(ca.view(cr));
             ca.free(cr);
             false
@@ -240,43 +391,63 @@ impl ClauseDB {
     {
         // Remove satisfied clauses:
         let stats = &mut self.stats;
+        let hook = &mut self.on_clause_deleted;
         self.learnts.retain(|&cr| {
-            Self::retain_clause(stats, ca, assigns, &mut notify, cr)
+            Self::retain_clause(stats, hook, ca, assigns, &mut notify, cr)
         });
 
         // TODO: what todo in if 'remove_satisfied' is false?
         if self.settings.remove_satisfied {
             // Can be turned off.
             self.clauses.retain(|&cr| {
-                Self::retain_clause(stats, ca, assigns, &mut notify, cr)
+                Self::retain_clause(stats, hook, ca, assigns, &mut notify, cr)
             });
         }
     }
 
     pub fn gc(&mut self, gc: &mut ClauseGC) {
-        // All learnt:
+        *self.occurs.get_mut() = None;
+        relocate_all(&mut self.learnts, gc);
+        relocate_all(&mut self.clauses, gc);
+    }
+
+    // Same as 'gc', but relocating problem clauses first and learnt ones from the most active down,
+    // so that clauses visited most often end up close together. Called before anything else is
+    // relocated, the order of lists stays the same.
+    pub fn gc_packed(&mut self, gc: &mut ClauseGC) {
+        *self.occurs.get_mut() = None;
+        relocate_all(&mut self.clauses, gc);
+
+        let mut order: Vec<usize> = (0..self.learnts.len()).collect();
         {
-            let mut j = 0;
-            for i in 0..self.learnts.len() {
-                if let Some(cr) = gc.relocate(self.learnts[i]) {
-                    self.learnts[j] = cr;
-                    j += 1;
-                }
-            }
-            self.learnts.truncate(j);
+            let fixed = self.is_fixed();
+            let activity = |i: usize| gc.view(self.learnts[i]).header.activity(fixed);
+            order.sort_by(|&x, &y| activity(y).partial_cmp(&activity(x)).unwrap());
         }
 
-        // All original:
-        {
-            let mut j = 0;
-            for i in 0..self.clauses.len() {
-                if let Some(cr) = gc.relocate(self.clauses[i]) {
-                    self.clauses[j] = cr;
-                    j += 1;
-                }
-            }
-            self.clauses.truncate(j);
+        let mut reloced = vec![None; self.learnts.len()];
+        for i in order {
+            reloced[i] = gc.relocate(self.learnts[i]);
         }
+        self.learnts.clear();
+        self.learnts.extend(reloced.into_iter().flatten());
+    }
+}
+
+fn relocate_all(crs: &mut Vec<ClauseRef>, gc: &mut ClauseGC) {
+    let mut j = 0;
+    for i in 0..crs.len() {
+        if let Some(cr) = gc.relocate(crs[i]) {
+            crs[j] = cr;
+            j += 1;
+        }
+    }
+    crs.truncate(j);
+}
+
+fn report_deleted(hook: &mut Option<DeletionHook>, clause: &Clause) {
+    if let Some(ref mut hook) = *hook {
+        hook(clause.lits());
     }
 }
 
@@ -301,3 +472,88 @@ fn retain_clause(clause: &mut Clause, assigns: &Assignment) {
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Learn 'n' ternary clauses with LBD 3 and return how many are left after 'rounds' reductions.
+    fn survivors(core_lbd_cutoff: u32, n: usize, rounds: usize) -> usize {
+        let mut db = ClauseDB::new(ClauseDBSettings { core_lbd_cutoff, ..Default::default() }, false);
+        let mut ca = ClauseAllocator::with_capacity(1024);
+        let mut assigns = Assignment::new();
+        for _ in 0..n {
+            let lits: Vec<Lit> = (0..3).map(|_| assigns.new_var().pos_lit()).collect();
+            db.learn_clause(&mut ca, &lits, 3);
+        }
+
+        for _ in 0..rounds {
+            db.reduce(&mut ca, &assigns, |_| {});
+        }
+        db.number_of_learnts()
+    }
+
+    #[test]
+    fn test_protect_first_learnts() {
+        let mut db = ClauseDB::new(ClauseDBSettings { protect_first_learnts: 10, ..Default::default() }, false);
+        let mut ca = ClauseAllocator::with_capacity(1024);
+        let mut assigns = Assignment::new();
+        let mut clauses = Vec::new();
+        for i in 0..40 {
+            let lits: Vec<Lit> = (0..3).map(|_| assigns.new_var().pos_lit()).collect();
+            let cr = db.learn_clause(&mut ca, &lits, 3);
+            // Later clauses are more active, so the earliest ones are the first to go otherwise:
+            for _ in 0..i {
+                db.bump_activity(&mut ca, cr);
+            }
+            clauses.push(lits);
+        }
+
+        for _ in 0..3 {
+            db.reduce(&mut ca, &assigns, |_| {});
+        }
+        let left: Vec<&[Lit]> = db.learnts().iter().map(|&cr| ca.view(cr).lits()).collect();
+        assert!(left.len() < 20);
+        for lits in clauses[..10].iter() {
+            assert!(left.contains(&&lits[..]));
+        }
+        assert!(!clauses[10..20].iter().any(|lits| left.contains(&&lits[..])));
+    }
+
+    #[test]
+    fn test_fixed_activity() {
+        // Both kinds of activities rank clauses the same way through many rescalings, and about as far apart:
+        let activities = |fixed: bool| {
+            let mut db = ClauseDB::new(Default::default(), fixed);
+            let mut ca = ClauseAllocator::with_capacity(1024);
+            let mut assigns = Assignment::new();
+            let crs: Vec<ClauseRef> = (0..4).map(|_| {
+                let lits: Vec<Lit> = (0..3).map(|_| assigns.new_var().pos_lit()).collect();
+                db.learn_clause(&mut ca, &lits, 3)
+            }).collect();
+
+            for i in 0..50000 {
+                db.bump_activity(&mut ca, crs[i % 7 % 4]);
+                db.decay_activity();
+            }
+            assert_eq!(db.is_fixed(), fixed);
+            crs.iter().map(|&cr| ca.view(cr).header.activity(fixed)).collect::<Vec<f64>>()
+        };
+
+        let (float, fixed) = (activities(false), activities(true));
+        let max = |act: &[f64]| act.iter().cloned().fold(0.0, f64::max);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_eq!(float[i] < float[j], fixed[i] < fixed[j], "{:?} {:?}", float, fixed);
+            }
+            assert!((float[i] / max(&float) - fixed[i] / max(&fixed)).abs() < 1e-3, "{:?} {:?}", float, fixed);
+        }
+    }
+
+    #[test]
+    fn test_core_lbd_cutoff() {
+        assert_eq!(survivors(3, 16, 8), 16);
+        assert!(survivors(2, 16, 8) <= 1);
+    }
+}
@@ -1,13 +1,20 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use crate::sat::formula::{assignment::*, clause::*, Lit, LitMap, Var, VarMap};
+use super::watches::Watches;
 
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum CCMinMode {
     None,
     Basic,
     Deep,
+    Adaptive, // Deep until it removes too few literals over a window of conflicts, then basic.
 }
 
+const ADAPTIVE_WINDOW: u64 = 1000; // Conflicts between evaluations of the adaptive mode.
+const ADAPTIVE_MIN_YIELD: f64 = 0.1; // Fraction of literals deep minimization has to remove to be kept.
+
 impl Default for CCMinMode {
     fn default() -> Self {
         CCMinMode::Deep
@@ -25,6 +32,7 @@ enum Seen {
 }
 
 
+#[derive(Debug)]
 pub enum Conflict {
     Ground,
     Unit(DecisionLevel, Lit),
@@ -32,29 +40,71 @@ pub enum Conflict {
 }
 
 
+// Partition of the implication graph by the cut of the last learnt clause.
+#[derive(Clone, Default, Debug)]
+pub struct ConflictSides {
+    pub conflict_side: Vec<Lit>, // Current level literals resolved away before reaching the first UIP.
+    pub learnt: Vec<Lit>,        // Literals of the final (minimized) learnt clause, asserting one first.
+}
+
+
+#[derive(Clone, Copy, Default)]
+struct YieldWindow {
+    conflicts: u64,
+    max_literals: u64, // Literals of learnt clauses in the window before minimization.
+    removed: u64,      // Literals removed by minimization in the window.
+}
+
+
+#[derive(Clone)]
 pub struct AnalyzeContext {
-    ccmin_mode: CCMinMode, // Controls conflict clause minimization
+    ccmin_mode: CCMinMode,       // Controls conflict clause minimization (never 'Adaptive', see 'adaptive').
+    adaptive: Option<YieldWindow>, // Present while 'Deep' mode is on trial in 'Adaptive' mode.
+    minimize_with_binary: bool, // Additionally remove literals implied by the asserting literal through binary clauses.
     seen: VarMap<Seen>,
     analyze_toclear: Vec<Lit>,
+    sides: Option<ConflictSides>, // Recorded only if enabled with 'record_sides'.
     pub max_literals: u64,
     pub tot_literals: u64,
 }
 
 impl AnalyzeContext {
-    pub fn new(ccmin_mode: CCMinMode) -> AnalyzeContext {
+    pub fn new(ccmin_mode: CCMinMode, minimize_with_binary: bool) -> AnalyzeContext {
+        let adaptive = if ccmin_mode == CCMinMode::Adaptive { Some(YieldWindow::default()) } else { None };
         AnalyzeContext {
-            ccmin_mode,
+            ccmin_mode: if adaptive.is_some() { CCMinMode::Deep } else { ccmin_mode },
+            adaptive,
+            minimize_with_binary,
             seen: VarMap::new(),
             analyze_toclear: Vec::new(),
+            sides: None,
             max_literals: 0,
             tot_literals: 0,
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.seen.reserve(n);
+    }
+
     pub fn init_var(&mut self, v: Var) {
         self.seen.insert(&v, Seen::Undef);
     }
 
+    pub fn record_sides(&mut self, enable: bool) {
+        self.sides = if enable { Some(ConflictSides::default()) } else { None };
+    }
+
+    // Minimization mode in effect for the next conflict.
+    pub fn ccmin_mode(&self) -> CCMinMode {
+        self.ccmin_mode
+    }
+
+    // Partition recorded by the last call to 'analyze', if recording is enabled.
+    pub fn last_sides(&self) -> Option<&ConflictSides> {
+        self.sides.as_ref()
+    }
+
     // Description:
     //   Analyze conflict and produce a reason clause.
     //
@@ -71,6 +121,7 @@ impl AnalyzeContext {
         &mut self,
         assigns: &Assignment,
         ca: &mut ClauseAllocator,
+        watches: &Watches,
         confl0: ClauseRef,
         mut bump_var: BV,
         mut bump_cla: BC,
@@ -85,6 +136,9 @@ impl AnalyzeContext {
 
         // Generate conflict clause:
         let mut out_learnt = Vec::with_capacity(assigns.number_of_assigns());
+        if let Some(sides) = self.sides.as_mut() {
+            sides.conflict_side.clear();
+        }
 
         {
             let mut confl = confl0;
@@ -95,8 +149,8 @@ impl AnalyzeContext {
             loop {
                 bump_cla(ca, confl);
 
-                let base = if confl == confl0 { 0 } else { 1 };
-                for &q in &ca.view(confl).lits()[base..] {
+                let c = ca.view(confl);
+                for &q in if confl == confl0 { c.lits() } else { c.tail() } {
                     let v = q.var();
                     if self.seen[&v] == Seen::Undef {
                         let level = assigns.vardata(q).level;
@@ -131,6 +185,9 @@ impl AnalyzeContext {
                     break;
                 }
 
+                if let Some(sides) = self.sides.as_mut() {
+                    sides.conflict_side.push(pl);
+                }
                 confl = assigns.vardata(!pl).reason.unwrap();
             }
         }
@@ -139,8 +196,9 @@ impl AnalyzeContext {
         // Simplify conflict clause:
         self.analyze_toclear = out_learnt.clone();
         self.max_literals += out_learnt.len() as u64;
+        let before = out_learnt.len();
         match self.ccmin_mode {
-            CCMinMode::Deep => {
+            CCMinMode::Deep | CCMinMode::Adaptive => {
                 out_learnt.retain(|&l| !self.lit_redundant(ca, assigns, l));
             }
             CCMinMode::Basic => {
@@ -148,6 +206,10 @@ impl AnalyzeContext {
             }
             CCMinMode::None => {}
         }
+        self.update_adaptive(before, out_learnt.len());
+        if self.minimize_with_binary {
+            self.binary_minimize(ca, assigns, watches, &mut out_learnt);
+        }
         self.tot_literals += out_learnt.len() as u64;
 
         for l in self.analyze_toclear.iter() {
@@ -156,6 +218,9 @@ impl AnalyzeContext {
 
         // Find correct backtrack level:
         if out_learnt.len() == 1 {
+            if let Some(sides) = self.sides.as_mut() {
+                sides.learnt.clone_from(&out_learnt);
+            }
             Conflict::Unit(GROUND_LEVEL, out_learnt[0])
         } else {
             // Find the first literal assigned at the next-highest level:
@@ -171,15 +236,61 @@ impl AnalyzeContext {
 
             // Swap-in this literal at index 1:
             out_learnt.swap(1, max_i);
+            if let Some(sides) = self.sides.as_mut() {
+                sides.learnt.clone_from(&out_learnt);
+            }
             Conflict::Learned(max_level, out_learnt[0], out_learnt)
         }
     }
 
+    // Remove literals 'x' such that there is a binary clause '(out_learnt[0] | ~x)': resolving
+    // with it leaves the rest of the clause intact. The asserting literal stays at index 0.
+    fn binary_minimize(&mut self, ca: &ClauseAllocator, assigns: &Assignment, watches: &Watches, out_learnt: &mut Vec<Lit>) {
+        let mut removed = false;
+        for imp in watches.binary_partners(ca, out_learnt[0]) {
+            if self.seen[&imp.var()] == Seen::Source && assigns.is_assigned_pos(imp) {
+                self.seen[&imp.var()] = Seen::Removable; // (var is already in 'analyze_toclear')
+                removed = true;
+            }
+        }
+
+        if removed {
+            let seen = &self.seen;
+            out_learnt.retain(|l| seen[&l.var()] != Seen::Removable);
+        }
+    }
+
+    // Downgrade to basic minimization once a window of conflicts shows that deep minimization
+    // removes too small a fraction of literals to pay off.
+    fn update_adaptive(&mut self, before: usize, after: usize) {
+        let window =
+            match self.adaptive.as_mut() {
+                Some(window) => window,
+                None => { return; }
+            };
+
+        window.conflicts += 1;
+        window.max_literals += before as u64;
+        window.removed += (before - after) as u64;
+        if window.conflicts < ADAPTIVE_WINDOW {
+            return;
+        }
+
+        let removed = window.removed as f64 / window.max_literals as f64;
+        if removed < ADAPTIVE_MIN_YIELD {
+            debug!(target: "minisat::search", "Deep minimization removed {:.2} % of literals, switching to basic", removed * 100.0);
+            self.ccmin_mode = CCMinMode::Basic;
+            self.adaptive = None;
+        } else {
+            *window = YieldWindow::default();
+        }
+    }
+
     fn lit_redundant_basic(&self, ca: &ClauseAllocator, assigns: &Assignment, literal: Lit) -> bool {
         match assigns.vardata(literal).reason {
             None => false,
             Some(cr) => {
-                for &lit in &ca.view(cr).lits()[1..] {
+                for &lit in ca.view(cr).tail() {
                     if self.seen[&lit.var()] == Seen::Undef
                         && assigns.vardata(lit).level > GROUND_LEVEL
                     {
@@ -201,7 +312,7 @@ impl AnalyzeContext {
         let mut analyze_stack =
             match assigns.vardata(literal).reason {
                 None => return false,
-                Some(cr) => vec![(literal, &ca.view(cr).lits()[1..])],
+                Some(cr) => vec![(literal, ca.view(cr).tail())],
             };
 
         while let Some((p, lits)) = analyze_stack.pop() {
@@ -219,7 +330,7 @@ impl AnalyzeContext {
                     match vd.reason {
                         // Recursively check 'l':
                         Some(cr) if seen == Seen::Undef => {
-                            analyze_stack.push((l, &ca.view(cr).lits()[1..]));
+                            analyze_stack.push((l, ca.view(cr).tail()));
                         }
 
                         // Check variable can not be removed for some local reason:
@@ -255,26 +366,99 @@ impl AnalyzeContext {
     pub fn analyze_final(&mut self, ca: &ClauseAllocator, assigns: &Assignment, p: Lit) -> LitMap<()> {
         let mut out_conflict = LitMap::new();
         out_conflict.insert(&p, ());
+        if assigns.is_ground_level() {
+            return out_conflict;
+        }
+
+        self.seen[&p.var()] = Seen::Source;
+        self.collect_decisions(ca, assigns, &mut out_conflict);
+        self.seen[&p.var()] = Seen::Undef;
+
+        out_conflict
+    }
+
+    // Same as 'analyze_final', for a clause with all literals false: negations of decisions that
+    // led to the conflict.
+    pub fn analyze_final_clause(&mut self, ca: &ClauseAllocator, assigns: &Assignment, confl: ClauseRef) -> LitMap<()> {
+        let mut out_conflict = LitMap::new();
+        for &lit in ca.view(confl).lits() {
+            if assigns.vardata(lit).level > GROUND_LEVEL {
+                self.seen[&lit.var()] = Seen::Source;
+            }
+        }
+        self.collect_decisions(ca, assigns, &mut out_conflict);
+
+        out_conflict
+    }
 
+    // Walk the trail back from marked variables to decisions, clearing the marks on the way:
+    fn collect_decisions(&mut self, ca: &ClauseAllocator, assigns: &Assignment, out_conflict: &mut LitMap<()>) {
         for &lit in assigns.trail_above(GROUND_LEVEL).iter().rev() {
             if self.seen[&lit.var()] != Seen::Undef {
-                match assigns.vardata(lit).reason {
+                let vd = assigns.vardata(!lit);
+                match vd.reason {
                     None => {
-                        assert!(assigns.vardata(lit).level > GROUND_LEVEL);
+                        assert!(vd.level > GROUND_LEVEL);
                         out_conflict.insert(&!lit, ());
                     }
 
                     Some(cr) => {
-                        for &lit in &ca.view(cr).lits()[1..] {
+                        for &lit in ca.view(cr).tail() {
                             if assigns.vardata(lit).level > GROUND_LEVEL {
                                 self.seen[&lit.var()] = Seen::Source;
                             }
                         }
                     }
                 }
+                self.seen[&lit.var()] = Seen::Undef;
             }
         }
+    }
+}
 
-        out_conflict
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::backtrack::BacktrackableFormula;
+
+    #[test]
+    fn test_record_sides() {
+        let mut bt = BacktrackableFormula::new();
+        let mut ctx = AnalyzeContext::new(CCMinMode::Deep, false);
+        let [a, b, c, d, e, f] = [(); 6].map(|_| {
+            let v = bt.new_var();
+            ctx.init_var(v);
+            v.pos_lit()
+        });
+
+        let clauses: [&[Lit]; 5] = [&[c, !a], &[d, !b], &[f, !d], &[!e, !d], &[e, !f, !c]];
+        for lits in clauses.iter() {
+            let (_, cr) = bt.ca.alloc(lits, ClauseHeader::Clause { abstraction: None });
+            bt.attach(cr);
+        }
+
+        // Level 1: 'a' implies 'c'. Level 2: 'b' implies 'd', then 'f' and '~e', and the last clause
+        // is in conflict. Resolution goes back through '~e' and 'f' to the first UIP 'd':
+        bt.push_decision(a);
+        assert!(bt.propagate().is_none());
+        bt.push_decision(b);
+        let confl = bt.propagate().expect("Expected conflict");
+
+        ctx.record_sides(true);
+        match ctx.analyze(&bt.assigns, &mut bt.ca, &bt.watches, confl, |_| {}, |_, _| {}) {
+            Conflict::Learned(level, lit, _) => {
+                assert_eq!(level, bt.assigns.vardata(!c).level);
+                assert_eq!(lit, !d);
+            }
+            _ => panic!("Expected learnt clause"),
+        }
+
+        let sides = ctx.last_sides().expect("Expected recorded sides");
+        assert_eq!(sides.conflict_side, vec![!e, f]);
+        assert_eq!(sides.learnt, vec![!d, !c]);
+
+        ctx.record_sides(false);
+        assert!(ctx.last_sides().is_none());
     }
 }
@@ -1,8 +1,9 @@
+use alloc::vec::Vec;
 use crate::sat::formula::{assignment::Assignment, Lit, Var, VarHeap, VarMap};
-use super::random;
+use super::{activity::Activity, random};
 
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone)]
 pub enum PhaseSaving {
     None,
     Limited,
@@ -10,6 +11,7 @@ pub enum PhaseSaving {
 }
 
 
+#[derive(Clone)]
 pub struct DecisionHeuristicSettings {
     pub var_decay: f64,
     pub random_seed: f64,
@@ -17,6 +19,10 @@ pub struct DecisionHeuristicSettings {
     pub phase_saving: PhaseSaving, // Controls the level of phase saving
     pub rnd_pol: bool,             // Use random polarities for branching heuristics.
     pub rnd_init_act: bool,        // Initialize variable activities with a small random value.
+    pub batch_bump: bool,          // Restore the order heap once per conflict instead of after every bump.
+    pub priority_decay: f64,       // Factor applied to activity given by 'set_activity' after every solve, 1.0 keeps it.
+    pub bump_learnt_extra: f64,    // Extra bump of variables of the learnt clause, as a fraction of the regular one.
+    pub deterministic: bool,       // Fixed-point activities (of variables and learnt clauses) and integer random numbers, giving the same search on every platform.
 }
 
 impl Default for DecisionHeuristicSettings {
@@ -28,25 +34,32 @@ impl Default for DecisionHeuristicSettings {
             phase_saving: PhaseSaving::Full,
             rnd_pol: false,
             rnd_init_act: false,
+            batch_bump: false,
+            priority_decay: 1.0,
+            bump_learnt_extra: 0.0,
+            deterministic: false,
         }
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct VarLine {
     polarity: bool,         // The preferred polarity of each variable.
     user_pol: Option<bool>, // The users preferred polarity of each variable.
     decision: bool, // Declares if a variable is eligible for selection in the decision heuristic.
+    last: Option<bool>, // Sign of the last assignment of the variable, kept after it is undone.
 }
 
+#[derive(Clone)]
 pub struct DecisionHeuristic {
     settings: DecisionHeuristicSettings,
-    var_inc: f64, // Amount to bump next variable with.
     rand: random::Random,
     var: VarMap<VarLine>,
-    activity: VarMap<f64>,
+    activity: Activity, // Also keeps the part set by the user, tracked only if 'priority_decay' is below 1.0.
     queue: VarHeap, // A priority queue of variables ordered with respect to the variable activity.
+    prefix: VarMap<usize>, // Position of every variable decided before all others (see 'set_decision_prefix').
+    prefix_queue: VarHeap, // Prefix variables ordered by position, then by activity.
 
     pub dec_vars: usize,
     pub rnd_decisions: u64,
@@ -54,46 +67,72 @@ pub struct DecisionHeuristic {
 
 impl DecisionHeuristic {
     pub fn new(settings: DecisionHeuristicSettings) -> DecisionHeuristic {
-        let seed = settings.random_seed;
+        let rand = random::Random::new(settings.random_seed);
+        let rand = if settings.deterministic { rand.integer() } else { rand };
+        let activity = Activity::new(settings.deterministic);
         DecisionHeuristic {
             settings,
-            var_inc: 1.0,
-            rand: random::Random::new(seed),
+            rand,
             var: VarMap::new(),
-            activity: VarMap::new(),
+            activity,
             queue: VarHeap::new(),
+            prefix: VarMap::new(),
+            prefix_queue: VarHeap::new(),
             dec_vars: 0,
             rnd_decisions: 0,
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.var.reserve(n);
+        self.activity.reserve(n);
+        self.queue.reserve(n);
+    }
+
     pub fn init_var(&mut self, v: Var, upol: Option<bool>, dvar: bool) {
-        self.activity.insert(
-            &v,
-            if self.settings.rnd_init_act {
-                self.rand.drand() * 0.00001
-            } else {
-                0.0
-            },
-        );
+        self.init_activity(&v);
         self.var.insert(
             &v,
             VarLine {
                 polarity: true,
                 user_pol: upol,
                 decision: false,
+                last: None,
             },
         );
         self.set_decision_var(v, dvar);
     }
 
+    fn init_activity(&mut self, v: &Var) {
+        let rand = if self.settings.rnd_init_act { Some(&mut self.rand) } else { None };
+        self.activity.init(v, rand);
+    }
+
+    // Forget activities and saved phases unless 'keep', as if all variables were just created.
+    // User polarities, decision flags and the prefix stay. The order heap has to be rebuilt after.
+    pub fn reset(&mut self, keep: bool) {
+        if keep {
+            return;
+        }
+
+        self.activity.clear();
+        let vars: Vec<Var> = self.var.iter().map(|(v, _)| v).collect();
+        for v in vars {
+            self.init_activity(&v);
+            self.var[&v].polarity = true;
+        }
+    }
+
     pub fn set_decision_var(&mut self, v: Var, b: bool) {
         let ref mut ln = self.var[&v];
         if b != ln.decision {
             if b {
                 self.dec_vars += 1;
-                let ref act = self.activity;
-                self.queue.insert(v, |a, b| act[a] > act[b]);
+                let act = &self.activity;
+                self.queue.insert(v, |a, b| act.before(a, b));
+                if self.prefix.get(&v).is_some() {
+                    self.prefix_queue.insert(v, prefix_order(act, &self.prefix));
+                }
             } else {
                 self.dec_vars -= 1;
             }
@@ -104,6 +143,7 @@ impl DecisionHeuristic {
     #[inline]
     pub fn save_phase(&mut self, lit: Lit, top_level: bool) {
         let ref mut ln = self.var[&lit.var()];
+        ln.last = Some(lit.sign());
         match self.settings.phase_saving {
             PhaseSaving::Full => {
                 ln.polarity = lit.sign();
@@ -118,29 +158,121 @@ impl DecisionHeuristic {
     pub fn try_return_var(&mut self, var: Var) {
         let ref mut ln = self.var[&var];
         if ln.decision {
-            let ref act = self.activity;
-            self.queue.insert(var, |a, b| act[a] > act[b]);
+            let act = &self.activity;
+            self.queue.insert(var, |a, b| act.before(a, b));
+            if self.prefix.get(&var).is_some() {
+                self.prefix_queue.insert(var, prefix_order(act, &self.prefix));
+            }
+        }
+    }
+
+    // Restore position of 'v' in the prefix queue after its activity has changed:
+    fn update_prefix(&mut self, v: &Var) {
+        if !self.prefix_queue.is_empty() {
+            self.prefix_queue.update(v, prefix_order(&self.activity, &self.prefix));
         }
     }
 
     pub fn bump_activity(&mut self, v: &Var) {
-        let new = self.activity[v] + self.var_inc;
-        if new > 1e100 {
-            self.var_inc *= 1e-100;
-            for (_, act) in self.activity.iter_mut() {
-                *act *= 1e-100;
+        self.activity.bump(v, 1.0);
+        let act = &self.activity;
+        self.queue.update(v, |a, b| act.before(a, b));
+        self.update_prefix(v);
+    }
+
+    // Same activities as bumping 'vars' one by one. With 'batch_bump' the heap is restored after
+    // all of them, and rebuilt from scratch if that is cheaper than sifting every bumped variable.
+    // Variables of equal activity may then come out of the heap in a different order.
+    pub fn bump_activities(&mut self, vars: &[Var]) {
+        if !self.settings.batch_bump {
+            for v in vars.iter() {
+                self.bump_activity(v);
             }
-            self.activity[v] = new * 1e-100;
+            return;
+        }
+
+        for v in vars.iter() {
+            self.activity.bump(v, 1.0);
+        }
+
+        let act = &self.activity;
+        let n = self.queue.len();
+        if vars.len() * log2(n) > n {
+            self.queue.rebuild(|a, b| act.before(a, b));
         } else {
-            self.activity[v] = new;
+            for v in vars.iter() {
+                self.queue.update(v, |a, b| act.before(a, b));
+            }
+        }
+        if !self.prefix_queue.is_empty() {
+            self.prefix_queue.rebuild(prefix_order(act, &self.prefix));
+        }
+    }
+
+    // Bump variables of the learnt clause once more by 'bump_learnt_extra' (after 'bump_activities'
+    // of the whole conflict side).
+    pub fn bump_learnt(&mut self, learnt: &[Lit]) {
+        let extra = self.settings.bump_learnt_extra;
+        if extra <= 0.0 {
+            return;
+        }
+
+        for lit in learnt.iter() {
+            self.activity.bump(&lit.var(), extra);
+            let act = &self.activity;
+            self.queue.update(&lit.var(), |a, b| act.before(a, b));
+            self.update_prefix(&lit.var());
+        }
+    }
+
+    // Overwrite activity of 'v' (in the same units as current activities, which grow with 'var_inc'):
+    pub fn set_activity(&mut self, v: &Var, act: f64) {
+        self.activity.set(v, act, self.settings.priority_decay < 1.0);
+        let activity = &self.activity;
+        self.queue.update(v, |a, b| activity.before(a, b));
+        self.update_prefix(v);
+    }
+
+    pub fn into_activity(self) -> VarMap<f64> {
+        self.activity.into_float()
+    }
+
+    // Decide unassigned variables from 'vars' before all others, in the given order. Activity only
+    // breaks ties between equal positions:
+    pub fn set_decision_prefix(&mut self, vars: &[Var]) {
+        self.prefix = VarMap::new();
+        let mut queued = Vec::with_capacity(vars.len());
+        for (i, &v) in vars.iter().enumerate() {
+            if self.prefix.get(&v).is_none() {
+                self.prefix.insert(&v, i);
+                if self.var[&v].decision {
+                    queued.push(v);
+                }
+            }
+        }
+        self.prefix_queue.heapify_from(queued, prefix_order(&self.activity, &self.prefix));
+    }
+
+    // Take away a 'priority_decay' share of activity set by the user. Activity from bumps stays, so a
+    // boosted variable gradually returns to where VSIDS alone would put it. Note that bumps grow with
+    // 'var_inc', so any boost loses weight relative to them during search anyway.
+    pub fn decay_priorities(&mut self) {
+        if self.settings.priority_decay >= 1.0 {
+            return;
         }
 
-        let ref act = self.activity;
-        self.queue.update(v, |a, b| act[a] > act[b]);
+        self.activity.decay_boosts(self.settings.priority_decay);
+        let act = &self.activity;
+        for v in act.boosted() {
+            self.queue.update(&v, |a, b| act.before(a, b));
+        }
+        if !self.prefix_queue.is_empty() {
+            self.prefix_queue.rebuild(prefix_order(act, &self.prefix));
+        }
     }
 
     pub fn decay_activity(&mut self) {
-        self.var_inc *= 1.0 / self.settings.var_decay;
+        self.activity.decay(self.settings.var_decay);
     }
 
     pub fn rebuild_order_heap(&mut self, assigns: &Assignment) {
@@ -151,11 +283,24 @@ impl DecisionHeuristic {
             }
         }
 
-        let ref act = self.activity;
-        self.queue.heapify_from(tmp, |a, b| act[a] > act[b]);
+        let act = &self.activity;
+        self.queue.heapify_from(tmp, |a, b| act.before(a, b));
+
+        let prefix: Vec<Var> = self.prefix.iter()
+            .map(|(v, _)| v)
+            .filter(|&v| self.var[&v].decision && assigns.is_undef(v))
+            .collect();
+        self.prefix_queue.heapify_from(prefix, prefix_order(act, &self.prefix));
     }
 
     fn pick_branch_var(&mut self, assigns: &Assignment) -> Option<Var> {
+        // Prefix decision:
+        while let Some(v) = self.prefix_queue.pop(prefix_order(&self.activity, &self.prefix)) {
+            if assigns.is_undef(v) && self.var[&v].decision {
+                return Some(v);
+            }
+        }
+
         // Random decision:
         if self.rand.chance(self.settings.random_var_freq) && !self.queue.is_empty() {
             let v = self.queue[self.rand.irand(self.queue.len())];
@@ -167,8 +312,8 @@ impl DecisionHeuristic {
 
         // Activity based decision:
         while let Some(v) = {
-            let ref act = self.activity;
-            self.queue.pop(|a, b| act[a] > act[b])
+            let act = &self.activity;
+            self.queue.pop(|a, b| act.before(a, b))
         } {
             if assigns.is_undef(v) && self.var[&v].decision {
                 return Some(v);
@@ -178,6 +323,43 @@ impl DecisionHeuristic {
         None
     }
 
+    // Activity of the variable that would be picked next if there is no random decision, or 'None'
+    // if it is not picked by activity. Assigned variables are dropped from the queue on the way,
+    // just as 'pick_branch_var' does.
+    pub fn next_activity(&mut self, assigns: &Assignment) -> Option<f64> {
+        while !self.prefix_queue.is_empty() {
+            let v = self.prefix_queue[0];
+            if assigns.is_undef(v) && self.var[&v].decision {
+                return None;
+            }
+            self.prefix_queue.pop(prefix_order(&self.activity, &self.prefix));
+        }
+
+        while !self.queue.is_empty() {
+            let v = self.queue[0];
+            if assigns.is_undef(v) && self.var[&v].decision {
+                return Some(self.activity.get(&v));
+            }
+            let act = &self.activity;
+            self.queue.pop(|a, b| act.before(a, b));
+        }
+
+        None
+    }
+
+    // Literal of the last assignment of 'v' that was undone, if any:
+    pub fn last_assigned(&self, v: Var) -> Option<Lit> {
+        self.var[&v].last.map(|sign| v.sign_lit(sign))
+    }
+
+    pub fn forget_last_assigned(&mut self, v: Var) {
+        self.var[&v].last = None;
+    }
+
+    pub fn activity(&self, v: Var) -> f64 {
+        self.activity.get(&v)
+    }
+
     pub fn pick_branch_lit(&mut self, assigns: &Assignment) -> Option<Lit> {
         // Choose polarity based on different polarity modes (global or per-variable):
         self.pick_branch_var(assigns).map(|v| {
@@ -191,3 +373,155 @@ impl DecisionHeuristic {
         })
     }
 }
+
+
+// Order of the prefix queue: by position in the prefix, and by activity between equal ones.
+fn prefix_order<'a>(act: &'a Activity, prefix: &'a VarMap<usize>) -> impl Fn(&Var, &Var) -> bool + 'a {
+    move |a, b| prefix[a] < prefix[b] || (prefix[a] == prefix[b] && act.before(a, b))
+}
+
+fn log2(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decision_prefix() {
+        let mut assigns = Assignment::new();
+        let mut heur = DecisionHeuristic::new(Default::default());
+        let vars: Vec<Var> = (0..8).map(|_| {
+            let v = assigns.new_var();
+            heur.init_var(v, None, true);
+            v
+        }).collect();
+
+        // Variables outside of the prefix are the most active ones, and prefix variables listed
+        // later are more active than the first one:
+        for (i, v) in vars.iter().enumerate() {
+            heur.set_activity(v, i as f64);
+        }
+        heur.set_activity(&vars[3], 2.0);
+        let prefix = [vars[3], vars[1], vars[4], vars[2]];
+        heur.set_decision_prefix(&prefix);
+
+        // Bumps during search don't reorder the prefix:
+        heur.bump_activity(&vars[1]);
+        heur.bump_activity(&vars[1]);
+
+        let mut order = Vec::new();
+        while let Some(lit) = heur.pick_branch_lit(&assigns) {
+            let v = lit.var();
+            if !prefix.contains(&v) {
+                assert!(prefix.iter().all(|&p| !assigns.is_undef(p)));
+            }
+            order.push(v);
+            assigns.new_decision_level();
+            assigns.assign_lit(lit, None);
+        }
+
+        // The prefix in the given order, whatever the activities, then the most active variable:
+        assert_eq!(order.len(), vars.len());
+        assert_eq!(&order[..4], &prefix);
+        assert_eq!(order[4], vars[7]);
+    }
+
+    #[test]
+    fn test_bump_activities() {
+        let run = |batch_bump: bool, deterministic: bool| {
+            let mut assigns = Assignment::new();
+            let mut heur = DecisionHeuristic::new(DecisionHeuristicSettings { batch_bump, deterministic, ..Default::default() });
+            let vars: Vec<Var> = (0..64).map(|_| {
+                let v = assigns.new_var();
+                heur.init_var(v, None, true);
+                v
+            }).collect();
+
+            // Long and short conflicts, with distinct bump counts for every variable in the end:
+            for round in 0..64 {
+                heur.bump_activities(&vars[round..]);
+                heur.decay_activity();
+            }
+
+            let mut order = Vec::new();
+            while let Some(lit) = heur.pick_branch_lit(&assigns) {
+                order.push((lit.var(), heur.activity(lit.var())));
+                assigns.new_decision_level();
+                assigns.assign_lit(lit, None);
+            }
+            order
+        };
+
+        let expected: Vec<Var> = (0..64).rev().map(Var::from_index).collect();
+        let plain = run(false, false);
+        assert_eq!(plain.iter().map(|&(v, _)| v).collect::<Vec<_>>(), expected);
+        assert_eq!(run(true, false), plain);
+
+        // Fixed-point activities, in the same order and the same units:
+        let fixed = run(false, true);
+        assert_eq!(fixed.iter().map(|&(v, _)| v).collect::<Vec<_>>(), expected);
+        assert_eq!(run(true, true), fixed);
+        for (&(_, p), &(_, f)) in plain.iter().zip(fixed.iter()) {
+            assert!((p - f).abs() <= p * 1e-3, "{} {}", p, f);
+        }
+
+        // A value set is read back the same in both modes, and bumps add the same to it:
+        for &deterministic in [false, true].iter() {
+            let mut heur = DecisionHeuristic::new(DecisionHeuristicSettings { deterministic, ..Default::default() });
+            let v = Assignment::new().new_var();
+            heur.init_var(v, None, true);
+            heur.set_activity(&v, 5.0);
+            assert_eq!(heur.activity(v), 5.0);
+            heur.bump_activity(&v);
+            assert_eq!(heur.activity(v), 6.0);
+        }
+    }
+
+    #[test]
+    fn test_bump_learnt_extra() {
+        let run = |bump_learnt_extra: f64| {
+            let mut assigns = Assignment::new();
+            let mut heur = DecisionHeuristic::new(DecisionHeuristicSettings { bump_learnt_extra, ..Default::default() });
+            let vars: Vec<Var> = (0..4).map(|_| {
+                let v = assigns.new_var();
+                heur.init_var(v, None, true);
+                v
+            }).collect();
+
+            // All variables are on the conflict side, only two of them are left in the learnt clause:
+            heur.bump_activities(&vars);
+            heur.bump_learnt(&[vars[3].neg_lit(), vars[1].pos_lit()]);
+            heur.decay_activity();
+
+            let mut order = Vec::new();
+            while let Some(lit) = heur.pick_branch_lit(&assigns) {
+                order.push(lit.var());
+                assigns.new_decision_level();
+                assigns.assign_lit(lit, None);
+            }
+            order
+        };
+
+        // Equal activities without it, so the first variable is decided first:
+        assert_eq!(run(0.0)[0], Var::from_index(0));
+        let mut first = run(0.5)[..2].to_vec();
+        first.sort();
+        assert_eq!(first, vec![Var::from_index(1), Var::from_index(3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "activity increment is not finite")]
+    fn test_zero_decay() {
+        let mut assigns = Assignment::new();
+        let mut heur = DecisionHeuristic::new(DecisionHeuristicSettings { var_decay: 0.0, ..Default::default() });
+        let v = assigns.new_var();
+        heur.init_var(v, None, true);
+        for _ in 0..2 {
+            heur.bump_activity(&v);
+            heur.decay_activity();
+        }
+    }
+}
@@ -1,4 +1,6 @@
 
+// Luby value of index 'x' computed from scratch, kept as a reference for 'LubyIterator':
+#[cfg(test)]
 pub fn luby(y: f64, mut x: u32) -> f64 {
     // Find the finite subsequence that contains index 'x', and the
     // size of that subsequence:
@@ -16,5 +18,70 @@ pub fn luby(y: f64, mut x: u32) -> f64 {
         x = x % size;
     }
 
-    y.powi(seq)
+    powi(y, seq)
+}
+
+
+// Successive values of 'luby(2.0, x)' for x = 0, 1, 2, ... Every value takes O(1): 'v' doubles
+// until it reaches the lowest set bit of 'u', then the next subsequence starts over from 1.
+pub struct LubyIterator {
+    u: u64,
+    v: u64,
+}
+
+impl LubyIterator {
+    pub fn new() -> LubyIterator {
+        LubyIterator { u: 1, v: 1 }
+    }
+}
+
+impl Iterator for LubyIterator {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let res = self.v;
+        if self.u & self.u.wrapping_neg() == self.v {
+            self.u += 1;
+            self.v = 1;
+        } else {
+            self.v *= 2;
+        }
+        Some(res)
+    }
+}
+
+// 'f64::powi' comes with 'std'. Without it the power is computed by squaring, which may differ
+// from it in the last bits.
+#[cfg(feature = "std")]
+#[inline]
+pub fn powi(x: f64, n: i32) -> f64 {
+    x.powi(n)
+}
+
+#[cfg(not(feature = "std"))]
+pub fn powi(x: f64, n: i32) -> f64 {
+    let mut base = if n < 0 { 1.0 / x } else { x };
+    let mut exp = n.unsigned_abs();
+    let mut res = 1.0;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res *= base;
+        }
+        base *= base;
+        exp >>= 1;
+    }
+    res
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_luby_iterator() {
+        for (i, x) in LubyIterator::new().take(32).enumerate() {
+            assert_eq!(x as f64, luby(2.0, i as u32), "index {}", i);
+        }
+    }
 }
@@ -1,15 +1,39 @@
-
+#[derive(Clone)]
 pub struct Random {
     seed: f64,
+    integer: bool, // Step with integer operations (see 'integer'), 'seed' then always holds an integer.
 }
 
+const MODULUS: u64 = 2147483647;
+
 impl Random {
     pub fn new(seed: f64) -> Random {
-        Random { seed }
+        Random { seed, integer: false }
+    }
+
+    // Any integer seed, mapped into the valid range [1, 2^31 - 2]:
+    pub fn from_u64(seed: u64) -> Random {
+        Random::new((seed % (MODULUS - 1) + 1) as f64)
+    }
+
+    // The same sequence computed with integer operations, so that it and the integers from 'irand'
+    // do not depend on floating-point rounding of the platform. Seed is truncated to an integer.
+    pub fn integer(self) -> Random {
+        Random { seed: (self.seed as u64 % MODULUS) as f64, integer: true }
+    }
+
+    fn step(&mut self) -> u64 {
+        let seed = (self.seed as u64 * 1389796) % MODULUS;
+        self.seed = seed as f64;
+        seed
     }
 
     // Returns a random float 0 <= x < 1. Seed must never be 0.
     pub fn drand(&mut self) -> f64 {
+        if self.integer {
+            return self.step() as f64 / MODULUS as f64;
+        }
+
         self.seed *= 1389796.0;
         let q = (self.seed / 2147483647.0) as i32;
         self.seed -= (q as f64) * 2147483647.0;
@@ -18,6 +42,10 @@ impl Random {
 
     // Returns a random integer 0 <= x < size. Seed must never be 0.
     pub fn irand(&mut self, size: usize) -> usize {
+        if self.integer {
+            return (self.step() as u128 * size as u128 / MODULUS as u128) as usize;
+        }
+
         (self.drand() * (size as f64)) as usize
     }
 
@@ -25,3 +53,26 @@ impl Random {
         self.drand() < p
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer() {
+        let mut float = Random::new(91648253.0);
+        let mut integer = Random::new(91648253.0).integer();
+        for _ in 0..10000 {
+            assert_eq!(integer.drand(), float.drand());
+            assert_eq!(integer.seed, float.seed);
+        }
+
+        let n = 1000;
+        let mut counts = vec![0; 10];
+        for _ in 0..n {
+            counts[integer.irand(10)] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > n / 20));
+    }
+}
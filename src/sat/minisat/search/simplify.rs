@@ -1,4 +1,6 @@
-use crate::sat::formula::{util::*, clause::*, LBool, Lit, Var, VarMap};
+use core::convert::TryFrom;
+use alloc::{vec, vec::Vec};
+use crate::sat::formula::{assignment::GROUND_LEVEL, util::*, clause::*, LBool, Lit, LitMap, Var, VarMap};
 use super::{util::*, SearchRes, SearchSettings, Searcher};
 use super::super::budget::Budget;
 use self::{elim_clauses::*, elim_queue::ElimOcc, subsumes::*, subsumption_queue::*};
@@ -14,9 +16,14 @@ pub struct SimplificatorSettings {
     pub grow: usize, // Allow a variable elimination step to grow by a number of clauses (default to zero).
     pub clause_lim: i32, // Variables are not eliminated if it produces a resolvent with a length above this limit. -1 means no limit.
     pub subsumption_lim: i32, // Do not check if subsumption against a clause larger than this. -1 means no limit.
+    pub subsuming_clause_max_len: i32, // Clauses longer than this are only checked for being subsumed, never used to subsume others. -1 means no limit.
     pub simp_garbage_frac: f64, // A different limit for when to issue a GC during simplification (Also see 'garbage_frac').
     pub use_asymm: bool,        // Shrink clauses by asymmetric branching.
     pub use_elim: bool,         // Perform variable elimination.
+    pub max_growth_factor: f64, // Variables are not eliminated if the number of clauses would exceed this multiple of its value before the pass.
+    pub use_pure_literals: bool, // Eliminate variables occurring in one polarity only, even if 'use_elim' is off.
+    pub max_preprocess_rounds: usize, // Repeat subsumption and elimination over the whole formula until a round changes nothing or this many rounds ran.
+    pub use_hbr: bool,          // Probe literals of new clauses before elimination, learning failed literals and hyper-binary resolvents.
 }
 
 impl Default for SimplificatorSettings {
@@ -25,9 +32,14 @@ impl Default for SimplificatorSettings {
             grow: 0,
             clause_lim: 20,
             subsumption_lim: 1000,
+            subsuming_clause_max_len: -1,
             simp_garbage_frac: 0.5,
             use_asymm: false,
             use_elim: true,
+            max_growth_factor: f64::INFINITY,
+            use_pure_literals: false,
+            max_preprocess_rounds: 1,
+            use_hbr: false,
         }
     }
 }
@@ -40,13 +52,52 @@ impl SimplificatorSettings {
     pub fn validate_subsumption_len(&self, len: usize) -> bool {
         self.subsumption_lim == -1 || ((len as i32) < self.subsumption_lim)
     }
+
+    pub fn validate_subsuming_len(&self, len: usize) -> bool {
+        match self.subsuming_clause_max_len {
+            -1 => true,
+            max => usize::try_from(max).is_ok_and(|max| len <= max),
+        }
+    }
+
+    // Replace limits below -1, which mean nothing, with the defaults.
+    pub fn validate(&mut self) {
+        let default = SimplificatorSettings::default();
+        for (name, lim, default) in [
+            ("clause_lim", &mut self.clause_lim, default.clause_lim),
+            ("subsumption_lim", &mut self.subsumption_lim, default.subsumption_lim),
+            ("subsuming_clause_max_len", &mut self.subsuming_clause_max_len, default.subsuming_clause_max_len),
+        ] {
+            if *lim < -1 {
+                warn!(target: "minisat::simp", "{} = {} is invalid, using {}", name, *lim, default);
+                *lim = default;
+            }
+        }
+    }
+}
+
+// Bound on binary clauses added by a single probing pass (see 'use_hbr'):
+const MAX_HBR_BINARIES: usize = 10000;
+
+
+// Preprocessing progress since the start of the current 'eliminate' call (or of a single round,
+// see 'Simplificator::rounds'):
+#[derive(Default, Debug, Clone)]
+pub struct SimpProgress {
+    pub eliminated_vars: u64,
+    pub removed_clauses: usize,
+    pub vars_left: usize, // Candidates still waiting in the elimination queue.
 }
 
 
 #[derive(Default)]
 struct Stats {
+    subsumption_checks: u64, // Clauses checked for being subsumed by a clause or a unit.
     asymm_lits: u64,
+    hbr_binaries: u64,
+    probed_lits: u64, // Literals assigned by 'probe'.
     eliminated_vars: u64,
+    rounds: Vec<SimpProgress>, // Reductions made by every round of the latest 'eliminate' call.
 }
 
 
@@ -67,17 +118,25 @@ impl Touched {
         self.n_touched == 0
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.touched.reserve(n);
+    }
+
     pub fn init_var(&mut self, v: Var) {
         self.touched.insert(&v, false);
     }
 
     pub fn add_clause(&mut self, lits: &[Lit]) {
         for &lit in lits {
-            self.touched[&lit.var()] = true;
-            self.n_touched += 1;
+            self.touch_var(lit.var());
         }
     }
 
+    pub fn touch_var(&mut self, v: Var) {
+        self.touched[&v] = true;
+        self.n_touched += 1;
+    }
+
 
     fn enqueue_touched_clauses(&mut self, ca: &mut ClauseAllocator, elo: &mut ElimOcc, queue: &mut SubsumptionQueue) {
         if self.is_empty() {
@@ -117,23 +176,49 @@ pub struct Simplificator {
     stats: Stats,
     elo: elim_queue::ElimOcc,
     touched: Touched,
+    unprobed: VarMap<bool>, // Variables of clauses added since they were last probed (see 'probe').
     subsumption_queue: SubsumptionQueue,
 }
 
 impl Simplificator {
-    pub fn new(settings: SimplificatorSettings) -> Self {
+    pub fn new(mut settings: SimplificatorSettings) -> Self {
+        settings.validate();
         Simplificator {
             settings,
             stats: Stats::default(),
             elo: elim_queue::ElimOcc::new(),
             touched: Touched::new(),
+            unprobed: VarMap::new(),
             subsumption_queue: SubsumptionQueue::new()
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.elo.reserve_vars(n);
+        self.touched.reserve_vars(n);
+        self.unprobed.reserve(n);
+    }
+
     pub fn init_var(&mut self, v: Var) {
         self.elo.init_var(v);
         self.touched.init_var(v);
+        self.unprobed.insert(&v, false);
+    }
+
+    pub fn is_eliminated(&self, v: Var) -> bool {
+        self.elo.is_eliminated(v)
+    }
+
+    pub fn rounds(&self) -> &[SimpProgress] {
+        &self.stats.rounds
+    }
+
+    // Undo elimination status of 'v'. Its removed clauses are expected to be added back by the caller.
+    pub fn reintroduce_var(&mut self, search: &Searcher, v: Var) {
+        assert!(self.elo.is_eliminated(v));
+        self.elo.var_status[&v].eliminated = false;
+        self.elo.occurs.init_var(&v);
+        self.elo.elim.update_elim_heap(v, &self.elo.var_status, &search.bt.assigns);
     }
 
     pub fn add_clause(&mut self, search: &mut Searcher, ps: &[Lit]) -> Result<(), ()> {
@@ -143,7 +228,7 @@ impl Simplificator {
         }
         //#endif
 
-        match search.add_clause(ps) {
+        match search.ground().add_clause(ps) {
             super::AddClauseRes::UnSAT => Err(()),
             super::AddClauseRes::Consumed => Ok(()),
             super::AddClauseRes::Added(cr) => {
@@ -157,11 +242,18 @@ impl Simplificator {
                 let lits = search.bt.ca.literals(cr);
                 self.elo.add_clause(cr, lits);
                 self.touched.add_clause(lits);
+                self.mark_unprobed(lits);
                 Ok(())
             }
         }
     }
 
+    fn mark_unprobed(&mut self, lits: &[Lit]) {
+        for lit in lits.iter() {
+            self.unprobed[&lit.var()] = true;
+        }
+    }
+
     pub fn solve_limited(
         &mut self,
         mut search: Searcher,
@@ -191,7 +283,7 @@ impl Simplificator {
             return SearchRes::UnSAT(search.stats());
         }
 
-        if !self.eliminate(&mut search, budget, elimclauses).is_ok() {
+        if self.eliminate(&mut search, budget, elimclauses, |_| {}).is_err() {
             return SearchRes::UnSAT(search.stats());
         }
 
@@ -210,21 +302,89 @@ impl Simplificator {
         }
     }
 
-    pub fn eliminate(
+    pub fn eliminate<F: FnMut(&SimpProgress)>(
+        &mut self,
+        search: &mut Searcher,
+        budget: &Budget,
+        elimclauses: &mut ElimClauses,
+        mut progress: F,
+    ) -> Result<(), ()> {
+        let base_eliminated = self.stats.eliminated_vars;
+        let base_clauses = search.ctx.db.stats.num_clauses;
+        self.stats.rounds.clear();
+
+        if self.settings.use_hbr {
+            self.probe(search, budget, base_eliminated)?;
+        }
+
+        for round in 0..self.settings.max_preprocess_rounds.max(1) {
+            if round > 0 {
+                if !self.within(budget, base_eliminated) {
+                    break;
+                }
+
+                // Every remaining clause and variable becomes a candidate again:
+                for (v, st) in self.elo.var_status.iter() {
+                    if !st.eliminated && search.bt.assigns.is_undef(v) {
+                        self.touched.touch_var(v);
+                        self.elo.elim.update_elim_heap(v, &self.elo.var_status, &search.bt.assigns);
+                    }
+                }
+            }
+
+            let round_eliminated = self.stats.eliminated_vars;
+            let round_clauses = search.ctx.db.stats.num_clauses;
+            self.eliminate_round(search, budget, elimclauses, (base_eliminated, base_clauses), &mut progress)?;
+
+            let reduction = SimpProgress {
+                eliminated_vars: self.stats.eliminated_vars - round_eliminated,
+                removed_clauses: round_clauses.saturating_sub(search.ctx.db.stats.num_clauses),
+                vars_left: self.elo.elim.len(),
+            };
+            trace!(target: "minisat::simp", "ROUND {}: {:?}", round + 1, reduction);
+            let changed = reduction.eliminated_vars > 0 || reduction.removed_clauses > 0;
+            self.stats.rounds.push(reduction);
+            if !changed {
+                break;
+            }
+        }
+
+        #[cfg(debug_assertions)]
+        search.check_watches();
+        Ok(())
+    }
+
+    // A single pass of subsumption and elimination over the queued candidates. Progress is
+    // reported relative to 'base', the state before the whole 'eliminate' call.
+    fn eliminate_round<F: FnMut(&SimpProgress)>(
         &mut self,
         search: &mut Searcher,
         budget: &Budget,
         elimclauses: &mut ElimClauses,
+        base: (u64, usize),
+        progress: &mut F,
     ) -> Result<(), ()> {
+        let (base_eliminated, base_clauses) = base;
+        let max_clauses = self.settings.max_growth_factor * (base_clauses as f64);
+        let report = |simp: &Self, search: &Searcher| SimpProgress {
+            eliminated_vars: simp.stats.eliminated_vars - base_eliminated,
+            removed_clauses: base_clauses.saturating_sub(search.ctx.db.stats.num_clauses),
+            vars_left: simp.elo.elim.len(),
+        };
+
+        if self.settings.use_pure_literals {
+            self.eliminate_pure_literals(search, elimclauses)?;
+        }
+
         // Main simplification loop:
         while !self.touched.is_empty() || self.subsumption_queue.assigns_left(&search.bt.assigns) > 0
             || self.elo.elim.len() > 0
         {
             self.touched.enqueue_touched_clauses(&mut search.bt.ca, &mut self.elo, &mut self.subsumption_queue);
-            self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, true)?;
+            self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, base_eliminated, true)?;
 
-            // Empty elim_heap and return immediately on user-interrupt:
-            if budget.interrupted() {
+            // Empty elim_heap and return immediately on user-interrupt or when out of budget:
+            if !self.within(budget, base_eliminated) {
                 assert_eq!(self.subsumption_queue.assigns_left(&search.bt.assigns), 0);
                 assert!(self.subsumption_queue.is_empty());
                 assert!(self.touched.is_empty());
@@ -232,15 +392,16 @@ impl Simplificator {
                 break;
             }
 
-            trace!("ELIM: vars = {}", self.elo.elim.len());
+            trace!(target: "minisat::simp", "ELIM: vars = {}", self.elo.elim.len());
             let mut cnt = 0;
             while let Some(var) = self.elo.elim.pop() {
-                if budget.interrupted() {
+                if !self.within(budget, base_eliminated) {
                     break;
                 }
 
                 if cnt % 100 == 0 {
-                    trace!("elimination left: {:10}", self.elo.elim.len());
+                    trace!(target: "minisat::simp", "elimination left: {:10}", self.elo.elim.len());
+                    progress(&report(self, search));
                 }
                 cnt += 1;
 
@@ -253,15 +414,15 @@ impl Simplificator {
                     let was_frozen = self.elo.var_status[&var].frozen;
                     self.elo.var_status[&var].frozen = true;
                     self.asymm_var(&mut search.bt, &mut search.ctx.db, &mut search.ctx.heur, var)?;
-                    self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, false)?;
+                    self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, base_eliminated, false)?;
                     self.elo.var_status[&var].frozen = was_frozen;
                 }
 
                 // At this point, the variable may have been set by asymmetric branching, so check it
                 // again. Also, don't eliminate frozen variables:
                 if self.settings.use_elim && search.bt.assigns.is_undef(var) && !self.elo.is_frozen(var) {
-                    self.eliminate_var(search, elimclauses, var)?;
-                    self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, false)?;
+                    self.eliminate_var(search, elimclauses, max_clauses, var)?;
+                    self.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, budget, base_eliminated, false)?;
                 }
 
                 self.try_garbage_collect(search);
@@ -270,22 +431,119 @@ impl Simplificator {
             assert!(self.subsumption_queue.is_empty());
         }
 
+        progress(&report(self, search));
         Ok(())
     }
 
+    // A pure variable is eliminated without any resolvents, so model extension gives it the
+    // polarity it occurs in. Removing its clauses may leave other variables pure as well.
+    fn eliminate_pure_literals(&mut self, search: &mut Searcher, elimclauses: &mut ElimClauses) -> Result<(), ()> {
+        loop {
+            let pure: Vec<Var> = self.elo.var_status.iter()
+                .filter(|&(v, st)| !st.frozen && !st.eliminated && search.bt.assigns.is_undef(v))
+                .map(|(v, _)| v)
+                .filter(|&v| self.elo.elim.pure_lit(v).is_some())
+                .collect();
+            if pure.is_empty() {
+                return Ok(());
+            }
+
+            for v in pure {
+                self.eliminate_var(search, elimclauses, f64::INFINITY, v)?;
+            }
+        }
+    }
+
+    // Failed-literal probing with hyper-binary resolution: every literal of a variable in clauses
+    // added since it was last probed is assigned alone above ground level and propagated. A literal
+    // leading to a conflict is false, otherwise every literal 'f' it forces through a longer clause
+    // gives a binary clause '!d | f', unless 'f' is reachable from 'd' through binary clauses
+    // already. These are learnt with LBD 2, so they never enter the occurrence lists used for
+    // elimination, and 'reduce' keeps them as it keeps every binary clause. At most
+    // 'MAX_HBR_BINARIES' of them are added per call. Variables not reached when out of budget are
+    // probed by the next call.
+    fn probe(&mut self, search: &mut Searcher, budget: &Budget, base_eliminated: u64) -> Result<(), ()> {
+        let mut added = 0;
+        let vars: Vec<Var> = self.elo.var_status.iter()
+            .filter(|&(v, st)| !st.eliminated && self.unprobed[&v])
+            .map(|(v, _)| v)
+            .collect();
+        for v in vars {
+            if !self.within(budget, base_eliminated) {
+                break;
+            }
+            self.unprobed[&v] = false;
+
+            for &d in [v.pos_lit(), v.neg_lit()].iter() {
+                if !search.bt.assigns.is_undef(v) {
+                    break;
+                }
+
+                self.stats.probed_lits += 1;
+                search.bt.assigns.new_decision_level();
+                search.bt.assigns.assign_lit(d, None);
+                let failed = search.bt.propagate().is_some();
+                let forced: Vec<Lit> =
+                    if failed {
+                        Vec::new()
+                    } else {
+                        let bt = &search.bt;
+                        bt.assigns.trail_above(GROUND_LEVEL)[1..].iter()
+                            .filter(|&&f| bt.assigns.vardata(!f).reason.is_some_and(|cr| bt.ca.view(cr).len() > 2))
+                            .cloned()
+                            .collect()
+                    };
+                search.bt.assigns.backtrack_to(GROUND_LEVEL);
+
+                if failed {
+                    self.add_clause(search, &[!d])?;
+                    continue;
+                }
+
+                let mut reached = LitMap::new();
+                reach_by_binaries(&search.bt, d, &mut reached);
+                for f in forced {
+                    if added >= MAX_HBR_BINARIES {
+                        return Ok(());
+                    }
+                    if reached.get(&f).is_some() {
+                        continue;
+                    }
+
+                    let cr = search.ctx.db.learn_clause(&mut search.bt.ca, &[!d, f], 2);
+                    search.bt.attach(cr);
+                    reach_by_binaries(&search.bt, f, &mut reached);
+                    added += 1;
+                    self.stats.hbr_binaries += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn within(&self, budget: &Budget, base_eliminated: u64) -> bool {
+        budget.within_elim(self.stats.eliminated_vars - base_eliminated)
+    }
+
+    // Remove 'l' from clause 'cr'. Clauses are never shrunk to length 1: a binary clause is deleted
+    // together with its watches and occurrences, and the other literal is propagated as a ground
+    // unit instead. Returns 'Err' if that unit is false already or propagating it fails, i.e. the
+    // formula is UNSAT.
     fn strengthen_clause(&mut self, bt: &mut BacktrackableFormula, db: &mut ClauseDB, cr: ClauseRef, l: Lit) -> Result<(), ()> {
         assert!(bt.is_ground_level());
 
         let len = bt.ca.view(cr).len();
         if len == 2 {
-            self.elo.smudge_clause(&bt.assigns, bt.ca.literals(cr));
-            bt.lazy_detach(cr);
-            db.remove_clause(&mut bt.ca, cr);
-
-            let unit = {
-                let c = bt.ca.edit(cr);
-                if l == c.prefix[0] { c.prefix[1] } else { c.prefix[0] }
+            let (unit, other) = {
+                let c = bt.ca.view(cr);
+                if l == c.prefix[0] { (c.prefix[1], c.prefix[0]) } else { (c.prefix[0], c.prefix[1]) }
             };
+            assert_eq!(l, other, "Literal {:?} is not found in binary clause", l);
+
+            self.elo.remove_lit(&bt.assigns, unit, cr);
+            self.elo.remove_lit(&bt.assigns, l, cr);
+            bt.force_detach(cr);
+            db.remove_clause(&mut bt.ca, cr);
 
             try_propagate(bt, unit, None)?;
         } else {
@@ -335,7 +593,7 @@ impl Simplificator {
         Ok(true)
     }
 
-    fn eliminate_var(&mut self, search: &mut Searcher, elimclauses: &mut ElimClauses, v: Var) -> Result<bool, ()> {
+    fn eliminate_var(&mut self, search: &mut Searcher, elimclauses: &mut ElimClauses, max_clauses: f64, v: Var) -> Result<bool, ()> {
         assert!({
             let ref st = self.elo.var_status[&v];
             !st.frozen && !st.eliminated
@@ -382,9 +640,17 @@ impl Simplificator {
             resolvents
         };
 
+        // Net growth of the whole formula is capped as well:
+        if ((search.ctx.db.stats.num_clauses - cls.len() + resolvents.len()) as f64) > max_clauses {
+            return Ok(false);
+        }
+
         // Delete and store old clauses:
         self.elo.var_status[&v].eliminated = true;
+        elimclauses.mark_eliminated(v);
         search.ctx.heur.set_decision_var(v, false);
+        // Values of eliminated variables come from eliminated clauses, not from search:
+        search.ctx.heur.forget_last_assigned(v);
         self.stats.eliminated_vars += 1;
 
         if pos.len() > neg.len() {
@@ -400,6 +666,7 @@ impl Simplificator {
         }
 
         for &cr in cls.iter() {
+            elimclauses.store_removed(v, search.bt.ca.literals(cr));
             self.elo.smudge_clause(&search.bt.assigns, search.bt.ca.literals(cr));
             search.bt.lazy_detach(cr);
             search.ctx.db.remove_clause(&mut search.bt.ca, cr);
@@ -425,12 +692,14 @@ impl Simplificator {
         bt: &mut BacktrackableFormula,
         db: &mut ClauseDB,
         budget: &Budget,
+        base_eliminated: u64,
         verbose: bool,
     ) -> Result<(), ()> {
         assert!(bt.is_ground_level());
 
         if verbose {
             trace!(
+                target: "minisat::simp",
                 "BWD-SUB: queue = {}, trail = {}",
                 self.subsumption_queue.len(),
                 self.subsumption_queue.assigns_left(&bt.assigns)
@@ -441,15 +710,26 @@ impl Simplificator {
         let mut deleted_literals = 0u64;
 
         let mut cnt = 0u64;
-        while let Some(job) = self.subsumption_queue.pop(&bt.ca, &bt.assigns) {
-            // Empty subsumption queue and return immediately on user-interrupt:
-            if budget.interrupted() {
+        loop {
+            let job = {
+                let settings = &self.settings;
+                self.subsumption_queue.pop(&bt.ca, &bt.assigns, |len| settings.validate_subsuming_len(len))
+            };
+            let job =
+                match job {
+                    Some(job) => job,
+                    None => { break; }
+                };
+
+            // Empty subsumption queue and return immediately on user-interrupt or when out of budget:
+            if !self.within(budget, base_eliminated) {
                 self.subsumption_queue.clear(&bt.assigns);
                 break;
             }
 
             if verbose && cnt % 1000 == 0 {
                 trace!(
+                    target: "minisat::simp",
                     "subsumption left: {:10} ({:10} subsumed, {:10} deleted literals)",
                     self.subsumption_queue.len(), subsumed, deleted_literals
                 );
@@ -465,7 +745,7 @@ impl Simplificator {
                     SubsumptionJob::Clause(sub_cr) => {
                         let c = bt.ca.view(sub_cr);
                         let mut best = c.prefix[0].var();
-                        for &lit in &c.lits()[1..] {
+                        for &lit in c.tail() {
                             // TODO: why not use n_occ?
                             if self.elo.occurs.occs_dirty(lit.var()) < self.elo.occurs.occs_dirty(best) {
                                 best = lit.var();
@@ -490,6 +770,7 @@ impl Simplificator {
                     continue;
                 }
 
+                self.stats.subsumption_checks += 1;
                 let sub =
                     match job {
                         SubsumptionJob::Clause(sub_cr) => {
@@ -525,10 +806,21 @@ impl Simplificator {
 
     fn try_garbage_collect(&mut self, search: &mut Searcher) {
         if search.bt.ca.check_garbage(self.settings.simp_garbage_frac) {
+            self.garbage_collect(search);
+        }
+    }
+
+    // Same as 'Searcher::collect_garbage', relocating occurrence lists and queues as well.
+    pub fn garbage_collect(&mut self, search: &mut Searcher) -> (usize, usize) {
+        let before = search.bt.ca.allocated_bytes();
+        {
             let mut gc = search.gc();
             self.elo.occurs.gc(&mut gc);
             self.subsumption_queue.gc(&mut gc);
         }
+        #[cfg(debug_assertions)]
+        search.check_watches();
+        (before, search.bt.ca.allocated_bytes())
     }
 
 
@@ -589,3 +881,157 @@ fn strengthen(clause: &mut Clause, p: Lit) {
         assert!(false, "Literal {:?} is not found in {:?}", p, clause);
     }
 }
+
+
+// Add 'lit' and every literal it implies through binary clauses alone to 'reached':
+fn reach_by_binaries(bt: &BacktrackableFormula, lit: Lit, reached: &mut LitMap<()>) {
+    let mut stack = vec![lit];
+    while let Some(l) = stack.pop() {
+        if reached.insert(&l, ()).is_none() {
+            stack.extend(bt.watches.binary_partners(&bt.ca, !l));
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::budget::InterruptHandle;
+
+    #[test]
+    fn test_strengthen_to_conflicting_unit() {
+        let mut search = Searcher::new(Default::default(), Default::default(), Default::default(), Default::default(), false);
+        Simplificator::on(&mut search);
+        let mut simp = Simplificator::new(Default::default());
+        let [a, b, c] = [0, 1, 2].map(|_| {
+            let v = search.new_var(None, true);
+            simp.init_var(v);
+            v
+        });
+
+        assert!(simp.add_clause(&mut search, &[a.pos_lit(), b.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[a.neg_lit(), c.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[a.neg_lit(), c.neg_lit()]).is_ok());
+        let cr = search.ctx.db.clauses()[0];
+
+        // Without 'b' the first clause is unit 'a', that implies both 'c' and '¬c':
+        let res = simp.strengthen_clause(&mut search.bt, &mut search.ctx.db, cr, b.pos_lit());
+        assert!(res.is_err());
+        assert!(search.bt.ca.is_deleted(cr));
+        assert_eq!(search.ctx.db.clauses().iter().filter(|&&x| !search.bt.ca.is_deleted(x)).count(), 2);
+
+        // Occurrences of the removed clause are gone right away, not just marked for cleanup:
+        assert_eq!(simp.elo.occurs.occs_dirty(a), 2);
+        assert_eq!(simp.elo.occurs.occs_dirty(b), 0);
+    }
+
+    #[test]
+    fn test_subsuming_clause_max_len() {
+        let checks = |subsuming_clause_max_len: i32| {
+            let mut search = Searcher::new(Default::default(), Default::default(), Default::default(), Default::default(), false);
+            Simplificator::on(&mut search);
+            let mut simp = Simplificator::new(SimplificatorSettings { subsuming_clause_max_len, ..Default::default() });
+            let vars: Vec<Var> = (0..40).map(|_| {
+                let v = search.new_var(None, true);
+                simp.init_var(v);
+                v
+            }).collect();
+
+            // Clauses over the same variables that differ in two signs, so that none subsumes or
+            // strengthens another:
+            for i in 0..20 {
+                let mut clause: Vec<Lit> = vars.iter().map(|v| v.pos_lit()).collect();
+                clause[i] = !clause[i];
+                clause[i + 20] = !clause[i + 20];
+                assert!(simp.add_clause(&mut search, &clause).is_ok());
+            }
+
+            assert!(simp.backward_subsumption_check(&mut search.bt, &mut search.ctx.db, &Budget::new(), 0, false).is_ok());
+            assert_eq!(search.ctx.db.clauses().len(), 20);
+            simp.stats.subsumption_checks
+        };
+
+        // Every clause is checked against all the others, unless it is too long to subsume:
+        assert_eq!(checks(-1), 20 * 19);
+        assert_eq!(checks(40), 20 * 19);
+        assert_eq!(checks(39), 0);
+        assert_eq!(checks(-5), 20 * 19);
+
+        let settings = SimplificatorSettings { subsuming_clause_max_len: -5, ..Default::default() };
+        assert!(!settings.validate_subsuming_len(1));
+    }
+
+    #[test]
+    fn test_probe_new_clauses_within_budget() {
+        let mut search = Searcher::new(Default::default(), Default::default(), Default::default(), Default::default(), false);
+        Simplificator::on(&mut search);
+        let settings = SimplificatorSettings { use_elim: false, use_hbr: true, ..Default::default() };
+        let mut simp = Simplificator::new(settings);
+        let [a, b, c, d] = [0, 1, 2, 3].map(|_| {
+            let v = search.new_var(None, true);
+            simp.init_var(v);
+            v
+        });
+        let mut elimclauses = ElimClauses::new(false, false, false);
+
+        // Nothing is probed once interrupted, and 'a' and 'b' are left for later:
+        let handle = InterruptHandle::new();
+        let budget = Budget::new().with_interrupt(&handle);
+        handle.interrupt();
+        assert!(simp.add_clause(&mut search, &[a.pos_lit(), b.pos_lit()]).is_ok());
+        assert!(simp.eliminate(&mut search, &budget, &mut elimclauses, |_| {}).is_ok());
+        assert_eq!(simp.stats.probed_lits, 0);
+
+        assert!(simp.add_clause(&mut search, &[a.pos_lit(), c.pos_lit()]).is_ok());
+        assert!(simp.eliminate(&mut search, &Budget::new(), &mut elimclauses, |_| {}).is_ok());
+        assert_eq!(simp.stats.probed_lits, 6);
+
+        // Only variables of clauses added since are probed again:
+        assert!(simp.add_clause(&mut search, &[c.neg_lit(), d.pos_lit()]).is_ok());
+        assert!(simp.eliminate(&mut search, &Budget::new(), &mut elimclauses, |_| {}).is_ok());
+        assert_eq!(simp.stats.probed_lits, 6 + 4);
+    }
+
+    #[test]
+    fn test_probe_learns_binaries() {
+        let mut search = Searcher::new(Default::default(), Default::default(), Default::default(), Default::default(), false);
+        Simplificator::on(&mut search);
+        let settings = SimplificatorSettings { use_elim: false, use_hbr: true, ..Default::default() };
+        let mut simp = Simplificator::new(settings);
+        let [a, b, c, d, e, f] = [0, 1, 2, 3, 4, 5].map(|_| {
+            let v = search.new_var(None, true);
+            simp.init_var(v);
+            v
+        });
+        let mut elimclauses = ElimClauses::new(false, false, false);
+
+        // 'a' forces 'c' through the ternary clause only, which gives '-a | c', learnt rather than
+        // added to the problem clauses:
+        assert!(simp.add_clause(&mut search, &[a.neg_lit(), b.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[a.neg_lit(), d.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[b.neg_lit(), d.neg_lit(), c.pos_lit()]).is_ok());
+        assert!(simp.eliminate(&mut search, &Budget::new(), &mut elimclauses, |_| {}).is_ok());
+        assert_eq!(simp.stats.hbr_binaries, 1);
+        assert_eq!(search.ctx.db.clauses().len(), 3);
+        assert_eq!(search.ctx.db.learnts().len(), 1);
+        let learnt = search.bt.ca.view(search.ctx.db.learnts()[0]);
+        assert_eq!((learnt.lits(), learnt.header.lbd()), (&[a.neg_lit(), c.pos_lit()][..], 2));
+
+        // 'e' forces 'h' through the ternary clause as well, but '-e | h' is implied by the chain
+        // 'e -> b -> g -> h' of binary clauses:
+        let [g, h] = [0, 1].map(|_| {
+            let v = search.new_var(None, true);
+            simp.init_var(v);
+            v
+        });
+        assert!(simp.add_clause(&mut search, &[e.neg_lit(), b.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[e.neg_lit(), f.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[b.neg_lit(), f.neg_lit(), h.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[b.neg_lit(), g.pos_lit()]).is_ok());
+        assert!(simp.add_clause(&mut search, &[g.neg_lit(), h.pos_lit()]).is_ok());
+        assert!(simp.eliminate(&mut search, &Budget::new(), &mut elimclauses, |_| {}).is_ok());
+        assert_eq!(search.ctx.db.learnts().len(), simp.stats.hbr_binaries as usize);
+        assert!(search.ctx.db.learnts().iter().all(|&cr| search.bt.ca.view(cr).lits() != [e.neg_lit(), h.pos_lit()]));
+    }
+}
@@ -1,4 +1,5 @@
-use std::mem;
+use core::mem;
+use alloc::vec::Vec;
 use crate::sat::formula::{util, Lit, Var, VarMap};
 
 
@@ -6,14 +7,20 @@ pub struct ElimClauses {
     extend_model: bool,
     literals: Vec<Lit>,
     sizes: Vec<usize>,
+    removed: Option<VarMap<Vec<Vec<Lit>>>>, // All clauses removed by elimination of each variable (only when elimination is reversible).
+    checked: Option<Vec<Vec<Lit>>>, // Clauses removed by irreversible elimination, only to check models against (only if asked for).
+    eliminated: VarMap<bool>, // Variables eliminated and not reintroduced since, whether elimination is reversible or not.
 }
 
 impl ElimClauses {
-    pub fn new(extend_model: bool) -> ElimClauses {
+    pub fn new(extend_model: bool, reversible: bool, keep_removed: bool) -> ElimClauses {
         ElimClauses {
             extend_model,
             literals: Vec::new(),
             sizes: Vec::new(),
+            removed: if reversible { Some(VarMap::new()) } else { None },
+            checked: if keep_removed && !reversible { Some(Vec::new()) } else { None },
+            eliminated: VarMap::new(),
         }
     }
 
@@ -40,6 +47,79 @@ impl ElimClauses {
         }
     }
 
+    pub fn mark_eliminated(&mut self, v: Var) {
+        self.eliminated.insert(&v, true);
+        if let Some(ref mut removed) = self.removed {
+            removed.insert(&v, Vec::new());
+        }
+    }
+
+    pub fn store_removed(&mut self, v: Var, clause: &[Lit]) {
+        if let Some(ref mut removed) = self.removed {
+            removed[&v].push(clause.to_vec());
+        } else if let Some(ref mut checked) = self.checked {
+            checked.push(clause.to_vec());
+        }
+    }
+
+    pub fn is_eliminated(&self, v: Var) -> bool {
+        self.eliminated.get(&v).cloned().unwrap_or(false)
+    }
+
+    // Whether 'v' is eliminated and its clauses are kept to add back (see 'reintroduce').
+    pub fn can_reintroduce(&self, v: Var) -> bool {
+        match self.removed {
+            Some(ref removed) => removed.get(&v).is_some(),
+            None => false,
+        }
+    }
+
+    // Forget everything about elimination of 'v' and return the clauses that have to be added
+    // back to the formula. Returns None if 'v' wasn't eliminated (or elimination isn't reversible).
+    pub fn reintroduce(&mut self, v: Var) -> Option<Vec<Vec<Lit>>> {
+        let clauses = self.removed.as_mut().and_then(|removed| removed.remove(&v))?;
+        self.eliminated.remove(&v);
+
+        // Drop model extension entries for 'v' (they always start with a literal of 'v'):
+        let mut literals = Vec::with_capacity(self.literals.len());
+        let mut sizes = Vec::with_capacity(self.sizes.len());
+        let mut head = 0;
+        for &tail in self.sizes.iter() {
+            let clause = &self.literals[head..tail];
+            if clause[0].var() != v {
+                literals.extend_from_slice(clause);
+                sizes.push(literals.len());
+            }
+            head = tail;
+        }
+        self.literals = literals;
+        self.sizes = sizes;
+
+        Some(clauses)
+    }
+
+    // Check that 'model' satisfies clauses removed by elimination. Unless all of them are kept,
+    // only clauses kept for model extension are checked: those of one polarity of each variable
+    // (units only pick a default value for a variable, so they are skipped).
+    pub fn satisfied_by(&self, model: &VarMap<bool>) -> bool {
+        match (&self.removed, &self.checked) {
+            (Some(removed), _) => removed.iter()
+                .all(|(_, clauses)| clauses.iter().all(|c| util::satisfied_with_model(c, model))),
+            (None, Some(checked)) => checked.iter().all(|c| util::satisfied_with_model(c, model)),
+            (None, None) => {
+                let mut head = 0;
+                for &tail in self.sizes.iter() {
+                    let clause = &self.literals[head..tail];
+                    if clause.len() > 1 && !util::satisfied_with_model(clause, model) {
+                        return false;
+                    }
+                    head = tail;
+                }
+                true
+            }
+        }
+    }
+
     pub fn extend_model(&self, model: &mut VarMap<bool>) {
         if !self.extend_model {
             return;
@@ -66,6 +146,7 @@ impl ElimClauses {
         let sz = self.literals.len() + self.sizes.len();
         if sz > 0 {
             info!(
+                target: "minisat::simp",
                 "|  Eliminated clauses:     {:10.2} Mb                                      |",
                 ((sz * mem::size_of::<u32>()) as f64) / (1024.0 * 1024.0)
             );
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use crate::sat::formula::{Lit, LitMap, Var, VarHeap, VarMap};
 use crate::sat::formula::assignment::Assignment;
 use crate::sat::formula::clause::*;
@@ -23,6 +24,11 @@ impl ElimQueue {
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.heap.reserve(n);
+        self.n_occ.reserve(2 * n);
+    }
+
     pub fn init_var(&mut self, v: Var) {
         self.n_occ.insert(&v.pos_lit(), 0);
         self.n_occ.insert(&v.neg_lit(), 0);
@@ -69,6 +75,16 @@ impl ElimQueue {
             .update(&lit.var(), move |a, b| Self::before(n_occ, a, b));
     }
 
+    // The only polarity 'v' occurs in, if it occurs at all.
+    pub fn pure_lit(&self, v: Var) -> Option<Lit> {
+        match (self.n_occ[&v.pos_lit()], self.n_occ[&v.neg_lit()]) {
+            (0, 0) => None,
+            (_, 0) => Some(v.pos_lit()),
+            (0, _) => Some(v.neg_lit()),
+            _ => None,
+        }
+    }
+
     pub fn pop(&mut self) -> Option<Var> {
         let ref n_occ = self.n_occ;
         self.heap.pop(move |a, b| Self::before(n_occ, a, b))
@@ -93,6 +109,10 @@ impl OccLists {
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.occs.reserve(n);
+    }
+
     pub fn init_var(&mut self, v: &Var) {
         self.occs.insert(
             v,
@@ -166,6 +186,12 @@ impl ElimOcc {
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.var_status.reserve(n);
+        self.occurs.reserve_vars(n);
+        self.elim.reserve_vars(n);
+    }
+
     pub fn init_var(&mut self, v: Var) {
         self.var_status.insert(&v, VarStatus { frozen: false, eliminated: false });
         self.occurs.init_var(&v);
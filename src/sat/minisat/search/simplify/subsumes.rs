@@ -1,4 +1,4 @@
-use crate::formula::{clause::*, Lit};
+use crate::sat::formula::{clause::*, Lit};
 
 
 pub enum Subsumes {
@@ -1,4 +1,4 @@
-use std::collections::vec_deque;
+use alloc::collections::vec_deque;
 use crate::sat::formula::{assignment::*, clause::*, Lit};
 
 
@@ -20,11 +20,13 @@ impl SubsumptionQueue {
         }
     }
 
-    pub fn pop(&mut self, ca: &ClauseAllocator, assigns: &Assignment) -> Option<SubsumptionJob> {
+    // Next clause or ground assignment to subsume with. Clauses of length not accepted by
+    // 'subsuming_len' are dropped from the queue.
+    pub fn pop<F: Fn(usize) -> bool>(&mut self, ca: &ClauseAllocator, assigns: &Assignment, subsuming_len: F) -> Option<SubsumptionJob> {
         let trail = assigns.trail_at(GROUND_LEVEL);
         loop {
             match self.subsumption_queue.pop_front() {
-                Some(cr) => if !ca.is_deleted(cr) {
+                Some(cr) => if !ca.is_deleted(cr) && subsuming_len(ca.view(cr).len()) {
                     return Some(SubsumptionJob::Clause(cr));
                 }
 
@@ -0,0 +1,233 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, MutexGuard};
+use alloc::vec::Vec;
+use crate::sat::formula::Lit;
+
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum TraceEvent {
+    Decision(Lit),
+    Conflict(Vec<Lit>), // Learnt clause, asserting literal first. Empty if nothing was learnt.
+    Restart,
+    GarbageCollection,
+}
+
+#[cfg(feature = "std")]
+const TAG_DECISION: u8 = 0;
+#[cfg(feature = "std")]
+const TAG_CONFLICT: u8 = 1;
+#[cfg(feature = "std")]
+const TAG_RESTART: u8 = 2;
+#[cfg(feature = "std")]
+const TAG_GC: u8 = 3;
+
+impl TraceEvent {
+    // Tag byte followed by little-endian 'u32' words: a literal code for decisions, the length and
+    // literal codes for conflicts.
+    #[cfg(feature = "std")]
+    pub fn write<W: io::Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        match *self {
+            TraceEvent::Decision(lit) => {
+                out.write_all(&[TAG_DECISION])?;
+                out.write_all(&lit.code().to_le_bytes())
+            }
+
+            TraceEvent::Conflict(ref clause) => {
+                out.write_all(&[TAG_CONFLICT])?;
+                out.write_all(&(clause.len() as u32).to_le_bytes())?;
+                for lit in clause.iter() {
+                    out.write_all(&lit.code().to_le_bytes())?;
+                }
+                Ok(())
+            }
+
+            TraceEvent::Restart => out.write_all(&[TAG_RESTART]),
+            TraceEvent::GarbageCollection => out.write_all(&[TAG_GC]),
+        }
+    }
+
+    // Returns 'None' at the end of input.
+    #[cfg(feature = "std")]
+    pub fn read<R: io::Read + ?Sized>(input: &mut R) -> io::Result<Option<TraceEvent>> {
+        let mut tag = [0u8; 1];
+        if input.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+
+        let event =
+            match tag[0] {
+                TAG_DECISION => TraceEvent::Decision(Lit::from_code(read_u32(input)?)),
+                TAG_CONFLICT => {
+                    let len = read_u32(input)? as usize;
+                    let mut clause = Vec::with_capacity(len);
+                    for _ in 0..len {
+                        clause.push(Lit::from_code(read_u32(input)?));
+                    }
+                    TraceEvent::Conflict(clause)
+                }
+                TAG_RESTART => TraceEvent::Restart,
+                TAG_GC => TraceEvent::GarbageCollection,
+                tag => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown trace event tag {}", tag)));
+                }
+            };
+        Ok(Some(event))
+    }
+}
+
+#[cfg(feature = "std")]
+fn read_u32<R: io::Read + ?Sized>(input: &mut R) -> io::Result<u32> {
+    let mut word = [0u8; 4];
+    input.read_exact(&mut word)?;
+    Ok(u32::from_le_bytes(word))
+}
+
+
+// Either writes search events out, or checks them against a previously recorded trace. Replay
+// panics at the first event that differs, as the search is expected to be deterministic. The first
+// IO error stops the trace and is kept for 'finish'.
+#[cfg(feature = "std")]
+enum TraceState {
+    Record(Box<dyn io::Write + Send>),
+    Replay(Box<dyn io::Read + Send>, u64), // Number of events matched so far.
+    Failed(io::Error),
+}
+
+#[cfg(feature = "std")]
+impl TraceState {
+    fn event(&mut self, event: TraceEvent) {
+        let res =
+            match *self {
+                TraceState::Record(ref mut out) => event.write(out.as_mut()),
+
+                TraceState::Replay(ref mut input, ref mut matched) => {
+                    TraceEvent::read(input.as_mut()).map(|expected| {
+                        assert!(expected.as_ref() == Some(&event),
+                                "Search diverged from trace at event {}: expected {:?}, got {:?}", matched, expected, event);
+                        *matched += 1;
+                    })
+                }
+
+                TraceState::Failed(_) => Ok(()),
+            };
+        if let Err(err) = res {
+            *self = TraceState::Failed(err);
+        }
+    }
+
+    fn flush(&mut self) {
+        if let TraceState::Record(ref mut out) = *self {
+            if let Err(err) = out.flush() {
+                *self = TraceState::Failed(err);
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            TraceState::Record(mut out) => out.flush(),
+
+            TraceState::Replay(mut input, matched) => {
+                match TraceEvent::read(input.as_mut())? {
+                    None => Ok(()),
+                    Some(event) => {
+                        let msg = format!("Search ended after {} events, but trace goes on with {:?}", matched, event);
+                        Err(io::Error::new(io::ErrorKind::InvalidData, msg))
+                    }
+                }
+            }
+
+            TraceState::Failed(err) => Err(err),
+        }
+    }
+}
+
+
+// Handle to a trace shared by the searcher and whoever set it up. A solve that doesn't give the
+// solver back drops the searcher, so a copy of the handle is the only way to finish the trace then.
+// Without 'std' there is nothing to write to, so no trace can be created.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct Trace(Arc<Mutex<Option<TraceState>>>);
+
+#[cfg(not(feature = "std"))]
+pub enum Trace {}
+
+#[cfg(feature = "std")]
+impl Trace {
+    pub fn record(out: Box<dyn io::Write + Send>) -> Self {
+        Trace(Arc::new(Mutex::new(Some(TraceState::Record(out)))))
+    }
+
+    pub fn replay(input: Box<dyn io::Read + Send>) -> Self {
+        Trace(Arc::new(Mutex::new(Some(TraceState::Replay(input, 0)))))
+    }
+
+    // A replay that diverged panics with the lock held, which doesn't leave the state inconsistent:
+    fn state(&self) -> MutexGuard<'_, Option<TraceState>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn event(&mut self, event: TraceEvent) {
+        if let Some(ref mut state) = *self.state() {
+            state.event(event);
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(ref mut state) = *self.state() {
+            state.flush();
+        }
+    }
+
+    // Flush a recorded trace, or check that a replayed one has no events left. Returns the first
+    // IO error of the trace, if any. Further events are ignored, and finishing again succeeds.
+    pub fn finish(&self) -> io::Result<()> {
+        let state = self.state().take();
+        state.map_or(Ok(()), TraceState::finish)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Trace {
+    pub fn event(&mut self, _: TraceEvent) {
+        match *self {}
+    }
+
+    pub fn flush(&mut self) {
+        match *self {}
+    }
+}
+
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::sat::formula::Var;
+
+    #[test]
+    fn test_roundtrip() {
+        let a = Var::from_index(0);
+        let b = Var::from_index(70000);
+        let events = vec![
+            TraceEvent::Decision(b.neg_lit()),
+            TraceEvent::Conflict(vec![a.pos_lit(), b.neg_lit()]),
+            TraceEvent::Conflict(Vec::new()),
+            TraceEvent::Restart,
+            TraceEvent::GarbageCollection,
+        ];
+
+        let mut buf = Vec::new();
+        for event in events.iter() {
+            event.write(&mut buf).unwrap();
+        }
+
+        let mut input = &buf[..];
+        for event in events.iter() {
+            assert_eq!(TraceEvent::read(&mut input).unwrap().as_ref(), Some(event));
+        }
+        assert_eq!(TraceEvent::read(&mut input).unwrap(), None);
+    }
+}
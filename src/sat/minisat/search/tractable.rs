@@ -0,0 +1,259 @@
+use alloc::vec::Vec;
+use crate::sat::formula::{Lit, LitMap, Var, VarMap};
+
+
+// Fragments of CNF that are decided in polynomial time:
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Fragment {
+    Horn,   // At most one positive literal per clause.
+    TwoSat, // At most two literals per clause.
+}
+
+
+// Fragment all 'clauses' belong to. Horn goes first if both fit, as it needs no implication graph.
+pub fn detect(clauses: &[Vec<Lit>]) -> Option<Fragment> {
+    if clauses.iter().all(|c| c.iter().filter(|lit| !lit.sign()).count() <= 1) {
+        Some(Fragment::Horn)
+    } else if clauses.iter().all(|c| c.len() <= 2) {
+        Some(Fragment::TwoSat)
+    } else {
+        None
+    }
+}
+
+// Values of variables occurring in 'clauses' that satisfy all of them, or 'None' if they are
+// unsatisfiable. 'clauses' must belong to 'fragment'.
+pub fn solve(fragment: Fragment, clauses: &[Vec<Lit>]) -> Option<VarMap<bool>> {
+    match fragment {
+        Fragment::Horn => solve_horn(clauses),
+        Fragment::TwoSat => solve_2sat(clauses),
+    }
+}
+
+
+// Propagation from the all-false assignment: a variable is set only when all negative literals of
+// a clause are falsified and its positive literal is the only way left to satisfy it. Every clause
+// is visited once per its negative literal, and the model found is the minimal one.
+fn solve_horn(clauses: &[Vec<Lit>]) -> Option<VarMap<bool>> {
+    let mut model = VarMap::new();
+    let mut occurs = VarMap::new(); // Clauses with a negative literal of the variable.
+    for c in clauses.iter() {
+        for &lit in c.iter() {
+            if model.insert(&lit.var(), false).is_none() {
+                occurs.insert(&lit.var(), Vec::new());
+            }
+        }
+    }
+
+    let mut pending = Vec::with_capacity(clauses.len()); // Negative literals not falsified yet.
+    let mut queue = Vec::new();
+    for (i, c) in clauses.iter().enumerate() {
+        let mut n = 0;
+        for &lit in c.iter().filter(|lit| lit.sign()) {
+            occurs[&lit.var()].push(i);
+            n += 1;
+        }
+        pending.push(n);
+
+        if n == 0 {
+            queue.push(head(c)?);
+        }
+    }
+
+    while let Some(v) = queue.pop() {
+        if model[&v] {
+            continue;
+        }
+        model[&v] = true;
+
+        for &i in occurs[&v].iter() {
+            pending[i] -= 1;
+            if pending[i] == 0 {
+                queue.push(head(&clauses[i])?);
+            }
+        }
+    }
+
+    Some(model)
+}
+
+fn head(clause: &[Lit]) -> Option<Var> {
+    clause.iter().find(|lit| !lit.sign()).map(|lit| lit.var())
+}
+
+
+// Implication graph has edges '!a -> b' and '!b -> a' for every clause 'a | b'. The formula is
+// unsatisfiable iff some variable shares a strongly connected component with its negation.
+// Otherwise a literal is true if its component comes after the one of its negation in topological
+// order.
+fn solve_2sat(clauses: &[Vec<Lit>]) -> Option<VarMap<bool>> {
+    let mut graph = LitMap::new();
+    let mut vars = Vec::new();
+    for c in clauses.iter() {
+        for &lit in c.iter() {
+            if graph.get(&lit.var().pos_lit()).is_none() {
+                graph.insert(&lit.var().pos_lit(), Vec::new());
+                graph.insert(&lit.var().neg_lit(), Vec::new());
+                vars.push(lit.var());
+            }
+        }
+    }
+
+    for c in clauses.iter() {
+        match c[..] {
+            [] => return None,
+            [a] => graph[&!a].push(a),
+            [a, b] => {
+                graph[&!a].push(b);
+                graph[&!b].push(a);
+            }
+            _ => panic!("Clause of {} literals in 2-SAT formula", c.len()),
+        }
+    }
+
+    let comp = components(&graph);
+    let mut model = VarMap::new();
+    for v in vars {
+        let (pos, neg) = (comp[&v.pos_lit()], comp[&v.neg_lit()]);
+        if pos == neg {
+            return None;
+        }
+        model.insert(&v, pos < neg);
+    }
+    Some(model)
+}
+
+// Tarjan's algorithm with explicit stack of calls. Components are numbered in reverse topological
+// order: edges only go from components with bigger numbers to ones with smaller numbers.
+fn components(graph: &LitMap<Vec<Lit>>) -> LitMap<usize> {
+    let mut index = LitMap::new(); // Order of discovery.
+    let mut low = LitMap::new();   // Smallest index reachable through the subtree and one more edge.
+    let mut comp = LitMap::new();
+    let mut stack = Vec::new();    // Visited literals without a component yet.
+    let mut calls = Vec::new();    // Literals being visited with the next edge to follow.
+    let mut n_visited = 0;
+    let mut n_comps = 0;
+
+    for (root, _) in graph.iter() {
+        if index.get(&root).is_some() {
+            continue;
+        }
+
+        index.insert(&root, n_visited);
+        low.insert(&root, n_visited);
+        n_visited += 1;
+        stack.push(root);
+        calls.push((root, 0));
+
+        while let Some((x, i)) = calls.pop() {
+            if let Some(&y) = graph[&x].get(i) {
+                calls.push((x, i + 1));
+                match index.get(&y) {
+                    None => {
+                        index.insert(&y, n_visited);
+                        low.insert(&y, n_visited);
+                        n_visited += 1;
+                        stack.push(y);
+                        calls.push((y, 0));
+                    }
+
+                    Some(&iy) if comp.get(&y).is_none() => {
+                        low[&x] = low[&x].min(iy);
+                    }
+
+                    Some(_) => {}
+                }
+            } else {
+                if low[&x] == index[&x] {
+                    while let Some(y) = stack.pop() {
+                        comp.insert(&y, n_comps);
+                        if y == x {
+                            break;
+                        }
+                    }
+                    n_comps += 1;
+                }
+
+                if let Some(&(parent, _)) = calls.last() {
+                    low[&parent] = low[&parent].min(low[&x]);
+                }
+            }
+        }
+    }
+
+    comp
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sat::formula::util::satisfied_with_model;
+
+    fn lits(ids: &[i32]) -> Vec<Lit> {
+        ids.iter().map(|&id| Var::from_index(id.unsigned_abs() as usize - 1).sign_lit(id < 0)).collect()
+    }
+
+    // Check 'solve' against all assignments of 'n' variables:
+    fn check(n: usize, clauses: &[Vec<Lit>]) {
+        let fragment = detect(clauses).expect("Tractable formula expected");
+        let satisfiable = (0..1u32 << n).any(|mask| {
+            let mut model = VarMap::new();
+            for i in 0..n {
+                model.insert(&Var::from_index(i), (mask >> i) & 1 == 1);
+            }
+            clauses.iter().all(|c| satisfied_with_model(c, &model))
+        });
+
+        match solve(fragment, clauses) {
+            Some(model) => assert!(clauses.iter().all(|c| satisfied_with_model(c, &model))),
+            None => assert!(!satisfiable, "{:?} formula is satisfiable: {:?}", fragment, clauses),
+        }
+    }
+
+    #[test]
+    fn test_horn() {
+        let clauses: Vec<Vec<Lit>> = [&[1][..], &[-1, 2], &[-1, -2, 3], &[-3, -4], &[-2, -5, 4]]
+            .iter().map(|c| lits(c)).collect();
+        assert_eq!(detect(&clauses), Some(Fragment::Horn));
+        check(5, &clauses);
+
+        // The minimal model leaves everything not forced false:
+        let model = solve(Fragment::Horn, &clauses).unwrap();
+        assert_eq!((model[&Var::from_index(3)], model[&Var::from_index(4)]), (false, false));
+
+        let mut unsat = clauses.clone();
+        unsat.push(lits(&[5]));
+        unsat.push(lits(&[-5, -3, -1]));
+        check(5, &unsat);
+        assert!(solve(Fragment::Horn, &unsat).is_none());
+    }
+
+    #[test]
+    fn test_2sat() {
+        // Implication chains 1 -> 2 -> 3 -> -1 force '1' false, then '4' and '5' follow:
+        let clauses: Vec<Vec<Lit>> = [&[-1, 2][..], &[-2, 3], &[-3, -1], &[1, 4], &[-4, 5], &[5, 2]]
+            .iter().map(|c| lits(c)).collect();
+        assert_eq!(detect(&clauses), Some(Fragment::TwoSat));
+        check(5, &clauses);
+
+        let mut unsat = clauses.clone();
+        unsat.push(lits(&[-5, -4]));
+        check(5, &unsat);
+        assert!(solve(Fragment::TwoSat, &unsat).is_none());
+
+        // All small 2-SAT formulas over 3 variables with 3 clauses:
+        let all: Vec<Vec<Lit>> = [1, 2, 3, -1, -2, -3].iter()
+            .flat_map(|&a| [1, 2, 3, -1, -2, -3].iter().map(move |&b| (a, b)))
+            .filter(|&(a, b): &(i32, i32)| a.abs() < b.abs())
+            .map(|(a, b)| lits(&[a, b]))
+            .collect();
+        for i in 0..all.len() {
+            for j in i + 1..all.len() {
+                for k in j + 1..all.len() {
+                    check(3, &[all[i].clone(), all[j].clone(), all[k].clone(), lits(&[-1, -3])]);
+                }
+            }
+        }
+    }
+}
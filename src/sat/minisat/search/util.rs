@@ -1,7 +1,35 @@
-use crate::sat::formula::{assignment::*, clause::*, util::*, LBool, Lit, Var};
+use crate::sat::formula::{assignment::*, clause::*, util::*, IdxVec, LBool, Lit, Var};
 use super::{backtrack::*, decision_heuristic::*};
 
 
+// Literal block distance: number of distinct decision levels among literals of 'clause'. Levels are
+// marked with a per-call stamp, so nothing is cleared or allocated between calls.
+#[derive(Clone)]
+pub struct LbdStamps {
+    stamp: u64,
+    levels: IdxVec<DecisionLevel, u64>,
+}
+
+impl LbdStamps {
+    pub fn new() -> Self {
+        LbdStamps { stamp: 0, levels: IdxVec::new() }
+    }
+
+    pub fn lbd(&mut self, assigns: &Assignment, clause: &[Lit]) -> u32 {
+        self.stamp += 1;
+        let mut n = 0;
+        for &lit in clause {
+            let level = assigns.vardata(lit).level;
+            self.levels.init(level);
+            if self.levels[level] != self.stamp {
+                self.levels[level] = self.stamp;
+                n += 1;
+            }
+        }
+        n
+    }
+}
+
 pub fn progress_estimate(assigns: &Assignment) -> f64 {
     let vars = 1.0 / (assigns.number_of_vars() as f64);
     let mut progress = 0.0;
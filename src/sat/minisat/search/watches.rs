@@ -1,4 +1,7 @@
-use std::{mem, ptr};
+use core::{mem, ptr};
+#[cfg(debug_assertions)]
+use alloc::{format, string::String};
+use alloc::vec::Vec;
 use crate::sat::formula::{assignment::Assignment, clause::*, Lit, LitVec, Var};
 
 
@@ -9,13 +12,14 @@ struct Watcher {
 }
 
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 struct WatchesLine {
     watchers: Vec<Watcher>,
     dirty: bool,
 }
 
 
+#[derive(Clone)]
 pub struct Watches {
     watches: LitVec<WatchesLine>,
     pub propagations: u64,
@@ -29,6 +33,10 @@ impl Watches {
         }
     }
 
+    pub fn reserve_vars(&mut self, n: usize) {
+        self.watches.reserve(2 * n);
+    }
+
     pub fn init_var(&mut self, v: Var) {
         self.watches.init(v.pos_lit());
         self.watches.init(v.neg_lit());
@@ -55,6 +63,16 @@ impl Watches {
         }
     }
 
+    // Other literals of binary clauses containing 'lit':
+    pub fn binary_partners<'a>(&'a self, ca: &'a ClauseAllocator, lit: Lit) -> impl Iterator<Item = Lit> + 'a {
+        self.watches[!lit].watchers.iter()
+            .filter(move |w| !ca.is_deleted(w.cref) && ca.view(w.cref).len() == 2)
+            .map(move |w| {
+                let lits = ca.view(w.cref).lits();
+                if lits[0] == lit { lits[1] } else { lits[0] }
+            })
+    }
+
     // Description:
     //   Propagates all enqueued facts. If a conflict arises, the conflicting clause is returned,
     //   otherwise CRef_Undef.
@@ -62,8 +80,15 @@ impl Watches {
     //   Post-conditions:
     //     * the propagation queue is empty, even if there was a conflict.
     pub fn propagate(&mut self, ca: &mut ClauseAllocator, assigns: &mut Assignment) -> Option<ClauseRef> {
+        // Every variable is assigned at most once, so a single call can't dequeue more literals
+        // than there are variables. Checked in debug builds only, to fail instead of looping:
+        let mut dequeued = 0;
         while let Some(p) = assigns.dequeue() {
             self.propagations += 1;
+            dequeued += 1;
+            debug_assert!(dequeued <= assigns.number_of_vars(),
+                          "Propagation dequeued {} literals with only {} variables (last {:?} at level {:?}, trail of {})",
+                          dequeued, assigns.number_of_vars(), p, assigns.current_level(), assigns.number_of_assigns());
 
             unsafe {
                 let not_p = !p;
@@ -104,15 +129,12 @@ impl Watches {
 
                     // Look for new watch:
                     {
-                        let (clause_l, clause_r) = clause.ptr_range();
-                        let mut plit = clause_l.offset(2);
-                        while plit < clause_r {
-                            if !assigns.is_assigned_neg(*plit) {
-                                self.watches[!*plit].watchers.push(cw);
-                                ptr::swap(clause_l.offset(1), plit);
-                                continue 'next_watch;
-                            }
-                            plit = plit.offset(1);
+                        let lits = clause.lits_mut();
+                        if let Some(k) = lits[2..].iter().position(|&lit| !assigns.is_assigned_neg(lit)) {
+                            let k = k + 2;
+                            self.watches[!lits[k]].watchers.push(cw);
+                            lits.swap(1, k);
+                            continue 'next_watch;
                         }
                     }
 
@@ -151,6 +173,48 @@ impl Watches {
         None
     }
 
+    // Debugging aid for states after complete propagation: every clause of 'clauses' has to be
+    // watched once by each of its first two literals and by nothing else, and these two literals
+    // can't be both false. Watches of deleted clauses may only remain in lists marked dirty.
+    #[cfg(debug_assertions)]
+    pub fn check_invariants<I: IntoIterator<Item = ClauseRef>>(
+        &self,
+        ca: &ClauseAllocator,
+        assigns: &Assignment,
+        clauses: I,
+    ) -> Result<(), String> {
+        let mut live = 0;
+        for (i, line) in self.watches.iter().enumerate() {
+            for w in line.watchers.iter() {
+                if !ca.is_deleted(w.cref) {
+                    live += 1;
+                } else if !line.dirty {
+                    return Err(format!("Watch of {:?} points to a deleted clause", !Lit::from_code(i as u32)));
+                }
+            }
+        }
+
+        let mut n_clauses = 0;
+        for cr in clauses {
+            let c = ca.view(cr);
+            for &lit in c.prefix.iter() {
+                let n = self.watches[!lit].watchers.iter().filter(|w| w.cref == cr).count();
+                if n != 1 {
+                    return Err(format!("{:?} is watched {} times by {:?}", c, n, lit));
+                }
+            }
+            if assigns.is_assigned_neg(c.prefix[0]) && assigns.is_assigned_neg(c.prefix[1]) {
+                return Err(format!("Both watched literals of {:?} are false", c));
+            }
+            n_clauses += 1;
+        }
+
+        if live != 2 * n_clauses {
+            return Err(format!("{} watches for {} clauses", live, n_clauses));
+        }
+        Ok(())
+    }
+
     pub fn gc(&mut self, gc: &mut ClauseGC) {
         for line in self.watches.iter_mut() {
             line.dirty = false;
@@ -175,3 +239,57 @@ impl Watches {
 fn ptr_diff<T>(a: *mut T, b: *mut T) -> usize {
     ((b as usize) - (a as usize)) / mem::size_of::<T>()
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::backtrack::BacktrackableFormula;
+    use crate::sat::formula::clause_header::ClauseHeader;
+
+    #[test]
+    fn test_propagate_chain() {
+        // The longest possible propagation: a chain of implications through every variable.
+        let mut bt = BacktrackableFormula::new();
+        let lits: Vec<_> = (0..100).map(|_| bt.new_var().pos_lit()).collect();
+        for pair in lits.windows(2) {
+            let (_, cr) = bt.ca.alloc(&[!pair[0], pair[1]], ClauseHeader::Clause { abstraction: None });
+            bt.attach(cr);
+        }
+
+        bt.push_decision(lits[0]);
+        assert!(bt.propagate().is_none());
+        assert!(lits.iter().all(|&lit| bt.assigns.is_assigned_pos(lit)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_check_invariants() {
+        let mut bt = BacktrackableFormula::new();
+        let lits: Vec<_> = (0..4).map(|_| bt.new_var().pos_lit()).collect();
+        let crs: Vec<_> = [[lits[0], lits[1], lits[2]], [!lits[1], lits[2], lits[3]]].iter().map(|c| {
+            let (_, cr) = bt.ca.alloc(c, ClauseHeader::Clause { abstraction: None });
+            bt.attach(cr);
+            cr
+        }).collect();
+        assert!(bt.watches.check_invariants(&bt.ca, &bt.assigns, crs.iter().cloned()).is_ok());
+
+        // A watch moved to a literal that isn't watched:
+        let mut broken = bt.clone();
+        let w = broken.watches.watches[!lits[0]].watchers.pop().unwrap();
+        broken.watches.watches[!lits[2]].watchers.push(w);
+        assert!(broken.watches.check_invariants(&broken.ca, &broken.assigns, crs.iter().cloned()).is_err());
+
+        // A clause deleted without its watches being marked dirty:
+        let mut broken = bt.clone();
+        broken.ca.free(crs[1]);
+        assert!(broken.watches.check_invariants(&broken.ca, &broken.assigns, Some(crs[0])).is_err());
+        broken.lazy_detach(crs[1]);
+        assert!(broken.watches.check_invariants(&broken.ca, &broken.assigns, Some(crs[0])).is_ok());
+
+        // Both watched literals false, as if propagation missed the clause:
+        let mut broken = bt.clone();
+        broken.push_decision(!lits[0]);
+        broken.assigns.assign_lit(!lits[1], None);
+        assert!(broken.watches.check_invariants(&broken.ca, &broken.assigns, crs.iter().cloned()).is_err());
+    }
+}
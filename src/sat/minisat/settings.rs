@@ -0,0 +1,385 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::convert::TryFrom;
+use core::fmt;
+use core::str;
+use super::{CCMinMode, CoreSettings, PhaseSaving, SimpSettings};
+
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct SettingsError {
+    pub setting: &'static str, // Name of the command line option (see 'SettingsBuilder::set').
+    pub expected: &'static str, // Range of valid values.
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid value of '{}', expected {}", self.setting, self.expected)
+    }
+}
+
+
+// Builder-style settings. Values out of range are not applied: the first of them is reported by
+// 'core' or 'simp' instead.
+#[derive(Default)]
+pub struct SettingsBuilder {
+    settings: SimpSettings,
+    error: Option<SettingsError>,
+}
+
+impl SettingsBuilder {
+    pub fn new() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    pub fn core(self) -> Result<CoreSettings, SettingsError> {
+        self.simp().map(|s| s.core)
+    }
+
+    pub fn simp(self) -> Result<SimpSettings, SettingsError> {
+        match self.error {
+            None => Ok(self.settings),
+            Some(error) => Err(error),
+        }
+    }
+
+
+    pub fn var_decay(mut self, x: f64) -> SettingsBuilder {
+        if self.check(0.0 < x && x < 1.0, "var-decay", "a value in (0, 1)") {
+            self.settings.core.heur.var_decay = x;
+        }
+        self
+    }
+
+    pub fn clause_decay(mut self, x: f64) -> SettingsBuilder {
+        if self.check(0.0 < x && x < 1.0, "cla-decay", "a value in (0, 1)") {
+            self.settings.core.db.clause_decay = x;
+        }
+        self
+    }
+
+    pub fn random_var_freq(mut self, x: f64) -> SettingsBuilder {
+        if self.check((0.0..=1.0).contains(&x), "rnd-freq", "a value in [0, 1]") {
+            self.settings.core.heur.random_var_freq = x;
+        }
+        self
+    }
+
+    pub fn random_seed(mut self, x: f64) -> SettingsBuilder {
+        if self.check(0.0 < x, "rnd-seed", "a positive value") {
+            self.settings.core.heur.random_seed = x;
+        }
+        self
+    }
+
+    pub fn ccmin(mut self, mode: CCMinMode) -> SettingsBuilder {
+        self.settings.core.ccmin_mode = mode;
+        self
+    }
+
+    pub fn phase_saving(mut self, mode: PhaseSaving) -> SettingsBuilder {
+        self.settings.core.heur.phase_saving = mode;
+        self
+    }
+
+    pub fn rnd_init(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.heur.rnd_init_act = b;
+        self
+    }
+
+    pub fn deterministic(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.heur.deterministic = b;
+        self
+    }
+
+    pub fn luby(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.search.restart.luby_restart = b;
+        self
+    }
+
+    pub fn restart_first(mut self, x: f64) -> SettingsBuilder {
+        if self.check(0.0 < x, "rfirst", "a positive value") {
+            self.settings.core.search.restart.restart_first = x;
+        }
+        self
+    }
+
+    pub fn restart_inc(mut self, x: f64) -> SettingsBuilder {
+        if self.check(1.0 < x, "rinc", "a value above 1") {
+            self.settings.core.search.restart.restart_inc = x;
+        }
+        self
+    }
+
+    pub fn garbage_frac(mut self, x: f64) -> SettingsBuilder {
+        if self.check(0.0 < x && x <= 1.0, "gc-frac", "a value in (0, 1]") {
+            self.settings.core.core.garbage_frac = x;
+        }
+        self
+    }
+
+    pub fn adaptive_gc(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.adaptive_gc = b;
+        self
+    }
+
+    pub fn core_lbd_cutoff(mut self, x: u32) -> SettingsBuilder {
+        self.settings.core.db.core_lbd_cutoff = x;
+        self
+    }
+
+    pub fn protect_first_learnts(mut self, x: usize) -> SettingsBuilder {
+        self.settings.core.db.protect_first_learnts = x;
+        self
+    }
+
+    pub fn min_learnts(mut self, x: i32) -> SettingsBuilder {
+        if self.check(0 <= x, "min-learnts", "a non-negative value") {
+            self.settings.core.search.learn.min_learnts_lim = x;
+        }
+        self
+    }
+
+    pub fn rcheck(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.use_rcheck = b;
+        self
+    }
+
+    pub fn trail_saving(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.trail_saving = b;
+        self
+    }
+
+    pub fn simplify_on_add(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.simplify_on_add = b;
+        self
+    }
+
+    pub fn shuffle_literals(mut self, seed: Option<u64>) -> SettingsBuilder {
+        self.settings.core.core.shuffle_literals = seed;
+        self
+    }
+
+    pub fn unlock_before_reduce(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.unlock_before_reduce = b;
+        self
+    }
+
+    pub fn quiet(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.quiet = b;
+        self
+    }
+
+    pub fn detect_tractable(mut self, b: bool) -> SettingsBuilder {
+        self.settings.core.core.detect_tractable = b;
+        self
+    }
+
+
+    // Only 'simp' settings from here on:
+    pub fn use_asymm(mut self, b: bool) -> SettingsBuilder {
+        self.settings.simp.use_asymm = b;
+        self
+    }
+
+    pub fn use_hbr(mut self, b: bool) -> SettingsBuilder {
+        self.settings.simp.use_hbr = b;
+        self
+    }
+
+    pub fn use_elim(mut self, b: bool) -> SettingsBuilder {
+        self.settings.simp.use_elim = b;
+        self
+    }
+
+    pub fn grow(mut self, x: usize) -> SettingsBuilder {
+        self.settings.simp.grow = x;
+        self
+    }
+
+    pub fn clause_lim(mut self, x: i32) -> SettingsBuilder {
+        if self.check(-1 <= x, "cl-lim", "-1 or a non-negative value") {
+            self.settings.simp.clause_lim = x;
+        }
+        self
+    }
+
+    pub fn subsumption_lim(mut self, x: i32) -> SettingsBuilder {
+        if self.check(-1 <= x, "sub-lim", "-1 or a non-negative value") {
+            self.settings.simp.subsumption_lim = x;
+        }
+        self
+    }
+
+    pub fn subsuming_clause_max_len(mut self, x: i32) -> SettingsBuilder {
+        if self.check(-1 <= x, "subsuming-lim", "-1 or a non-negative value") {
+            self.settings.simp.subsuming_clause_max_len = x;
+        }
+        self
+    }
+
+    pub fn max_preprocess_rounds(mut self, x: usize) -> SettingsBuilder {
+        if self.check(0 < x, "preprocess-rounds", "a positive value") {
+            self.settings.simp.max_preprocess_rounds = x;
+        }
+        self
+    }
+
+    pub fn simp_garbage_frac(mut self, x: f64) -> SettingsBuilder {
+        if self.check(0.0 < x && x <= 1.0, "simp-gc-frac", "a value in (0, 1]") {
+            self.settings.simp.simp_garbage_frac = x;
+        }
+        self
+    }
+
+
+    fn check(&mut self, valid: bool, setting: &'static str, expected: &'static str) -> bool {
+        if !valid && self.error.is_none() {
+            self.error = Some(SettingsError { setting, expected });
+        }
+        valid
+    }
+
+
+    // Apply a core setting named as its command line option (without dashes) to a textual value:
+    // a number, 'true' or 'false' for flags, a number or a name for modes. Values that do not parse
+    // are reported here, values out of range are reported by 'core' as usual.
+    pub fn set(self, name: &str, value: &str) -> Result<SettingsBuilder, String> {
+        Ok(match name {
+            "var-decay" => self.var_decay(parse(name, value)?),
+            "cla-decay" => self.clause_decay(parse(name, value)?),
+            "rnd-freq" => self.random_var_freq(parse(name, value)?),
+            "rnd-seed" => self.random_seed(parse(name, value)?),
+            "rnd-init" => self.rnd_init(parse(name, value)?),
+            "deterministic" => self.deterministic(parse(name, value)?),
+            "ccmin-mode" | "ccmin" => self.ccmin(match value {
+                "0" | "none" => CCMinMode::None,
+                "1" | "basic" => CCMinMode::Basic,
+                "2" | "deep" => CCMinMode::Deep,
+                "3" | "adaptive" => CCMinMode::Adaptive,
+                _ => return Err(invalid(name, value)),
+            }),
+            "phase-saving" => self.phase_saving(match value {
+                "0" | "none" => PhaseSaving::None,
+                "1" | "limited" => PhaseSaving::Limited,
+                "2" | "full" => PhaseSaving::Full,
+                _ => return Err(invalid(name, value)),
+            }),
+            "luby" => self.luby(parse(name, value)?),
+            "rfirst" => self.restart_first(parse(name, value)?),
+            "rinc" => self.restart_inc(parse(name, value)?),
+            "gc-frac" => self.garbage_frac(parse(name, value)?),
+            "adaptive-gc" => self.adaptive_gc(parse(name, value)?),
+            "lbd-cutoff" => self.core_lbd_cutoff(parse(name, value)?),
+            "protect-learnts" => self.protect_first_learnts(parse(name, value)?),
+            "min-learnts" => self.min_learnts(parse(name, value)?),
+            "rcheck" => self.rcheck(parse(name, value)?),
+            "trail-saving" => self.trail_saving(parse(name, value)?),
+            "unlock-before-reduce" => self.unlock_before_reduce(parse(name, value)?),
+            "simplify-on-add" => self.simplify_on_add(parse(name, value)?),
+            "shuffle-lits" => self.shuffle_literals(Some(parse(name, value)?)),
+            "detect-tractable" => self.detect_tractable(parse(name, value)?),
+            _ => return Err(format!("Unknown setting '{}'", name)),
+        })
+    }
+}
+
+fn parse<T: str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| invalid(name, value))
+}
+
+fn invalid(name: &str, value: &str) -> String {
+    format!("Invalid value '{}' of '{}'", value, name)
+}
+
+
+// Core settings from a comma-separated list of 'name=value' pairs with names of command line options,
+// e.g. "var-decay=0.9,luby=true,ccmin=deep" (see 'SettingsBuilder::set'). Empty items are skipped.
+pub fn parse_config(config: &str) -> Result<CoreSettings, String> {
+    let mut b = SettingsBuilder::new();
+    for item in config.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        match item.split_once('=') {
+            Some((name, value)) => {
+                b = b.set(name.trim(), value.trim())?;
+            }
+            None => {
+                return Err(format!("Expected 'name=value', found '{}'", item));
+            }
+        }
+    }
+    b.core().map_err(|err| err.to_string())
+}
+
+impl TryFrom<&str> for CoreSettings {
+    type Error = String;
+
+    fn try_from(config: &str) -> Result<CoreSettings, String> {
+        parse_config(config)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let s = SettingsBuilder::new()
+            .var_decay(0.8)
+            .luby(false)
+            .ccmin(CCMinMode::Basic)
+            .restart_inc(1.5)
+            .use_elim(false)
+            .clause_lim(-1)
+            .simp()
+            .unwrap();
+        assert_eq!(s.core.heur.var_decay, 0.8);
+        assert!(!s.core.search.restart.luby_restart);
+        assert_eq!(s.core.ccmin_mode, CCMinMode::Basic);
+        assert_eq!(s.core.search.restart.restart_inc, 1.5);
+        assert!(!s.simp.use_elim);
+        assert_eq!(s.simp.clause_lim, -1);
+
+        // Everything not set keeps its default:
+        assert_eq!(s.core.db.clause_decay, CoreSettings::default().db.clause_decay);
+        assert!(s.simp.use_pure_literals == SimpSettings::default().simp.use_pure_literals);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let res = SettingsBuilder::new().var_decay(1.5).clause_decay(0.0).luby(false).core();
+        assert_eq!(res.err(), Some(SettingsError { setting: "var-decay", expected: "a value in (0, 1)" }));
+
+        assert!(SettingsBuilder::new().restart_inc(1.0).core().is_err());
+        assert!(SettingsBuilder::new().clause_lim(-2).simp().is_err());
+        assert!(SettingsBuilder::new().max_preprocess_rounds(0).simp().is_err());
+        assert!(SettingsBuilder::new().var_decay(0.5).core().is_ok());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let s = parse_config("var-decay=0.9, luby=false,ccmin=deep,phase-saving=1,rnd-seed=7,").unwrap();
+        assert_eq!(s.heur.var_decay, 0.9);
+        assert!(!s.search.restart.luby_restart);
+        assert_eq!(s.ccmin_mode, CCMinMode::Deep);
+        assert!(s.heur.phase_saving == PhaseSaving::Limited);
+        assert_eq!(s.heur.random_seed, 7.0);
+        assert_eq!(s.db.clause_decay, CoreSettings::default().db.clause_decay);
+
+        let s = CoreSettings::try_from("ccmin-mode=0,rnd-init=true").unwrap();
+        assert_eq!(s.ccmin_mode, CCMinMode::None);
+        assert!(s.heur.rnd_init_act);
+        assert!(parse_config("").is_ok());
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        assert_eq!(parse_config("var-decay=0.9,bogus=1").err().unwrap(), "Unknown setting 'bogus'");
+        assert_eq!(parse_config("luby=yes").err().unwrap(), "Invalid value 'yes' of 'luby'");
+        assert_eq!(parse_config("ccmin=shallow").err().unwrap(), "Invalid value 'shallow' of 'ccmin'");
+        assert_eq!(parse_config("luby").err().unwrap(), "Expected 'name=value', found 'luby'");
+        assert_eq!(parse_config("var-decay=1.5").err().unwrap(),
+                   "Invalid value of 'var-decay', expected a value in (0, 1)");
+    }
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "std")]
+
+use minisat_rust::sat::{dimacs, minisat, Solver};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+
+// Counts reallocations, i.e. buffers grown one step at a time. This file has its own allocator,
+// and a single test, so that nothing else runs while it counts.
+struct CountingAlloc;
+
+static REALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+fn reallocs_of<F: FnOnce()>(f: F) -> usize {
+    let before = REALLOCS.load(Ordering::Relaxed);
+    f();
+    REALLOCS.load(Ordering::Relaxed) - before
+}
+
+
+#[test]
+fn reserved_vars_are_not_reallocated() {
+    const N: usize = 1_000_000;
+    let new_vars = |reserve: bool| {
+        reallocs_of(|| {
+            let mut solver = minisat::SimpSolver::new(Default::default());
+            if reserve {
+                solver.reserve_vars(N);
+            }
+            for _ in 0..N {
+                solver.new_var(None, true);
+            }
+            assert_eq!(solver.n_vars(), N);
+        })
+    };
+
+    let grown = new_vars(false);
+    let reserved = new_vars(true);
+    assert!(grown >= 100, "{} reallocations", grown);
+    assert_eq!(reserved, 0);
+
+    // 'parse' reserves variables declared in the header:
+    let cnf = format!("p cnf {} 1\n1 -{} 0\n", N, N);
+    let parsed = reallocs_of(|| {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse(cnf.as_bytes(), &mut solver, true).expect("IO Error");
+        assert_eq!(solver.n_vars(), N);
+    });
+    assert!(parsed * 4 < grown, "{} reallocations while parsing, {} without reserving", parsed, grown);
+}
@@ -0,0 +1,223 @@
+#![cfg(feature = "std")]
+
+use std::{fs, path};
+use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver};
+use minisat_rust::sat::formula::{Lit, Var};
+use minisat_rust::sat::minisat::budget::Budget;
+
+
+// Small deterministic xorshift generator, so that failures are reproducible:
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % (n as u64)) as usize
+    }
+}
+
+// Random 3-SAT formula with 'n_vars' variables around the satisfiability threshold:
+fn random_3sat(rng: &mut Rng, n_vars: usize) -> Vec<Vec<Lit>> {
+    let n_clauses = n_vars * 4 + rng.below(n_vars + 1);
+    (0..n_clauses)
+        .map(|_| {
+            (0..3)
+                .map(|_| Var::from_index(rng.below(n_vars)).sign_lit(rng.next() & 1 == 1))
+                .collect()
+        })
+        .collect()
+}
+
+// Random formula with clauses of length 1..=4, so units and binaries are exercised too:
+fn random_mixed(rng: &mut Rng, n_vars: usize) -> Vec<Vec<Lit>> {
+    let n_clauses = n_vars * 2 + rng.below(n_vars * 2 + 1);
+    (0..n_clauses)
+        .map(|_| {
+            let len = if rng.below(10) == 0 { 1 } else { 2 + rng.below(3) };
+            (0..len)
+                .map(|_| Var::from_index(rng.below(n_vars)).sign_lit(rng.next() & 1 == 1))
+                .collect()
+        })
+        .collect()
+}
+
+fn satisfies(formula: &[Vec<Lit>], model: &[Lit]) -> bool {
+    formula.iter().all(|clause| clause.iter().any(|lit| model.contains(lit)))
+}
+
+// Exhaustive reference checker: bit 'i' of an assignment is the value of variable 'i'.
+fn brute_force(n_vars: usize, formula: &[Vec<Lit>]) -> bool {
+    assert!(n_vars <= 20);
+    (0..1u32 << n_vars).any(|mask| {
+        let model: Vec<Lit> = (0..n_vars)
+            .map(|i| Var::from_index(i).sign_lit((mask >> i) & 1 == 0))
+            .collect();
+        satisfies(formula, &model)
+    })
+}
+
+fn solve<S: Solver>(solver: S) -> Option<Vec<Lit>> {
+    match solver.solve_limited(&Budget::new(), &[]) {
+        SolveRes::SAT(model, _) => Some(model),
+        SolveRes::UnSAT(_) => None,
+        SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+    }
+}
+
+fn is_sat<S: Solver>(solver: S) -> bool {
+    solve(solver).is_some()
+}
+
+
+#[test]
+fn core_and_simp_agree() {
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+    for _ in 0..300 {
+        let n_vars = 3 + rng.below(10);
+        let formula = random_3sat(&mut rng, n_vars);
+        let clauses: Vec<&[Lit]> = formula.iter().map(|c| &c[..]).collect();
+
+        let core = minisat::CoreSolver::from_clauses(Default::default(), n_vars, &clauses);
+        let simp = minisat::SimpSolver::from_clauses(Default::default(), n_vars, &clauses);
+        assert_eq!(is_sat(core), is_sat(simp), "{:?}", formula);
+    }
+}
+
+
+#[test]
+fn agree_with_brute_force() {
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+    for case in 0..400 {
+        let n_vars = 1 + rng.below(12);
+        let formula = if case % 2 == 0 { random_3sat(&mut rng, n_vars) } else { random_mixed(&mut rng, n_vars) };
+        let clauses: Vec<&[Lit]> = formula.iter().map(|c| &c[..]).collect();
+        let expected = brute_force(n_vars, &formula);
+
+        let core = minisat::CoreSolver::from_clauses(Default::default(), n_vars, &clauses);
+        let mut simp = minisat::SimpSolver::from_clauses(Default::default(), n_vars, &clauses);
+        simp.preprocess(&Budget::new());
+        let mut dpll_settings = minisat::CoreSettings::default();
+        dpll_settings.core.learning = false;
+        let dpll = minisat::CoreSolver::from_clauses(dpll_settings, n_vars, &clauses);
+        let mut pure_settings = minisat::SimpSettings::default();
+        pure_settings.simp.use_elim = false;
+        pure_settings.simp.use_pure_literals = true;
+        let mut pure = minisat::SimpSolver::from_clauses(pure_settings, n_vars, &clauses);
+        pure.preprocess(&Budget::new());
+
+        for model in [solve(core), solve(simp), solve(dpll), solve(pure)].iter() {
+            assert_eq!(model.is_some(), expected, "{:?}", formula);
+            if let Some(ref model) = model {
+                assert!(satisfies(&formula, model), "{:?} => {:?}", formula, model);
+            }
+        }
+    }
+}
+
+
+// Solve the instance at 'path' with both solvers. Models are checked by a fresh solver holding the
+// whole formula, so clauses removed by elimination are checked as well. Returns satisfiability and
+// the number of variables eliminated by preprocessing.
+fn solve_both(path: &path::Path) -> (bool, u64) {
+    let load = || {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        solver
+    };
+    let mut simp = minisat::SimpSolver::new(Default::default());
+    dimacs::parse_file(path, &mut simp, false).expect("IO Error");
+
+    let mut eliminated = 0;
+    let simp_ok = simp.preprocess_with_progress(&Budget::new(), |p| eliminated = p.eliminated_vars);
+    let simp_model = if simp_ok { solve(simp) } else { None };
+    let core_model = solve(load());
+
+    assert_eq!(core_model.is_some(), simp_model.is_some(), "{}", path.display());
+    let checker = load();
+    for model in [&core_model, &simp_model].iter() {
+        if let Some(model) = model {
+            assert!(checker.verify_model(model), "{}", path.display());
+        }
+    }
+    (core_model.is_some(), eliminated)
+}
+
+// Check instances of './tests/cnf' selected by their file names. Returns the numbers of SAT and
+// UNSAT ones, and the most variables eliminated from a single instance.
+fn check_bundled<F: Fn(usize, &str) -> bool>(select: F) -> (usize, usize, u64) {
+    let mut paths: Vec<path::PathBuf> = fs::read_dir("./tests/cnf").expect("IO Error")
+        .map(|entry| entry.expect("IO Error").path())
+        .collect();
+    paths.sort();
+
+    let (mut sat, mut unsat, mut max_eliminated) = (0, 0, 0);
+    for (i, path) in paths.iter().enumerate() {
+        if select(i, &path.file_name().unwrap().to_string_lossy()) {
+            let (is_sat, eliminated) = solve_both(path);
+            if is_sat { sat += 1; } else { unsat += 1; }
+            max_eliminated = max_eliminated.max(eliminated);
+        }
+    }
+    (sat, unsat, max_eliminated)
+}
+
+#[test]
+fn bundled_instances_agree() {
+    // All structured instances, but only a sample of random ones ('uf*' and 'uuf*'), as there are
+    // thousands of them. Circuits ('3bitadd_31' above all) have most of their variables eliminated:
+    let (sat, unsat, max_eliminated) = check_bundled(|i, name| {
+        let random = name.starts_with("uf") || name.starts_with("uuf");
+        !random || (i % 10 == 0 && !name.starts_with("uf250"))
+    });
+    assert!(sat > 0 && unsat > 0);
+    assert!(max_eliminated > 1000, "{} variables eliminated at most", max_eliminated);
+}
+
+#[test]
+#[ignore]
+fn all_bundled_instances_agree() {
+    check_bundled(|_, _| true);
+}
+
+
+#[test]
+fn soft_optimum_matches_brute_force() {
+    let mut rng = Rng(0xD1B54A32D192ED03);
+    for _ in 0..200 {
+        let n_vars = 2 + rng.below(8);
+        let formula = random_mixed(&mut rng, n_vars);
+        let clauses: Vec<&[Lit]> = formula.iter().map(|c| &c[..]).collect();
+        let soft: Vec<(Lit, u64)> = (0..1 + rng.below(2 * n_vars))
+            .map(|_| (Var::from_index(rng.below(n_vars)).sign_lit(rng.next() & 1 == 1), 1 + rng.below(5) as u64))
+            .collect();
+
+        let best = (0..1u32 << n_vars)
+            .filter_map(|mask| {
+                let model: Vec<Lit> = (0..n_vars)
+                    .map(|i| Var::from_index(i).sign_lit((mask >> i) & 1 == 0))
+                    .collect();
+                if satisfies(&formula, &model) {
+                    Some(soft.iter().filter(|&(lit, _)| model.contains(lit)).map(|&(_, w)| w).sum::<u64>())
+                } else {
+                    None
+                }
+            })
+            .max();
+
+        let solver = minisat::CoreSolver::from_clauses(Default::default(), n_vars, &clauses);
+        match solver.solve_limited_soft(&Budget::new(), &[], &soft) {
+            minisat::SoftRes::SAT(model, weight, _) => {
+                assert_eq!(Some(weight), best, "{:?} {:?}", formula, soft);
+                assert!(satisfies(&formula, &model), "{:?} => {:?}", formula, model);
+            }
+            minisat::SoftRes::UnSAT(_) => assert_eq!(best, None, "{:?}", formula),
+            minisat::SoftRes::Interrupted(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
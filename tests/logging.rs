@@ -0,0 +1,90 @@
+#![cfg(feature = "std")]
+
+use log::{Level, Log, Metadata, Record};
+use minisat_rust::sat::{dimacs, minisat, Solver};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::sync::{Mutex, Once};
+use std::thread::{self, ThreadId};
+
+
+// Keeps targets and messages of all records, along with the thread that logged them, as tests of
+// this file share the logger while running in parallel:
+struct CapturingLogger {
+    records: Mutex<Vec<(ThreadId, String, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let entry = (thread::current().id(), record.target().to_string(), record.args().to_string());
+        self.records.lock().unwrap().push(entry);
+    }
+
+    fn flush(&self) {}
+}
+
+impl CapturingLogger {
+    // Targets and messages of records logged by the current thread:
+    fn records_of_current_thread(&self) -> Vec<(String, String)> {
+        let id = thread::current().id();
+        self.records.lock().unwrap().iter()
+            .filter(|&&(t, _, _)| t == id)
+            .map(|(_, target, msg)| (target.clone(), msg.clone()))
+            .collect()
+    }
+}
+
+fn logger() -> &'static CapturingLogger {
+    static INIT: Once = Once::new();
+    static LOGGER: CapturingLogger = CapturingLogger {
+        records: Mutex::new(Vec::new()),
+    };
+
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(Level::Trace.to_level_filter());
+    });
+    &LOGGER
+}
+
+
+#[test]
+fn simplification_and_search_targets() {
+    let logger = logger();
+
+    let mut solver = minisat::SimpSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    solver.preprocess(&Budget::new());
+    solver.solve_limited(&Budget::new(), &[]);
+
+    let records = logger.records_of_current_thread();
+    let targets_of = |prefixes: &[&str]| -> Vec<String> {
+        records.iter()
+            .filter(|(_, msg)| prefixes.iter().any(|p| msg.starts_with(p)))
+            .map(|(target, _)| target.clone())
+            .collect()
+    };
+
+    let simp = targets_of(&["ROUND", "ELIM", "BWD-SUB"]);
+    assert!(!simp.is_empty());
+    assert!(simp.iter().all(|t| t == "minisat::simp"), "{:?}", simp);
+
+    let search = targets_of(&["============================[ Search Statistics ]", "| Conflicts |"]);
+    assert_eq!(search.len(), 2);
+    assert!(search.iter().all(|t| t == "minisat::search"), "{:?}", search);
+}
+
+#[test]
+fn quiet_search_logs_no_table() {
+    let logger = logger();
+
+    let mut settings = minisat::CoreSettings::default();
+    settings.core.quiet = true;
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    solver.solve_limited(&Budget::new(), &[]);
+    assert!(logger.records_of_current_thread().iter().all(|(target, _)| target != "minisat::search"));
+}
@@ -1,8 +1,9 @@
+#![cfg(feature = "std")]
 
 use tempfile;
-use time;
 
 use std::{fs, io, path, process};
+use std::time::Instant;
 use std::io::{Read, Seek};
 use minisat_rust::sat::{dimacs, minisat, SolveRes, Solver, Stats};
 use minisat_rust::sat::minisat::budget::Budget;
@@ -70,12 +71,12 @@ fn walk(dir_path: &str, bins: usize, bin: usize) -> io::Result<()> {
 fn test_file(path: &path::Path) -> io::Result<bool> {
     let (minisat_result, stdout, minisat_time) = {
         let out_file = tempfile::NamedTempFile::new()?;
-        let start_time = time::precise_time_s();
+        let start_time = Instant::now();
         let out = process::Command::new("minisat")
             .arg(path)
             .arg(out_file.path())
             .output()?;
-        let end_time = time::precise_time_s();
+        let minisat_time = start_time.elapsed().as_secs_f64();
         assert!(
             out.status.code() == Some(10) || out.status.code() == Some(20),
             "minisat error code on {}",
@@ -99,10 +100,10 @@ fn test_file(path: &path::Path) -> io::Result<bool> {
         let len = stdout.len();
         assert!(len > 10);
 
-        (output, stdout, end_time - start_time)
+        (output, stdout, minisat_time)
     };
 
-    let start_time = time::precise_time_s();
+    let start_time = Instant::now();
     let mut solver = minisat::SimpSolver::new(Default::default());
 
     let backward_subst = match dimacs::parse_file(path, &mut solver, false) {
@@ -120,7 +121,7 @@ fn test_file(path: &path::Path) -> io::Result<bool> {
         }
     };
 
-    let my_time = time::precise_time_s() - start_time;
+    let my_time = start_time.elapsed().as_secs_f64();
 
     let outcome = match res {
         SolveRes::SAT(_, ref stats) => {
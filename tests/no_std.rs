@@ -0,0 +1,26 @@
+// The solver is expected to build without the 'std' feature, on top of 'core' and 'alloc' only.
+// These run the same builds a CI job would, in a target directory of their own.
+use std::process::Command;
+
+
+fn cargo_without_std(args: &[&str]) {
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .arg("--no-default-features")
+        .arg("--target-dir")
+        .arg(concat!(env!("CARGO_TARGET_TMPDIR"), "/no_std"))
+        .status()
+        .expect("Failed to run cargo");
+    assert!(status.success(), "cargo {:?} --no-default-features failed", args);
+}
+
+#[test]
+fn build_without_std() {
+    cargo_without_std(&["build", "--lib"]);
+}
+
+#[test]
+fn solve_without_std() {
+    cargo_without_std(&["test", "--lib", "test_pigeonhole"]);
+}
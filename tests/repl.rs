@@ -0,0 +1,57 @@
+#![cfg(feature = "std")]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+
+fn run_repl(commands: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minisat-rust"))
+        .args(&["--repl", "--verb", "0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start minisat-rust");
+    child.stdin.take().unwrap().write_all(commands.as_bytes()).expect("IO Error");
+
+    let output = child.wait_with_output().expect("IO Error");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap().lines().map(|line| line.to_string()).collect()
+}
+
+
+#[test]
+fn repl_answers_incremental_queries() {
+    let commands = "\
+c exactly one of 1, 2, 3
+declare 3
+clause 1 2 3 0
+clause -1 -2 0
+clause -1 -3 0
+clause -2 -3 0
+
+assume 1
+check
+model
+assume -1
+assume -2
+check
+clause -3 0
+assume -1
+assume -2
+check
+model
+check
+reset
+declare 1
+clause 2 0
+check
+";
+
+    assert_eq!(run_repl(commands), vec![
+        "ok", "ok", "ok", "ok", "ok",
+        "ok", "sat", "1 -2 -3 0",
+        "ok", "ok", "sat",
+        "ok", "ok", "ok", "unsat", "error: no model, the latest 'check' was not 'sat'", "sat",
+        "ok", "ok", "error: undeclared variable 2", "sat",
+    ]);
+}
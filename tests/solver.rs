@@ -0,0 +1,2136 @@
+#![cfg(feature = "std")]
+
+use minisat_rust::sat::{dimacs, minisat, AssumptionError, ClauseSource, SolveRes, Solver};
+use minisat_rust::sat::formula::{assignment::CheckpointError, LBool, Lit, Var};
+use minisat_rust::sat::minisat::budget::Budget;
+use std::time::{Duration, Instant};
+
+
+#[test]
+fn simplify_removes_satisfied_clauses() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let a = solver.new_var(None, true);
+    let b = solver.new_var(None, true);
+    let c = solver.new_var(None, true);
+
+    assert!(solver.add_clause(&[a.pos_lit(), b.pos_lit()]));
+    assert!(solver.add_clause(&[a.pos_lit(), c.neg_lit()]));
+    assert!(solver.add_clause(&[b.neg_lit(), c.pos_lit()]));
+    assert_eq!(solver.n_clauses(), 3);
+
+    assert!(solver.add_clause(&[a.pos_lit()]));
+    assert!(solver.simplify());
+    assert_eq!(solver.n_clauses(), 1);
+
+    assert!(!solver.add_clause(&[a.neg_lit()]));
+    assert!(!solver.simplify());
+}
+
+
+#[test]
+fn reintroduce_eliminated_vars() {
+    for &(extra_unsat, expected_sat) in [(false, true), (true, false)].iter() {
+        let mut solver = minisat::SimpSolver::new(minisat::SimpSettings { reversible_elim: true, ..Default::default() });
+        let a = solver.new_var(None, true);
+        let b = solver.new_var(None, true);
+        let c = solver.new_var(None, true);
+
+        assert!(solver.add_clause(&[a.pos_lit(), b.pos_lit()]));
+        assert!(solver.add_clause(&[a.neg_lit(), c.pos_lit()]));
+        assert!(solver.preprocess(&Budget::new()));
+        assert_eq!(solver.n_clauses(), 0);
+
+        assert!(solver.add_clause(&[b.neg_lit()]));
+        if extra_unsat {
+            assert!(!solver.add_clause(&[c.neg_lit()]));
+        }
+
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, _) => {
+                assert!(expected_sat);
+                assert!(model.contains(&a.pos_lit()));
+                assert!(model.contains(&b.neg_lit()));
+                assert!(model.contains(&c.pos_lit()));
+            }
+            SolveRes::UnSAT(_) => assert!(!expected_sat),
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
+
+
+#[test]
+fn per_call_conflict_budget() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(10);
+    budget.set_per_call(true);
+
+    let mut prev_conflicts = 0;
+    for _ in 0..3 {
+        solver = match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, s) => s,
+            _ => panic!("Expected interrupted search"),
+        };
+        let conflicts = solver.stats().conflicts;
+        assert!(conflicts > prev_conflicts && conflicts - prev_conflicts <= 10);
+        prev_conflicts = conflicts;
+    }
+}
+
+
+#[test]
+fn verify_model_in_memory() {
+    let load = || {
+        let mut solver = minisat::SimpSolver::new(Default::default());
+        dimacs::parse_file("./tests/cnf/uf50-0100.cnf.gz", &mut solver, false).expect("IO Error");
+        assert!(solver.preprocess(&Budget::new()));
+        solver
+    };
+
+    let model = match load().solve_limited(&Budget::new(), &[]) {
+        SolveRes::SAT(model, _) => model,
+        _ => panic!("Expected SAT"),
+    };
+
+    let solver = load();
+    assert!(solver.verify_model(&model));
+
+    let corrupted: Vec<_> = model.iter().map(|&lit| !lit).collect();
+    assert!(!solver.verify_model(&corrupted));
+
+    // 'x' is eliminated, and only clauses of one of its polarities are kept to extend models. A
+    // model violating either removed clause is still rejected if they are all kept:
+    let mut solver = minisat::SimpSolver::new(minisat::SimpSettings { keep_removed: true, ..Default::default() });
+    let [x, a, b] = [(); 3].map(|_| solver.new_var(None, true).pos_lit());
+    assert!(solver.add_clause(&[x, a]));
+    assert!(solver.add_clause(&[!x, b]));
+    assert!(solver.preprocess(&Budget::new()));
+    assert!(solver.is_eliminated(x.var()));
+    assert!(solver.verify_model(&[!x, a, b]));
+    assert!(!solver.verify_model(&[!x, !a, b]));
+    assert!(!solver.verify_model(&[x, a, !b]));
+}
+
+
+#[test]
+fn minimize_with_binary_clauses() {
+    let run = |minimize_with_binary: bool| {
+        let mut solver = minisat::CoreSolver::new(minisat::CoreSettings { minimize_with_binary, ..Default::default() });
+        let x = solver.new_var(None, true);
+        let y = solver.new_var(None, true);
+        let a = solver.new_var(None, true);
+        let c = solver.new_var(None, true);
+
+        // Under assumptions ~x, ~y, ~a the conflict on 'c' gives '(a | x | y)': 'x' is a decision,
+        // so only resolution with '(a | ~x)' removes it.
+        assert!(solver.add_clause(&[a.pos_lit(), x.neg_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), x.pos_lit(), y.pos_lit(), c.pos_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), x.pos_lit(), y.pos_lit(), c.neg_lit()]));
+
+        match solver.solve_limited(&Budget::new(), &[x.neg_lit(), y.neg_lit(), a.neg_lit()]) {
+            SolveRes::UnSAT(stats) => stats,
+            _ => panic!("Expected UnSAT under assumptions"),
+        }
+    };
+
+    let plain = run(false);
+    let minimized = run(true);
+    assert_eq!(plain.conflicts, 1);
+    assert_eq!(minimized.conflicts, 1);
+    assert_eq!(plain.tot_literals, 3);
+    assert_eq!(minimized.tot_literals, 2);
+}
+
+
+#[test]
+fn var_activity_picks_first_decision() {
+    for &boost_x in [false, true].iter() {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let x = solver.new_var(None, true);
+        let y = solver.new_var(None, true);
+        assert!(solver.add_clause(&[x.pos_lit(), y.pos_lit()]));
+
+        // Decisions are negative by default, so the first decision propagates the other variable:
+        solver.set_var_activity(if boost_x { x } else { y }, 1.0);
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, _) => {
+                assert_eq!(model.contains(&x.neg_lit()), boost_x);
+                assert_eq!(model.contains(&y.neg_lit()), !boost_x);
+            }
+            _ => panic!("Expected SAT"),
+        }
+    }
+}
+
+
+#[test]
+fn solve_with_timeout_returns_partial_assignment() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf250-014.cnf.gz", &mut solver, false).expect("IO Error");
+
+    match solver.solve_with_timeout(Duration::from_micros(500), &[]) {
+        minisat::TimeoutRes::TimedOut(partial, solver) => {
+            assert!(!partial.is_empty());
+            assert!(partial.len() <= solver.n_vars());
+            let mut vars: Vec<Var> = partial.iter().map(|lit| lit.var()).collect();
+            vars.sort();
+            vars.dedup();
+            assert_eq!(vars.len(), partial.len());
+        }
+        _ => panic!("Expected timeout"),
+    }
+}
+
+
+#[test]
+fn replay_drat_proof() {
+    let formula = "p cnf 2 4\n1 2 0\n1 -2 0\n-1 2 0\n-1 -2 0\n";
+    let load = || {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse(formula.as_bytes(), &mut solver, false).expect("IO Error");
+        solver
+    };
+
+    let proof = "c known-good\n1 0\nd 1 -2 0\nd 1 2 0\n0\n";
+    assert_eq!(load().replay_proof(proof.as_bytes()).expect("IO Error"), None);
+
+    // After deletion of '(1 -2)' unit '1' is no longer RUP:
+    let proof = "d 1 -2 0\n1 0\n0\n";
+    assert_eq!(load().replay_proof(proof.as_bytes()).expect("IO Error"), Some(1));
+
+    // Deleting a clause that isn't there is an invalid step:
+    let proof = "d 1 2 0\nd -2 -1 0\nd 1 2 0\n";
+    assert_eq!(load().replay_proof(proof.as_bytes()).expect("IO Error"), Some(2));
+    let proof = "d 1 0\n";
+    assert_eq!(load().replay_proof(proof.as_bytes()).expect("IO Error"), Some(0));
+}
+
+
+#[test]
+fn batch_bump_keeps_answers() {
+    for &(path, expected_sat) in [("./tests/cnf/uf250-014.cnf.gz", true), ("./tests/cnf/uuf50-0227.cnf.gz", false)].iter() {
+        let mut settings = minisat::CoreSettings::default();
+        settings.heur.batch_bump = true;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, _) => {
+                assert!(expected_sat);
+                let mut checker = minisat::CoreSolver::new(Default::default());
+                dimacs::parse_file(path, &mut checker, false).expect("IO Error");
+                assert!(checker.verify_model(&model));
+            }
+            SolveRes::UnSAT(_) => assert!(!expected_sat),
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
+
+
+#[test]
+fn bundled_instances() {
+    let mut paths: Vec<_> = std::fs::read_dir("./tests/cnf").expect("IO Error")
+        .map(|entry| entry.expect("IO Error").path())
+        .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("uf50-") || name.starts_with("uuf50-")))
+        .collect();
+    paths.sort();
+
+    for path in paths.iter().step_by(10) {
+        let expected_sat = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("uf"));
+        let load = || {
+            let mut solver = minisat::CoreSolver::new(Default::default());
+            dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+            solver
+        };
+
+        match load().solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, _) => assert!(expected_sat && load().verify_model(&model), "{:?}", path),
+            SolveRes::UnSAT(_) => assert!(!expected_sat, "{:?}", path),
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
+
+
+#[test]
+fn solve_with_assumption_clauses() {
+    let load = || {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let vars: Vec<_> = (0..7).map(|_| solver.new_var(None, true).pos_lit()).collect();
+        let (a, b, c, d, x) = (vars[0], vars[1], vars[2], vars[3], vars[4]);
+
+        // Either of 'a', 'b' implies 'x', either of 'c', 'd' implies '~x'. The rest are unconstrained:
+        assert!(solver.add_clause(&[!a, x]));
+        assert!(solver.add_clause(&[!b, x]));
+        assert!(solver.add_clause(&[!c, !x]));
+        assert!(solver.add_clause(&[!d, !x]));
+        (solver, vars)
+    };
+
+    let (solver, v) = load();
+    match solver.solve_limited_with_clauses(&Budget::new(), &[&[v[5], v[6]], &[v[0], v[1]], &[v[2], v[3]]]) {
+        minisat::ClauseAssumpsRes::Failed(failed, _) => assert_eq!(failed, vec![1, 2]),
+        _ => panic!("Expected failed assumption clauses"),
+    }
+
+    let (solver, v) = load();
+    match solver.solve_limited_with_clauses(&Budget::new(), &[&[v[5], v[6]], &[v[0], v[1]]]) {
+        minisat::ClauseAssumpsRes::SAT(model, _) => {
+            assert_eq!(model.len(), 7);
+            assert!(model.contains(&v[0]) || model.contains(&v[1]));
+            assert!(model.contains(&v[5]) || model.contains(&v[6]));
+        }
+        _ => panic!("Expected SAT"),
+    }
+}
+
+
+#[test]
+fn assumption_clauses_over_undeclared_vars() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let a = solver.new_var(None, true).pos_lit();
+    assert!(solver.add_clause(&[a]));
+
+    // Neither 'x' nor 'y' exists in the solver yet, so they must not be confused with selectors:
+    let (x, y) = (Var::from_index(3).pos_lit(), Var::from_index(5).neg_lit());
+    match solver.solve_limited_with_clauses(&Budget::new(), &[&[!a, x], &[y]]) {
+        minisat::ClauseAssumpsRes::SAT(model, _) => {
+            assert_eq!(model.len(), 6);
+            assert!(model.contains(&a) && model.contains(&x) && model.contains(&y));
+        }
+        _ => panic!("Expected SAT"),
+    }
+}
+
+
+#[test]
+fn preprocess_with_elim_budget() {
+    let path = "./tests/cnf/uf50-0100.cnf.gz";
+    let load = || {
+        let mut solver = minisat::SimpSolver::new(Default::default());
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        solver
+    };
+
+    let preprocess = |solver: &mut minisat::SimpSolver, budget: &Budget| {
+        let mut reports = Vec::new();
+        assert!(solver.preprocess_with_progress(budget, |p| reports.push((p.eliminated_vars, p.removed_clauses))));
+        *reports.last().expect("Expected final progress report")
+    };
+
+    let (full_eliminated, _) = preprocess(&mut load(), &Budget::new());
+    assert!(full_eliminated > 2);
+
+    let mut budget = Budget::new();
+    budget.set_elim_budget(2);
+    let mut solver = load();
+    let (eliminated, removed) = preprocess(&mut solver, &budget);
+    assert_eq!(eliminated, 2);
+    assert!(removed > 0);
+
+    match solver.solve_limited(&Budget::new(), &[]) {
+        SolveRes::SAT(model, _) => assert!(load().verify_model(&model)),
+        _ => panic!("Expected SAT"),
+    }
+}
+
+
+#[test]
+fn manual_garbage_collection() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let x = solver.new_var(None, true);
+    let vars: Vec<_> = (0..100).map(|_| solver.new_var(None, true)).collect();
+
+    // Few enough clauses mention 'x' to stay below the automatic collection threshold:
+    for (i, w) in vars.windows(3).enumerate() {
+        assert!(solver.add_clause(&[w[0].pos_lit(), w[1].neg_lit(), w[2].pos_lit()]));
+        if i % 10 == 0 {
+            assert!(solver.add_clause(&[x.pos_lit(), w[0].neg_lit(), w[2].neg_lit()]));
+        }
+    }
+    assert!(solver.add_clause(&[x.pos_lit()]));
+    assert!(solver.simplify());
+    assert_eq!(solver.stats().gc_runs, 0);
+
+    let (before, after) = solver.collect_garbage();
+    assert!(after < before);
+    let stats = solver.stats();
+    assert_eq!(stats.gc_runs, 1);
+    assert_eq!(stats.gc_reclaimed_bytes, (before - after) as u64);
+}
+
+
+#[test]
+fn dpll_without_learning() {
+    for &(path, expected_sat) in [("./tests/cnf/uf50-0100.cnf.gz", true), ("./tests/cnf/uuf50-0227.cnf.gz", false)].iter() {
+        let load = |learning: bool| {
+            let mut settings = minisat::CoreSettings::default();
+            settings.core.learning = learning;
+            let mut solver = minisat::CoreSolver::new(settings);
+            dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+            solver
+        };
+
+        // No literals of learnt clauses are counted, as there are no learnt clauses at all:
+        match load(false).solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, stats) => {
+                assert!(expected_sat && load(true).verify_model(&model));
+                assert_eq!(stats.tot_literals, 0);
+            }
+            SolveRes::UnSAT(stats) => {
+                assert!(!expected_sat);
+                assert!(stats.conflicts > 0);
+                assert_eq!(stats.tot_literals, 0);
+            }
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
+
+
+#[test]
+fn add_clause_allocates_missing_vars() {
+    let x = Var::from_index(5);
+    let y = Var::from_index(2);
+
+    let mut core = minisat::CoreSolver::new(Default::default());
+    assert!(core.add_clause(&[x.neg_lit(), y.pos_lit()]));
+    assert!(core.add_clause(&[x.pos_lit()]));
+    assert_eq!(core.n_vars(), 6);
+
+    let mut simp = minisat::SimpSolver::new(Default::default());
+    assert!(simp.add_clause(&[x.neg_lit(), y.pos_lit()]));
+    assert!(simp.add_clause(&[x.pos_lit()]));
+    assert_eq!(simp.n_vars(), 6);
+
+    for model in [solve_sat(core), solve_sat(simp)].iter() {
+        assert!(model.contains(&x.pos_lit()) && model.contains(&y.pos_lit()));
+    }
+}
+
+fn solve_sat<S: Solver>(solver: S) -> Vec<Lit> {
+    match solver.solve_limited(&Budget::new(), &[]) {
+        SolveRes::SAT(model, _) => model,
+        _ => panic!("Expected SAT"),
+    }
+}
+
+
+#[test]
+fn dont_care_polarity() {
+    for &(dont_care, expected) in [(minisat::DontCarePolarity::Omit, None), (minisat::DontCarePolarity::False, Some(false)), (minisat::DontCarePolarity::True, Some(true))].iter() {
+        let mut settings = minisat::CoreSettings::default();
+        settings.search.dont_care = dont_care;
+        let mut solver = minisat::CoreSolver::new(settings);
+        let x = solver.new_var(None, true);
+        let free = solver.new_var(None, false);
+        let y = solver.new_var(None, true);
+        assert!(solver.add_clause(&[x.pos_lit(), y.pos_lit()]));
+
+        let model = solve_sat(solver);
+        assert_eq!(model.len(), if expected.is_some() { 3 } else { 2 });
+        assert_eq!(model.contains(&free.pos_lit()), expected == Some(true));
+        assert_eq!(model.contains(&free.neg_lit()), expected == Some(false));
+    }
+
+    // A variable left unassigned in the second model keeps its value from the first one, and a
+    // variable that was never assigned is left out:
+    let mut settings = minisat::CoreSettings::default();
+    settings.search.dont_care = minisat::DontCarePolarity::LastAssigned;
+    let mut solver = minisat::CoreSolver::new(settings);
+    let x = solver.new_var(None, true);
+    let free = solver.new_var(None, false);
+    solver.new_var(None, false);
+    assert!(solver.add_clause(&[!x.pos_lit(), free.neg_lit()]));
+    match solver.solve_under(&Budget::new(), &[x.pos_lit()]) {
+        minisat::StepRes::SAT(model) => assert_eq!(model, vec![x.pos_lit(), free.neg_lit()]),
+        _ => panic!("Expected SAT"),
+    }
+    match solver.solve_under(&Budget::new(), &[x.neg_lit()]) {
+        minisat::StepRes::SAT(model) => assert_eq!(model, vec![x.neg_lit(), free.neg_lit()]),
+        _ => panic!("Expected SAT"),
+    }
+}
+
+
+#[test]
+fn detailed_stats() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    match solver.solve_limited(&Budget::new(), &[]) {
+        SolveRes::UnSAT(stats) => {
+            assert!(stats.conflicts > 0);
+            let detailed = stats.detailed_stats();
+            assert!(detailed.propagations_per_conflict > 0.0);
+            assert!(detailed.conflicts_per_restart > 0.0);
+            assert!(detailed.max_trail_size > 0 && detailed.max_trail_size <= 50);
+
+            assert!(detailed.avg_learnt_len > 0.0 && detailed.avg_lbd > 0.0);
+            assert!(detailed.max_learnt_len as f64 >= detailed.avg_learnt_len);
+            assert!(detailed.avg_lbd <= detailed.avg_learnt_len);
+        }
+        _ => panic!("Expected UnSAT"),
+    }
+}
+
+
+#[test]
+fn analyze_bundled_instance() {
+    let stats = minisat_rust::analyze("./tests/cnf/uf50-0100.cnf.gz").expect("IO Error");
+    assert_eq!(stats.vars, stats.header_vars);
+    assert_eq!(stats.clauses, stats.header_clauses);
+    assert_eq!(stats.clause_lengths.iter().sum::<usize>(), stats.clauses);
+    assert_eq!(stats.occurrences.iter().sum::<usize>(), 2 * stats.vars);
+}
+
+
+#[test]
+fn solve_reports_phase_timings() {
+    let options = minisat_rust::MainOptions {
+        strict: true,
+        header_vars: dimacs::HeaderMismatch::Error,
+        pre: true,
+        solve: true,
+        analyze: false,
+        in_path: "./tests/cnf/uf250-015.cnf.gz".into(),
+        out_path: None,
+        dimacs_path: None,
+        compact: false,
+        trace_path: None,
+        replay_path: None,
+    };
+
+    let start = Instant::now();
+    let timings = minisat_rust::solve(options, minisat_rust::SolverOptions::Simp(Default::default())).expect("IO Error");
+    let elapsed = start.elapsed();
+
+    // Every phase takes some time, and setup and the model check afterwards are left out:
+    assert!(timings.parse > Duration::ZERO);
+    assert!(timings.preprocess > Duration::ZERO);
+    assert!(timings.search > Duration::ZERO);
+    assert!(timings.total() <= elapsed);
+}
+
+
+#[test]
+fn elimination_growth_cap() {
+    // Eliminating the hub 'h' replaces 2n binary clauses with n^2 resolvents. Until then, pool
+    // variables can not go as their resolvents with the long clauses would exceed 'clause_lim', so
+    // the hub is the only variable eliminated within the budget:
+    let n = 11;
+    let preprocess = |max_growth_factor: f64| {
+        let mut settings = minisat::SimpSettings::default();
+        settings.simp.grow = 1_000_000;
+        settings.simp.max_growth_factor = max_growth_factor;
+        let mut solver = minisat::SimpSolver::new(settings);
+        let h = solver.new_var(None, true);
+        let ps: Vec<Var> = (0..n).map(|_| solver.new_var(None, true)).collect();
+        let qs: Vec<Var> = (0..n).map(|_| solver.new_var(None, true)).collect();
+        for (&p, &q) in ps.iter().zip(qs.iter()) {
+            assert!(solver.add_clause(&[h.pos_lit(), p.pos_lit()]));
+            assert!(solver.add_clause(&[h.neg_lit(), q.pos_lit()]));
+        }
+        let pool: Vec<Var> = ps.iter().chain(qs.iter()).cloned().collect();
+        assert!(solver.add_clause(&pool.iter().map(|v| v.pos_lit()).collect::<Vec<Lit>>()));
+        assert!(solver.add_clause(&pool.iter().map(|v| v.neg_lit()).collect::<Vec<Lit>>()));
+
+        let before = solver.n_clauses();
+        let mut budget = Budget::new();
+        budget.set_elim_budget(1);
+        assert!(solver.preprocess(&budget));
+        (before, solver.n_clauses())
+    };
+
+    let (before, unlimited) = preprocess(f64::INFINITY);
+    assert_eq!(unlimited, n * n + 2);
+
+    let (_, capped) = preprocess(2.0);
+    assert!(capped <= 2 * before);
+}
+
+
+#[test]
+fn reason_of_propagated_literal() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let a = solver.new_var(None, true);
+    let b = solver.new_var(None, true);
+    let c = solver.new_var(None, true);
+    let d = solver.new_var(None, true);
+    assert!(solver.add_clause(&[a.neg_lit(), b.neg_lit(), c.pos_lit()]));
+    assert!(solver.add_clause(&[c.neg_lit(), d.pos_lit(), a.neg_lit()]));
+    assert!(solver.add_clause(&[a.pos_lit()]));
+    assert!(solver.add_clause(&[b.pos_lit()]));
+
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(0);
+    let solver = match solver.solve_limited(&budget, &[]) {
+        SolveRes::Interrupted(_, solver) => solver,
+        _ => panic!("Expected interruption"),
+    };
+
+    // Units have no reason clause. Every other literal of a reason clause is falsified by earlier
+    // assignments, so the clause implies the propagated one:
+    assert_eq!(solver.reason_of(a), None);
+    let mut reason = solver.reason_of(c).expect("No reason for propagated literal").to_vec();
+    reason.sort();
+    assert_eq!(reason, vec![a.neg_lit(), b.neg_lit(), c.pos_lit()]);
+
+    let mut reason = solver.reason_of(d).expect("No reason for propagated literal").to_vec();
+    reason.sort();
+    assert_eq!(reason, vec![a.neg_lit(), c.neg_lit(), d.pos_lit()]);
+}
+
+
+#[test]
+fn restart_limits_are_clamped() {
+    let mut settings = minisat::CoreSettings::default();
+    settings.search.restart.restart_first = 0.0;
+    settings.search.restart.restart_inc = 0.5;
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+
+    // Restarting before every conflict would never finish:
+    match solver.solve_limited(&Budget::new(), &[]) {
+        SolveRes::UnSAT(stats) => assert!(stats.restarts <= stats.conflicts + 1),
+        _ => panic!("Expected UnSAT"),
+    }
+}
+
+
+#[test]
+fn soft_assumptions_drop_lightest() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let vars: Vec<Var> = (0..4).map(|_| solver.new_var(None, true)).collect();
+    let (a, b, c, d) = (vars[0], vars[1], vars[2], vars[3]);
+    assert!(solver.add_clause(&[a.neg_lit(), b.neg_lit(), c.neg_lit()]));
+    assert!(solver.add_clause(&[d.neg_lit(), a.pos_lit()]));
+
+    // Hard assumption 'd' forces 'a', so one of 'b' and 'c' has to go:
+    let soft = [(b.pos_lit(), 3), (a.pos_lit(), 4), (c.pos_lit(), 2)];
+    match solver.solve_limited_soft(&Budget::new(), &[d.pos_lit()], &soft) {
+        minisat::SoftRes::SAT(model, weight, _) => {
+            assert_eq!(weight, 7);
+            assert!(model.contains(&a.pos_lit()) && model.contains(&b.pos_lit()) && model.contains(&c.neg_lit()));
+        }
+        _ => panic!("Expected SAT"),
+    }
+}
+
+
+#[test]
+fn into_parts_after_interrupt() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(20);
+    let solver = match solver.solve_limited(&budget, &[]) {
+        SolveRes::Interrupted(_, solver) => solver,
+        _ => panic!("Expected interruption"),
+    };
+
+    let n_vars = solver.n_vars();
+    let n_clauses = solver.n_clauses();
+    let parts = solver.into_parts();
+    assert_eq!(parts.clauses.len(), n_clauses);
+    assert!(!parts.learnts.is_empty());
+    assert_eq!(parts.assigns.number_of_vars(), n_vars);
+    assert!(parts.assigns.is_ground_level());
+}
+
+
+#[test]
+fn pure_literals_are_eliminated() {
+    let preprocess = |use_pure_literals: bool| {
+        let mut settings = minisat::SimpSettings::default();
+        settings.simp.use_elim = false;
+        settings.simp.use_pure_literals = use_pure_literals;
+        let mut solver = minisat::SimpSolver::new(settings);
+        let vars: Vec<Var> = (0..3).map(|_| solver.new_var(None, true)).collect();
+        let (p, a, b) = (vars[0], vars[1], vars[2]);
+        assert!(solver.add_clause(&[p.pos_lit(), a.pos_lit(), b.pos_lit()]));
+        assert!(solver.add_clause(&[p.pos_lit(), a.neg_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), b.neg_lit()]));
+        assert!(solver.add_clause(&[a.neg_lit(), b.pos_lit()]));
+        assert!(solver.preprocess(&Budget::new()));
+        (solver, p)
+    };
+
+    let (solver, _) = preprocess(false);
+    assert_eq!(solver.n_clauses(), 4);
+
+    // Only 'p' is pure, clauses left after removing it have both polarities of 'a' and 'b':
+    let (solver, p) = preprocess(true);
+    assert_eq!(solver.n_clauses(), 2);
+    assert!(solve_sat(solver).contains(&p.pos_lit()));
+}
+
+
+#[test]
+fn preprocess_rounds_until_fixpoint() {
+    let preprocess = |max_rounds: usize| {
+        let mut settings = minisat::SimpSettings::default();
+        settings.simp.use_elim = false;
+        settings.simp.use_pure_literals = true;
+        settings.simp.max_preprocess_rounds = max_rounds;
+        let mut solver = minisat::SimpSolver::new(settings);
+        let vars: Vec<Var> = (0..4).map(|_| solver.new_var(None, true)).collect();
+        let (a, b, c, d) = (vars[0], vars[1], vars[2], vars[3]);
+        assert!(solver.add_clause(&[a.pos_lit(), b.pos_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), b.pos_lit(), c.neg_lit()]));
+        assert!(solver.add_clause(&[c.pos_lit(), d.pos_lit()]));
+        assert!(solver.add_clause(&[a.neg_lit(), b.neg_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), d.neg_lit()]));
+        assert!(solver.preprocess(&Budget::new()));
+        let rounds: Vec<(u64, usize)> =
+            solver.preprocess_rounds().iter().map(|r| (r.eliminated_vars, r.removed_clauses)).collect();
+        (solver, rounds)
+    };
+
+    // Nothing is pure up front. Subsumption removes the only clause with '-c' in the first round,
+    // which leaves 'c' and then 'd' pure for the second one:
+    let (solver, rounds) = preprocess(1);
+    assert_eq!(rounds, vec![(0, 1)]);
+    assert_eq!(solver.n_clauses(), 4);
+
+    let (solver, rounds) = preprocess(10);
+    assert_eq!(rounds, vec![(0, 1), (2, 2), (0, 0)]);
+    assert_eq!(solver.n_clauses(), 2);
+    assert_eq!(solve_sat(solver).len(), 4);
+}
+
+
+#[test]
+fn composed_budget_limits() {
+    use minisat_rust::sat::minisat::budget::InterruptHandle;
+
+    let interrupted_after = |budget: Budget| {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, solver) => Some(solver.stats().conflicts),
+            _ => None,
+        }
+    };
+
+    let handle = InterruptHandle::new();
+    let compose = |conflicts: u64, time: Duration| Budget::new().conflicts(conflicts).time(time).with_interrupt(&handle);
+    let long = Duration::from_secs(60);
+
+    assert_eq!(interrupted_after(compose(10, long)), Some(10));
+    assert_eq!(interrupted_after(compose(u32::MAX as u64, Duration::from_secs(0))), Some(0));
+
+    handle.interrupt();
+    assert_eq!(interrupted_after(compose(u32::MAX as u64, long)), Some(0));
+    handle.clear();
+    assert_eq!(interrupted_after(compose(u32::MAX as u64, long)), None);
+}
+
+#[test]
+fn trace_replay_detects_divergence() {
+    let path = std::env::temp_dir().join(format!("minisat-trace-{}.bin", std::process::id()));
+    let run = |seed: f64, replay: bool, budget: Budget| {
+        let mut settings = minisat::CoreSettings::default();
+        settings.heur.random_var_freq = 0.05;
+        settings.heur.random_seed = seed;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+        if replay {
+            solver.replay_trace(std::fs::File::open(&path).expect("IO Error"));
+        } else {
+            solver.record_trace(std::fs::File::create(&path).expect("IO Error"));
+        }
+        solver.solve_under(&budget, &[]);
+        solver.finish_trace()
+    };
+
+    assert!(run(91648253.0, false, Budget::new()).is_ok());
+    assert!(run(91648253.0, true, Budget::new()).is_ok());
+    // A replay that stops early leaves events of the trace unchecked:
+    assert_eq!(run(91648253.0, true, Budget::new().conflicts(10)).map_err(|err| err.kind()), Err(std::io::ErrorKind::InvalidData));
+    let diverged = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run(12345.0, true, Budget::new())));
+    std::fs::remove_file(&path).expect("IO Error");
+    assert!(diverged.is_err());
+
+    // Write errors are reported at the end instead of panicking during search:
+    struct Full;
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "Disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    solver.record_trace(Full);
+    assert!(matches!(solver.solve_under(&Budget::new(), &[]), minisat::StepRes::UnSAT));
+    assert_eq!(solver.finish_trace().map_err(|err| err.kind()), Err(std::io::ErrorKind::WriteZero));
+
+    // A solve that consumes the solver leaves the trace to its handle:
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    let trace = solver.record_trace(Full);
+    assert!(matches!(solver.solve_limited(&Budget::new(), &[]), SolveRes::UnSAT(_)));
+    assert_eq!(trace.finish().map_err(|err| err.kind()), Err(std::io::ErrorKind::WriteZero));
+    assert!(trace.finish().is_ok());
+}
+
+#[test]
+fn clauses_with_literal() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let v: Vec<Lit> = (0..4).map(|_| solver.new_var(None, true).pos_lit()).collect();
+    let mut clauses = vec![
+        vec![v[0], v[1]],
+        vec![!v[0], v[2], v[3]],
+        vec![v[1], !v[2]],
+        vec![!v[1], !v[3], v[0]],
+    ];
+    for c in clauses.iter() {
+        assert!(solver.add_clause(c));
+    }
+
+    let check = |solver: &minisat::CoreSolver, clauses: &[Vec<Lit>]| {
+        for l in v.iter().flat_map(|&l| vec![l, !l]) {
+            let mut found: Vec<Vec<Lit>> = solver.clauses_with(l).iter().map(|c| c.to_vec()).collect();
+            let mut expected: Vec<Vec<Lit>> = clauses.iter().filter(|c| c.contains(&l)).cloned().collect();
+            for c in found.iter_mut().chain(expected.iter_mut()) {
+                c.sort();
+            }
+            found.sort();
+            expected.sort();
+            assert_eq!(found, expected, "literal {:?}", l);
+        }
+    };
+
+    check(&solver, &clauses);
+
+    // The index must pick up clauses added after the first query:
+    clauses.push(vec![v[2], !v[3]]);
+    assert!(solver.add_clause(&clauses[4]));
+    check(&solver, &clauses);
+}
+
+#[test]
+fn absolute_learnt_cap() {
+    let peak = |cap: Option<usize>| {
+        let mut settings = minisat::CoreSettings::default();
+        settings.search.learn.max_learnts_abs = cap;
+        settings.db.core_lbd_cutoff = 0;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file("./tests/cnf/uf250-014.cnf.gz", &mut solver, false).expect("IO Error");
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(_, stats) => stats.max_learnts,
+            _ => panic!("Unexpected result"),
+        }
+    };
+
+    // Binary learnt clauses (with the MiniSat cutoff) are never removed and a streak of conflicts is only reduced after it
+    // ends, so the cap may be overshot a little:
+    let cap = 20;
+    assert!(peak(None) > 2 * cap);
+    assert!(peak(Some(cap as usize)) <= 2 * cap);
+}
+
+#[test]
+fn cloned_solvers_are_independent() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf50-0100.cnf.gz", &mut solver, false).expect("IO Error");
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(10);
+    let snapshot = match solver.solve_limited(&budget, &[]) {
+        SolveRes::Interrupted(_, solver) => solver,
+        _ => panic!("Expected interruption"),
+    };
+    let (n_vars, n_clauses) = (snapshot.n_vars(), snapshot.n_clauses());
+
+    let model = solve_sat(snapshot.clone());
+    assert!(snapshot.verify_model(&model));
+
+    // Pin half of the model in one copy and contradict it in another:
+    let mut pinned = snapshot.clone();
+    for &lit in model.iter().step_by(2) {
+        assert!(pinned.add_clause(&[lit]));
+    }
+    let mut contradicted = snapshot.clone();
+    let y = contradicted.new_var(None, true);
+    contradicted.add_clause(&[y.pos_lit()]);
+    contradicted.add_clause(&[y.neg_lit()]);
+
+    let pinned_model = solve_sat(pinned);
+    assert!(model.iter().step_by(2).all(|lit| pinned_model.contains(lit)));
+    match contradicted.solve_limited(&Budget::new(), &[]) {
+        SolveRes::UnSAT(_) => {}
+        _ => panic!("Expected UnSAT"),
+    }
+
+    // Neither copy leaked into the snapshot:
+    assert_eq!((snapshot.n_vars(), snapshot.n_clauses()), (n_vars, n_clauses));
+    assert!(snapshot.verify_model(&solve_sat(snapshot.clone())));
+}
+
+#[test]
+fn export_import_learnts() {
+    for &(path, expected_sat) in [("./tests/cnf/uf50-0100.cnf.gz", true), ("./tests/cnf/uuf50-0227.cnf.gz", false)].iter() {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        let backward_subst = dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        let mut budget = Budget::new();
+        budget.set_conflict_budget(20);
+        let solver = match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, solver) => solver,
+            _ => panic!("Expected interruption"),
+        };
+
+        let mut exported = Vec::new();
+        solver.export_learnts(&mut exported, 6, &backward_subst).expect("IO Error");
+
+        let mut fresh = minisat::CoreSolver::new(Default::default());
+        let fresh_subst = dimacs::parse_file(path, &mut fresh, false).expect("IO Error");
+        let imported = fresh.import_learnts(&exported[..], &fresh_subst).expect("IO Error");
+        assert!(imported > 0);
+        assert_eq!(fresh.n_clauses(), solver.n_clauses());
+
+        match fresh.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, _) => {
+                assert!(expected_sat);
+                let mut check = minisat::CoreSolver::new(Default::default());
+                dimacs::parse_file(path, &mut check, false).expect("IO Error");
+                assert!(check.verify_model(&model));
+            }
+            SolveRes::UnSAT(_) => assert!(!expected_sat),
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
+
+#[test]
+fn adaptive_conflict_minimization() {
+    let adaptive = || {
+        let mut settings = minisat::CoreSettings::default();
+        settings.ccmin_mode = minisat::CCMinMode::Adaptive;
+        minisat::CoreSolver::new(settings)
+    };
+
+    for &(path, expected_sat) in [("./tests/cnf/4blocks.cnf.gz", true), ("./tests/cnf/uuf50-0227.cnf.gz", false)].iter() {
+        let mut solver = adaptive();
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(_, _) => assert!(expected_sat),
+            SolveRes::UnSAT(_) => assert!(!expected_sat),
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+
+    // Deep minimization removes about 1 % of literals on the adder, but a sixth on random 3-SAT:
+    for &(path, expected_mode) in [("./tests/cnf/3bitadd_31.cnf.gz", minisat::CCMinMode::Basic), ("./tests/cnf/uf250-014.cnf.gz", minisat::CCMinMode::Deep)].iter() {
+        let mut solver = adaptive();
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        assert_eq!(solver.ccmin_mode(), minisat::CCMinMode::Deep);
+        let mut budget = Budget::new();
+        budget.set_conflict_budget(2000);
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, solver) => assert_eq!(solver.ccmin_mode(), expected_mode, "{}", path),
+            _ => panic!("Expected interruption"),
+        }
+    }
+}
+
+#[test]
+fn persistent_conflict_budget() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0227.cnf.gz", &mut solver, false).expect("IO Error");
+    solver.set_conflict_budget(Some(0));
+
+    // The budget holds for every call until it is removed:
+    for _ in 0..2 {
+        solver = match solver.solve() {
+            SolveRes::Interrupted(_, solver) => solver,
+            _ => panic!("Expected interruption"),
+        };
+        assert_eq!(solver.stats().conflicts, 0);
+    }
+
+    solver.set_conflict_budget(None);
+    match solver.solve() {
+        SolveRes::UnSAT(_) => {}
+        _ => panic!("Expected UnSAT"),
+    }
+}
+
+#[test]
+fn cube_and_conquer() {
+    use minisat::cube::{self, CubeRes};
+
+    for &(path, expected_sat) in [("./tests/cnf/uf250-014.cnf.gz", true), ("./tests/cnf/uuf50-0890.cnf.gz", false)].iter() {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+
+        let cubes = cube::split(&solver, 4);
+        assert_eq!(cubes.len(), 4);
+
+        // Every assignment of the split variables agrees with exactly one cube:
+        let vars: Vec<Var> = cubes[0].iter().map(|lit| lit.var()).collect();
+        assert_eq!(vars.len(), 2);
+        for mask in 0..4 {
+            let assignment: Vec<Lit> = vars.iter().enumerate().map(|(i, v)| v.sign_lit((mask >> i) & 1 == 1)).collect();
+            let agreeing = cubes.iter().filter(|c| c.iter().all(|lit| assignment.contains(lit))).count();
+            assert_eq!(agreeing, 1);
+        }
+
+        match cube::solve_cubes(&Default::default(), &solver, &cubes, 2) {
+            CubeRes::SAT(model, _) => {
+                assert!(expected_sat);
+                assert!(solver.verify_model(&model));
+            }
+            CubeRes::UnSAT(_) => assert!(!expected_sat),
+        }
+    }
+}
+
+
+#[test]
+fn trail_saving_keeps_verdicts() {
+    let paths = ["./tests/cnf/4blocks.cnf.gz", "./tests/cnf/uf50-0100.cnf.gz", "./tests/cnf/uuf50-0890.cnf.gz"];
+    for &path in paths.iter() {
+        let load = |trail_saving: bool| {
+            let mut settings = minisat::CoreSettings::default();
+            settings.core.trail_saving = trail_saving;
+            let mut solver = minisat::CoreSolver::new(settings);
+            dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+            solver
+        };
+
+        // Assumptions are kept at restarts, so they are checked too:
+        let assumptions = [Var::from_index(0).pos_lit(), Var::from_index(1).neg_lit()];
+        for assumptions in [&[][..], &assumptions[..]].iter() {
+            let verdict = |res: SolveRes<minisat::CoreSolver>| match res {
+                SolveRes::SAT(model, _) => {
+                    assert!(load(false).verify_model(&model), "{}", path);
+                    assert!(assumptions.iter().all(|lit| model.contains(lit)), "{}", path);
+                    true
+                }
+                SolveRes::UnSAT(_) => false,
+                SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+            };
+
+            let expected = verdict(load(false).solve_limited(&Budget::new(), assumptions));
+            let sat = verdict(load(true).solve_limited(&Budget::new(), assumptions));
+            assert_eq!(sat, expected, "{} {:?}", path, assumptions);
+        }
+    }
+}
+
+
+#[test]
+fn trail_saving_simplifies_under_assumptions() {
+    let satisfied_after = |trail_saving: bool| {
+        let mut settings = minisat::CoreSettings::default();
+        settings.core.trail_saving = trail_saving;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file("./tests/cnf-hard/uuf250-01.cnf.gz", &mut solver, false).expect("IO Error");
+
+        // Each 'x' is learnt as a unit right at the start, which satisfies four clauses. The random
+        // part keeps the search going with restarts long after that:
+        let a = solver.new_var(None, true).pos_lit();
+        let mut prefix = Vec::new();
+        for _ in 0..50 {
+            let [x, y, z] = [(); 3].map(|_| solver.new_var(None, true).pos_lit());
+            for &(sy, sz) in [(false, false), (true, false), (false, true), (true, true)].iter() {
+                assert!(solver.add_clause(&[x, if sy { !y } else { y }, if sz { !z } else { z }]));
+            }
+            prefix.extend_from_slice(&[x.var(), y.var(), z.var()]);
+        }
+        solver.set_decision_prefix(&prefix);
+
+        match solver.solve_limited(&Budget::new().conflicts(5000), &[a]) {
+            SolveRes::Interrupted(_, solver) => {
+                let parts = solver.into_parts();
+                assert_eq!(parts.assigns.number_of_ground_assigns(), 50);
+                parts.clauses.iter().filter(|c| c.iter().any(|&lit| parts.assigns.is_assigned_pos(lit))).count()
+            }
+            _ => panic!("Expected interruption"),
+        }
+    };
+
+    // The assumption level is kept at restarts, but not when it's time to remove satisfied clauses:
+    assert_eq!(satisfied_after(false), 0);
+    assert_eq!(satisfied_after(true), 0);
+}
+
+
+#[test]
+fn progress_of_resumed_solve() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uuf50-0890.cnf.gz", &mut solver, false).expect("IO Error");
+    assert_eq!(solver.progress(), 0.0);
+
+    let mut seen = Vec::new();
+    let budget = Budget::new().per_call().conflicts(20);
+    let stats = loop {
+        match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(progress, s) => {
+                assert_eq!(s.progress(), progress);
+                seen.push(progress);
+                solver = s;
+            }
+            SolveRes::UnSAT(stats) => break stats,
+            SolveRes::SAT(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    };
+    seen.push(stats.progress);
+
+    assert!(seen.len() > 2);
+    assert!(seen.iter().all(|&p| (0.0..=1.0).contains(&p)));
+    assert!(seen.windows(2).all(|w| w[0] <= w[1]));
+    assert!(seen[0] < stats.progress);
+}
+
+
+#[test]
+fn long_clauses_never_subsume() {
+    for &(path, expected_sat) in [("./tests/cnf/uf50-0100.cnf.gz", true), ("./tests/cnf/uuf50-0227.cnf.gz", false)].iter() {
+        let mut settings = minisat::SimpSettings::default();
+        settings.simp.subsuming_clause_max_len = 100;
+        settings.simp.use_elim = false; // All resolvents on the long variables are tautologies.
+        let mut solver = minisat::SimpSolver::new(settings);
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+
+        // Long clauses over the same variables that differ in two signs, so that none subsumes
+        // or strengthens another, but each one would be checked against all the others:
+        let long: Vec<Lit> = (0..900).map(|_| solver.new_var(None, true).pos_lit()).collect();
+        for i in 0..300 {
+            let mut clause = long.clone();
+            clause[i] = !clause[i];
+            clause[i + 300] = !clause[i + 300];
+            solver.add_clause(&clause);
+        }
+
+        assert!(solver.preprocess(&Budget::new()));
+        assert_eq!(solver.clauses().iter().filter(|c| c.len() == long.len()).count(), 300, "{}", path);
+
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::SAT(model, _) => {
+                let mut check = minisat::CoreSolver::new(Default::default());
+                dimacs::parse_file(path, &mut check, false).expect("IO Error");
+                assert!(expected_sat && check.verify_model(&model[..50]));
+            }
+            SolveRes::UnSAT(_) => assert!(!expected_sat),
+            SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+        }
+    }
+}
+
+
+#[test]
+fn check_units_by_propagation() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(|i| Var::from_index(i));
+    solver.add_clause(&[a.neg_lit(), b.pos_lit()]);
+    solver.add_clause(&[b.neg_lit(), c.pos_lit()]);
+    solver.add_clause(&[c.neg_lit(), d.neg_lit()]);
+    solver.add_clause(&[e.neg_lit()]);
+
+    match solver.check_units(&[a.pos_lit()]) {
+        minisat::ConsistencyRes::Consistent(implied) => assert_eq!(implied, vec![b.pos_lit(), c.pos_lit(), d.neg_lit()]),
+        minisat::ConsistencyRes::Conflict(_) => panic!("Unexpected conflict"),
+    }
+
+    // 'e' alone refutes the first set, 'a' and 'd' refute the others, as 'b' follows from 'a'. The
+    // last one fails in propagation rather than on a unit that is false already:
+    for &(units, expected) in [(&[b.pos_lit(), e.pos_lit()][..], &[e.pos_lit()][..]),
+                               (&[a.pos_lit(), b.pos_lit(), d.pos_lit()][..], &[a.pos_lit(), d.pos_lit()][..]),
+                               (&[d.pos_lit(), a.pos_lit()][..], &[a.pos_lit(), d.pos_lit()][..])].iter() {
+        match solver.check_units(units) {
+            minisat::ConsistencyRes::Conflict(core) => assert_eq!(core, expected),
+            minisat::ConsistencyRes::Consistent(_) => panic!("Unexpected consistency"),
+        }
+    }
+
+    // Nothing stays assigned above ground level, and the formula is still satisfiable:
+    assert_eq!(solver.assignment().trail(), &[e.neg_lit()]);
+    solve_sat(solver);
+}
+
+
+#[test]
+fn gzipped_result_round_trips() {
+    use std::io::Read;
+
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let backward_subst = dimacs::parse_file("./tests/cnf/uf50-0100.cnf.gz", &mut solver, false).expect("IO Error");
+    let model = solve_sat(solver);
+
+    let dir = tempfile::tempdir().expect("IO Error");
+    let path = dir.path().join("result.gz");
+    let result: SolveRes<minisat::CoreSolver> = SolveRes::SAT(model.clone(), Default::default());
+    dimacs::write_result_file(&path, result, &backward_subst).expect("IO Error");
+
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(&path).expect("IO Error"))
+        .read_to_string(&mut text)
+        .expect("IO Error");
+
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("SAT"));
+    let ids: Vec<i32> = lines.next().unwrap().split_whitespace().map(|x| x.parse().unwrap()).collect();
+    let expected: Vec<i32> = model.iter().map(|&lit| dimacs::DimacsLit(lit, &backward_subst).id()).chain(Some(0)).collect();
+    assert_eq!(ids, expected);
+    assert_eq!(lines.next(), None);
+}
+
+
+#[test]
+fn persistent_simplification_eliminates_new_vars() {
+    for &persist_simp in [false, true].iter() {
+        let mut settings = minisat::SimpSettings::default();
+        settings.persist_simp = persist_simp;
+        let mut solver = minisat::SimpSolver::new(settings);
+        dimacs::parse_file("./tests/cnf/uuf50-0890.cnf.gz", &mut solver, false).expect("IO Error");
+        assert!(solver.preprocess(&Budget::new()));
+
+        let budget = Budget::new().conflicts(10).per_call();
+        let mut solver = match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, solver) => solver,
+            _ => panic!("Expected interruption"),
+        };
+
+        // 'x' only links 'a' and 'b', so it is eliminated by the next round. 'b' is an assumption
+        // and has to survive it:
+        let x = solver.new_var(None, true);
+        let a = solver.new_var(None, true);
+        let b = solver.new_var(None, true);
+        solver.add_clause(&[x.pos_lit(), a.pos_lit()]);
+        solver.add_clause(&[x.neg_lit(), b.pos_lit()]);
+
+        match solver.solve_limited(&budget, &[b.neg_lit()]) {
+            SolveRes::Interrupted(_, solver) => {
+                assert_eq!(solver.is_eliminated(x), persist_simp);
+                assert!(!solver.is_eliminated(b));
+            }
+            _ => panic!("Expected interruption"),
+        }
+    }
+}
+
+
+#[test]
+fn root_units_after_preprocessing() {
+    let mut solver = minisat::SimpSolver::new(Default::default());
+    let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(|i| Var::from_index(i));
+    solver.add_clause(&[c.neg_lit(), a.pos_lit()]);
+    solver.add_clause(&[c.pos_lit(), a.pos_lit()]);
+    solver.add_clause(&[a.neg_lit(), b.neg_lit()]);
+    solver.add_clause(&[c.pos_lit(), d.pos_lit(), e.pos_lit()]);
+    solver.add_clause(&[d.neg_lit(), e.neg_lit()]);
+    assert!(solver.root_units().is_empty());
+
+    // 'a' follows by resolution on 'c' and 'b' by propagation from it:
+    assert!(solver.preprocess(&Budget::new()));
+    let units = solver.root_units();
+    assert!(units.contains(&a.pos_lit()));
+    assert!(units.contains(&b.neg_lit()));
+    assert!(units.iter().all(|&lit| solver.assignment().is_assigned_pos(lit)));
+}
+
+
+#[test]
+fn priority_decays_across_solves() {
+    for &priority_decay in [1.0, 0.5].iter() {
+        let mut settings = minisat::CoreSettings::default();
+        settings.heur.priority_decay = priority_decay;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file("./tests/cnf/uuf50-0890.cnf.gz", &mut solver, false).expect("IO Error");
+
+        // A variable that occurs in no clause is never bumped, so its activity is all boost:
+        let v = solver.new_var(None, true);
+        solver.set_var_activity(v, 100.0);
+
+        let mut activities = Vec::new();
+        for _ in 0..3 {
+            solver = match solver.solve_limited(&Budget::new().conflicts(10).per_call(), &[]) {
+                SolveRes::Interrupted(_, solver) => solver,
+                _ => panic!("Expected interruption"),
+            };
+            activities.push(solver.var_activity(v));
+        }
+
+        if priority_decay < 1.0 {
+            assert_eq!(activities, vec![50.0, 25.0, 12.5]);
+        } else {
+            assert_eq!(activities, vec![100.0, 100.0, 100.0]);
+        }
+    }
+}
+
+
+// Random clauses over 'n_vars' variables. Horn ones have 1 to 'len' literals with only the first
+// one possibly positive, others have exactly 'len' literals of any polarity:
+fn random_clauses(seed: u64, n_vars: usize, n_clauses: usize, len: usize, horn: bool) -> Vec<Vec<Lit>> {
+    let mut state = seed;
+    let mut next = move |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+
+    (0..n_clauses).map(|_| {
+        let len = if horn { 1 + next(len) } else { len };
+        let mut clause: Vec<Lit> = Vec::with_capacity(len);
+        while clause.len() < len {
+            let v = Var::from_index(next(n_vars));
+            let positive = (!horn || clause.is_empty()) && next(2) == 0;
+            if clause.iter().all(|lit| lit.var() != v) {
+                clause.push(v.sign_lit(!positive));
+            }
+        }
+        clause
+    }).collect()
+}
+
+#[test]
+fn tractable_fragments_skip_search() {
+    let instances = [(minisat::Fragment::Horn, 3, 1.0), (minisat::Fragment::TwoSat, 2, 1.0)];
+    for &(fragment, len, ratio) in instances.iter() {
+        let (mut sat, mut unsat) = (0, 0);
+        for seed in 1..40 {
+            let n_vars = 100;
+            let clauses = random_clauses(seed, n_vars, (n_vars as f64 * ratio) as usize, len, fragment == minisat::Fragment::Horn);
+            let refs: Vec<&[Lit]> = clauses.iter().map(|c| &c[..]).collect();
+            let expected = match minisat::CoreSolver::from_clauses(Default::default(), n_vars, &refs).solve() {
+                SolveRes::SAT(_, _) => true,
+                SolveRes::UnSAT(_) => false,
+                SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected interruption"),
+            };
+
+            let mut settings = minisat::CoreSettings::default();
+            settings.core.detect_tractable = true;
+            let mut solver = minisat::CoreSolver::from_clauses(settings.clone(), n_vars, &refs);
+            // Propagation alone refutes Horn formulas, so only 2-SAT ones need the fast path here:
+            if !solver.preprocess(&Budget::new()) {
+                assert!(!expected, "{:?} seed {}", fragment, seed);
+                // Solving without 'preprocess' refutes it without search as well:
+                match minisat::CoreSolver::from_clauses(settings, n_vars, &refs).solve() {
+                    SolveRes::UnSAT(stats) => assert_eq!((stats.conflicts, stats.decisions), (0, 0)),
+                    _ => panic!("Expected UNSAT: {:?} seed {}", fragment, seed),
+                }
+                unsat += 1;
+                continue;
+            }
+
+            assert_eq!(solver.tractable_fragment(), Some(fragment));
+            match solver.solve() {
+                SolveRes::SAT(model, stats) => {
+                    assert!(expected, "{:?} seed {}", fragment, seed);
+                    assert_eq!((stats.conflicts, stats.decisions), (0, 0));
+                    assert!(clauses.iter().all(|c| c.iter().any(|lit| model.contains(lit))));
+                    sat += 1;
+                }
+                _ => panic!("Expected SAT: {:?} seed {}", fragment, seed),
+            }
+        }
+        assert!(sat > 0 && unsat > 0, "{:?}: {} sat, {} unsat", fragment, sat, unsat);
+    }
+}
+
+
+#[test]
+fn adaptive_gc_keeps_memory_bounded() {
+    let mut settings = minisat::CoreSettings::default();
+    settings.core.adaptive_gc = true;
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse_file("./tests/cnf-hard/uuf250-01.cnf.gz", &mut solver, false).expect("IO Error");
+
+    let mut fracs = Vec::new();
+    for _ in 0..10 {
+        solver = match solver.solve_limited(&Budget::new().conflicts(2000).per_call(), &[]) {
+            SolveRes::Interrupted(_, solver) => solver,
+            _ => panic!("Expected interruption"),
+        };
+        fracs.push(solver.garbage_frac());
+    }
+
+    assert!(solver.stats().gc_runs > 0);
+    assert!(fracs.iter().all(|&frac| (0.05..=0.5).contains(&frac)), "{:?}", fracs);
+    assert!(fracs.iter().any(|&frac| frac != 0.2), "{:?}", fracs);
+
+    // No more than half of clause memory is garbage:
+    let (before, after) = solver.collect_garbage();
+    assert!(before <= 2 * after, "{} bytes before collection, {} after", before, after);
+}
+
+
+#[test]
+fn strengthening_to_conflicting_unit() {
+    // Self-subsumption turns '(a | b)' into unit 'a', that contradicts the last two clauses:
+    let mut solver = minisat::SimpSolver::new(Default::default());
+    let [a, b, c] = [0, 1, 2].map(|i| Var::from_index(i));
+    solver.add_clause(&[a.pos_lit(), b.pos_lit()]);
+    solver.add_clause(&[a.pos_lit(), b.neg_lit()]);
+    solver.add_clause(&[a.neg_lit(), c.pos_lit()]);
+    solver.add_clause(&[a.neg_lit(), c.neg_lit()]);
+
+    assert!(!solver.preprocess(&Budget::new()));
+    match solver.solve() {
+        SolveRes::UnSAT(_) => {}
+        _ => panic!("Expected UNSAT"),
+    }
+}
+
+#[test]
+fn simplify_on_add_cleans_earlier_clauses() {
+    let clauses_of = |simplify_on_add: bool| {
+        let mut settings = minisat::CoreSettings::default();
+        settings.core.simplify_on_add = simplify_on_add;
+        let mut solver = minisat::CoreSolver::new(settings);
+        let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(|i| Var::from_index(i));
+        assert!(solver.add_clause(&[a.pos_lit(), b.pos_lit(), c.pos_lit(), d.pos_lit()]));
+        assert!(solver.add_clause(&[e.pos_lit(), c.neg_lit()]));
+        // Unit '!e' implies '!c', which shortens the clause added before and the one added after:
+        assert!(solver.add_clause(&[e.neg_lit()]));
+        assert!(solver.add_clause(&[c.pos_lit(), a.neg_lit(), b.pos_lit()]));
+
+        let mut long = solver.clauses_with(a.pos_lit())[0].to_vec();
+        long.sort();
+        let mut short = solver.clauses_with(a.neg_lit())[0].to_vec();
+        short.sort();
+        assert_eq!(short, vec![a.neg_lit(), b.pos_lit()]);
+        (solver.n_clauses(), long)
+    };
+
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| Var::from_index(i).pos_lit());
+    assert_eq!(clauses_of(false), (3, vec![a, b, c, d]));
+    assert_eq!(clauses_of(true), (2, vec![a, b, d]));
+}
+
+
+#[test]
+fn contradictory_assumptions_fail_up_front() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf20-01.cnf.gz", &mut solver, false).expect("IO Error");
+    let [a, b] = [0, 1].map(|i| Var::from_index(i));
+    match solver.solve_limited_assumps(&Budget::new(), &[a.pos_lit(), b.neg_lit(), a.neg_lit()]) {
+        minisat::AssumpsRes::Failed(failed, stats) => {
+            assert_eq!(failed, vec![a.pos_lit(), a.neg_lit()]);
+            assert_eq!(stats.decisions, 0);
+        }
+        _ => panic!("Expected failed assumptions"),
+    }
+
+    let mut solver = minisat::SimpSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf20-01.cnf.gz", &mut solver, false).expect("IO Error");
+    solver.set_assumption_limit(Some(2));
+    let solver = match solver.solve_limited_assumps(&Budget::new(), &[a.pos_lit(), b.neg_lit(), a.neg_lit()]) {
+        minisat::AssumpsRes::Invalid(AssumptionError::TooMany(3), solver) => solver,
+        _ => panic!("Expected too many assumptions"),
+    };
+    // 'solve_limited' reports them the same way:
+    let solver = match solver.solve_limited(&Budget::new(), &[a.pos_lit(), b.neg_lit(), a.neg_lit()]) {
+        SolveRes::Invalid(AssumptionError::TooMany(3), solver) => solver,
+        _ => panic!("Expected too many assumptions"),
+    };
+    match solver.solve_limited(&Budget::new(), &[b.neg_lit(), b.pos_lit()]) {
+        SolveRes::UnSAT(_) => {}
+        _ => panic!("Expected UNSAT"),
+    }
+}
+
+
+#[test]
+fn assumptions_over_eliminated_vars() {
+    for &reversible_elim in [false, true].iter() {
+        let settings = minisat::SimpSettings { persist_simp: true, reversible_elim, ..Default::default() };
+        let mut solver = minisat::SimpSolver::new(settings);
+        // 'x' only links 'a' and 'b', so it is eliminated:
+        let [x, a, b] = [0, 1, 2].map(|i| Var::from_index(i));
+        solver.add_clause(&[x.pos_lit(), a.pos_lit()]);
+        solver.add_clause(&[x.neg_lit(), b.pos_lit()]);
+        assert!(solver.preprocess(&Budget::new()));
+        assert!(solver.is_eliminated(x));
+
+        match solver.solve_limited_assumps(&Budget::new(), &[x.pos_lit(), b.neg_lit()]) {
+            minisat::AssumpsRes::Invalid(err, _) => {
+                assert!(!reversible_elim);
+                assert_eq!(err, AssumptionError::Eliminated(x));
+            }
+            minisat::AssumpsRes::Failed(failed, _) => {
+                assert!(reversible_elim);
+                assert!(failed.contains(&x.pos_lit()) && failed.contains(&b.neg_lit()));
+            }
+            _ => panic!("Unexpected result"),
+        }
+    }
+
+    // With default settings the simplificator is gone after 'preprocess', and elimination is not
+    // reversible, but the variable is still known to be eliminated:
+    let mut solver = minisat::SimpSolver::new(minisat::SimpSettings::default());
+    let [x, a, b] = [0, 1, 2].map(|i| Var::from_index(i));
+    solver.add_clause(&[x.pos_lit(), a.pos_lit()]);
+    solver.add_clause(&[x.neg_lit(), b.pos_lit()]);
+    assert!(solver.preprocess(&Budget::new()));
+    assert!(solver.is_eliminated(x));
+    let solver = match solver.solve_limited(&Budget::new(), &[x.pos_lit(), b.neg_lit()]) {
+        SolveRes::Invalid(err, solver) => {
+            assert_eq!(err, AssumptionError::Eliminated(x));
+            solver
+        }
+        _ => panic!("Expected the assumptions to be rejected"),
+    };
+    assert!(solver.is_eliminated(x));
+    solve_sat(solver);
+}
+
+
+#[test]
+fn reset_keeps_heuristics_for_related_instance() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf250-015.cnf.gz", &mut solver, false).expect("IO Error");
+    let n_vars = solver.n_vars();
+    let clauses = solver.clone().into_parts().clauses;
+
+    // Solve in slices to keep the solver from just before the last one:
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(100);
+    budget.set_per_call(true);
+    let (model, mut solved) = loop {
+        let before = solver.clone();
+        solver = match solver.solve_limited(&budget, &[]) {
+            SolveRes::Interrupted(_, s) => s,
+            SolveRes::SAT(model, _) => break (model, before),
+            SolveRes::UnSAT(_) | SolveRes::Invalid(_, _) => panic!("Expected SAT"),
+        };
+    };
+
+    // Near-identical instance, still satisfied by 'model':
+    let extra = [!model[0], model[1]];
+    let conflicts = |mut solver: minisat::CoreSolver| {
+        for c in clauses.iter() {
+            solver.add_clause(c);
+        }
+        solver.add_clause(&extra);
+        match solver.solve() {
+            SolveRes::SAT(_, stats) => stats.conflicts,
+            _ => panic!("Expected SAT"),
+        }
+    };
+
+    let cold = conflicts(minisat::CoreSolver::from_clauses(Default::default(), n_vars, &[]));
+    let mut reset = solved.clone();
+    reset.reset();
+    assert_eq!(reset.n_clauses(), 0);
+    assert_eq!(conflicts(reset), cold);
+
+    solved.reset_keep_heuristics();
+    let warm = conflicts(solved);
+    assert!(warm < cold, "warm start took {} conflicts, cold one {}", warm, cold);
+}
+
+
+#[test]
+fn longest_problem_clause() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let lits: Vec<Lit> = (0..10).map(|_| solver.new_var(None, true).pos_lit()).collect();
+    solver.add_clause(&lits[0..2]);
+    solver.add_clause(&lits[2..7]);
+    solver.add_clause(&lits[7..10]);
+    assert_eq!(solver.max_clause_len(), 5);
+    assert_eq!(solver.stats().max_clause_len, 5);
+}
+
+
+#[test]
+fn shuffled_literals_keep_verdict() {
+    for &(path, expected_sat) in [("./tests/cnf/uf50-0100.cnf.gz", true), ("./tests/cnf/uuf50-0227.cnf.gz", false)].iter() {
+        let solve = |seed: Option<u64>| {
+            let mut settings = minisat::CoreSettings::default();
+            settings.core.shuffle_literals = seed;
+            settings.core.check_watches = true;
+            let mut solver = minisat::CoreSolver::new(settings);
+            dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+            match solver.solve() {
+                SolveRes::SAT(_, stats) => (true, stats.propagations),
+                SolveRes::UnSAT(stats) => (false, stats.propagations),
+                SolveRes::Interrupted(_, _) | SolveRes::Invalid(_, _) => panic!("Unexpected result"),
+            }
+        };
+
+        let (sat1, props1) = solve(Some(1));
+        let (sat2, props2) = solve(Some(2));
+        assert_eq!((sat1, sat2), (expected_sat, expected_sat), "{}", path);
+        assert_ne!(props1, props2, "{}", path);
+        assert_eq!(solve(Some(1)), (sat1, props1));
+    }
+}
+
+
+#[test]
+fn solve_under_reuses_assumption_prefix() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    // Each of the assumptions propagates a chain of 20 literals:
+    let mut prefix = Vec::new();
+    let mut chain_ends = Vec::new();
+    for _ in 0..50 {
+        let a = solver.new_var(None, true).pos_lit();
+        let mut prev = a;
+        for _ in 0..20 {
+            let next = solver.new_var(None, true).pos_lit();
+            solver.add_clause(&[!prev, next]);
+            prev = next;
+        }
+        prefix.push(a);
+        chain_ends.push(prev);
+    }
+    let x = solver.new_var(None, true).pos_lit();
+    let y = solver.new_var(None, true).pos_lit();
+    solver.add_clause(&[x, y]);
+
+    let budget = Budget::new();
+    let solve = |solver: &mut minisat::CoreSolver, last: &[Lit]| {
+        let mut assumptions = prefix.clone();
+        assumptions.extend_from_slice(last);
+        let before = solver.stats().propagations;
+        let res = solver.solve_under(&budget, &assumptions);
+        (res, solver.stats().propagations - before)
+    };
+
+    let (res, first) = solve(&mut solver, &[x]);
+    assert!(matches!(res, minisat::StepRes::SAT(ref model) if model.contains(&x) && chain_ends.iter().all(|c| model.contains(c))));
+    let (res, second) = solve(&mut solver, &[!x]);
+    assert!(matches!(res, minisat::StepRes::SAT(ref model) if model.contains(&y)));
+    assert!(second * 10 < first, "{} propagations after {}", second, first);
+
+    // A failed assumption keeps the prefix before it, clauses added in between drop it all:
+    let (res, _) = solve(&mut solver, &[!chain_ends[0]]);
+    assert!(matches!(res, minisat::StepRes::AssumpsConfl(ref failed) if failed.contains(&prefix[0]) && failed.contains(&!chain_ends[0])));
+    solver.add_clause(&[!x]);
+    let (res, third) = solve(&mut solver, &[]);
+    assert!(matches!(res, minisat::StepRes::SAT(ref model) if model.contains(&!x) && model.contains(&y)));
+    assert!(third > second * 10, "{} propagations after {}", third, second);
+    let (res, _) = solve(&mut solver, &[!y]);
+    assert!(matches!(res, minisat::StepRes::AssumpsConfl(_)));
+}
+
+
+#[test]
+fn clause_deletion_hook() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf250-015.cnf.gz", &mut solver, false).expect("IO Error");
+    let deleted = Arc::new(AtomicUsize::new(0));
+    {
+        let deleted = deleted.clone();
+        solver.on_clause_deleted(Some(Box::new(move |lits: &[Lit]| {
+            assert!(lits.len() >= 2);
+            deleted.fetch_add(1, Ordering::Relaxed);
+        })));
+    }
+
+    let mut budget = Budget::new();
+    budget.set_conflict_budget(3000);
+    let mut solver = match solver.solve_limited(&budget, &[]) {
+        SolveRes::Interrupted(_, solver) => solver,
+        _ => panic!("Expected interruption"),
+    };
+
+    // Learnt clauses were dropped by 'reduce', far more of them than there are problem clauses:
+    let after_search = deleted.load(Ordering::Relaxed);
+    assert!(after_search > solver.n_clauses(), "{}", after_search);
+
+    // Garbage collection only moves clauses:
+    solver.collect_garbage();
+    assert_eq!(deleted.load(Ordering::Relaxed), after_search);
+
+    // Copies don't report to the hook of the original:
+    let copy = solver.clone();
+    match copy.solve_limited(&budget, &[]) {
+        SolveRes::Interrupted(..) | SolveRes::SAT(..) => {}
+        SolveRes::UnSAT(_) | SolveRes::Invalid(..) => panic!("Expected SAT"),
+    }
+    assert_eq!(deleted.load(Ordering::Relaxed), after_search);
+}
+
+
+#[test]
+fn packed_gc_keeps_clauses() {
+    let run = |packed_gc: bool| {
+        let mut settings = minisat::CoreSettings::default();
+        settings.core.packed_gc = packed_gc;
+        settings.core.garbage_frac = 0.05;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file("./tests/cnf/uf250-015.cnf.gz", &mut solver, false).expect("IO Error");
+        match solver.solve_limited(&Budget::new().conflicts(3000), &[]) {
+            SolveRes::Interrupted(_, solver) => solver,
+            _ => panic!("Expected interruption"),
+        }
+    };
+
+    // Clauses only move, so the search goes exactly the same way:
+    let plain = run(false);
+    let mut packed = run(true);
+    assert!(packed.stats().gc_runs > 0);
+    assert_eq!(packed.stats().propagations, plain.stats().propagations);
+
+    let before = packed.clone().into_parts();
+    packed.collect_garbage();
+    let after = packed.into_parts();
+    assert_eq!(after.clauses, before.clauses);
+    assert_eq!(after.learnts, before.learnts);
+    assert_eq!(after.learnts, plain.into_parts().learnts);
+}
+
+
+#[test]
+fn set_garbage_frac_at_runtime() {
+    let resume = |solver: minisat::CoreSolver| {
+        match solver.solve_limited(&Budget::new().conflicts(3000).per_call(), &[]) {
+            SolveRes::Interrupted(_, solver) => solver,
+            _ => panic!("Expected interruption"),
+        }
+    };
+
+    let mut settings = minisat::CoreSettings::default();
+    settings.core.garbage_frac = 0.9;
+    settings.db.core_lbd_cutoff = 0;
+    let mut solver = minisat::CoreSolver::new(settings);
+    dimacs::parse_file("./tests/cnf/uf250-015.cnf.gz", &mut solver, false).expect("IO Error");
+    let solver = resume(solver);
+
+    // Collection is only checked for after 'reduce', which rarely wastes 0.9 of clause memory:
+    let mut lowered = solver.clone();
+    lowered.set_garbage_frac(0.2).expect("Valid fraction");
+    assert_eq!(lowered.garbage_frac(), 0.2);
+    let kept = resume(solver);
+    let mut lowered = resume(lowered);
+    assert!(lowered.stats().gc_runs > kept.stats().gc_runs + 2, "{} vs {}", lowered.stats().gc_runs, kept.stats().gc_runs);
+
+    let (size, wasted) = lowered.allocator_stats();
+    assert!(wasted < size);
+    lowered.collect_garbage();
+    assert_eq!(lowered.allocator_stats(), (size - wasted, 0));
+
+    assert!(lowered.set_garbage_frac(0.0).is_err());
+    assert!(lowered.set_garbage_frac(1.5).is_err());
+    assert_eq!(lowered.garbage_frac(), 0.2);
+}
+
+
+#[test]
+fn deterministic_conflicts() {
+    // Recorded with fixed-point activities and integer random numbers, so it must not change from
+    // one platform to another (only with changes to the search itself):
+    const GOLDEN: u64 = 1088;
+
+    let mut conflicts = 0;
+    for i in 1..=20 {
+        let mut settings = minisat::CoreSettings::default();
+        settings.heur.deterministic = true;
+        settings.heur.rnd_init_act = true;
+        settings.heur.random_var_freq = 0.02;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file(format!("./tests/cnf/uuf50-0{}.cnf.gz", i), &mut solver, false).expect("IO Error");
+
+        match solver.solve_limited(&Budget::new(), &[]) {
+            SolveRes::UnSAT(stats) => conflicts += stats.conflicts,
+            _ => panic!("Expected UNSAT"),
+        }
+    }
+    assert_eq!(conflicts, GOLDEN);
+}
+
+
+#[test]
+fn backbone_literals() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let [a, b, c, d, e] = [(); 5].map(|_| solver.new_var(None, true).pos_lit());
+    for clause in [&[a, b][..], &[a, !b], &[c, d], &[!c, !e]].iter() {
+        solver.add_clause(clause);
+    }
+    assert_eq!(solver.backbone(&Budget::new()), vec![a]);
+
+    // Every literal of the backbone is refuted, every other one has a model, and an interrupted
+    // run finds only a part of it:
+    let path = "./tests/cnf/uf20-01.cnf.gz";
+    let solver_for = || {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        solver
+    };
+    let backbone = solver_for().backbone(&Budget::new());
+    assert!(!backbone.is_empty());
+    for i in 0..solver_for().n_vars() {
+        for &lit in [Var::from_index(i).pos_lit(), Var::from_index(i).neg_lit()].iter() {
+            let sat = matches!(solver_for().solve_limited(&Budget::new(), &[!lit]), SolveRes::SAT(_, _));
+            assert_eq!(sat, !backbone.contains(&lit), "{:?}", lit);
+        }
+    }
+
+    let mut solver = solver_for();
+    let partial = solver.backbone(&Budget::new().propagations(150));
+    assert!(!partial.is_empty() && partial.len() < backbone.len());
+    assert!(partial.iter().all(|lit| backbone.contains(lit)));
+}
+
+
+#[test]
+fn write_compact_preprocessed_formula() {
+    let dir = tempfile::tempdir().expect("IO Error");
+    let write = |name: &str, compact: bool| {
+        let path = dir.path().join(name);
+        let options = minisat_rust::MainOptions {
+            strict: true,
+            header_vars: dimacs::HeaderMismatch::Error,
+            pre: true,
+            solve: false,
+            analyze: false,
+            in_path: "./tests/cnf/2bitadd_11.cnf.gz".into(),
+            out_path: None,
+            dimacs_path: Some(path.clone()),
+            compact,
+            trace_path: None,
+            replay_path: None,
+        };
+        minisat_rust::solve(options, minisat_rust::SolverOptions::Simp(Default::default())).expect("IO Error");
+
+        // Declared number of variables and clauses of literal ids:
+        let text = std::fs::read_to_string(&path).expect("IO Error");
+        let header: Vec<usize> = text.lines().next().unwrap().split_whitespace().skip(2).map(|x| x.parse().unwrap()).collect();
+        let clauses: Vec<Vec<i32>> = text.lines().skip(1)
+            .map(|line| line.split_whitespace().map(|x| x.parse().unwrap()).take_while(|&id| id != 0).collect())
+            .collect();
+        assert_eq!(clauses.len(), header[1]);
+        (path, header[0], clauses)
+    };
+
+    let (_, sparse_vars, sparse) = write("sparse.cnf", false);
+    let (path, k, compact) = write("compact.cnf", true);
+    assert!(k < sparse_vars);
+    let mut ids: Vec<i32> = compact.iter().flat_map(|c| c.iter()).map(|id| id.abs()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids, (1..=k as i32).collect::<Vec<_>>());
+
+    let map = std::fs::read_to_string(format!("{}.map", path.display())).expect("IO Error");
+    let original: Vec<i32> = map.lines().enumerate().map(|(i, line)| {
+        let pair: Vec<i32> = line.split_whitespace().map(|x| x.parse().unwrap()).collect();
+        assert_eq!(pair[0], i as i32 + 1);
+        pair[1]
+    }).collect();
+    assert_eq!(original.len(), k);
+    assert!(original.windows(2).all(|w| w[0] < w[1]));
+
+    let renamed: Vec<Vec<i32>> = compact.iter()
+        .map(|c| c.iter().map(|&id| original[id.abs() as usize - 1] * id.signum()).collect())
+        .collect();
+    assert_eq!(renamed, sparse);
+}
+
+
+#[test]
+fn ground_assignments() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let [a, b, c, d] = [(); 4].map(|_| solver.new_var(None, true).pos_lit());
+    solver.add_clause(&[a]);
+    solver.add_clause(&[!b]);
+    solver.add_clause(&[c, d]);
+    assert_eq!(solver.vars().collect::<Vec<_>>(), [a, b, c, d].iter().map(|l| l.var()).collect::<Vec<_>>());
+    assert_eq!(solver.assignments().collect::<Vec<_>>(),
+               vec![(a.var(), LBool::True), (b.var(), LBool::False), (c.var(), LBool::Undef), (d.var(), LBool::Undef)]);
+
+    // Assumptions kept by 'solve_under' are not fixed by the formula:
+    assert!(matches!(solver.solve_under(&Budget::new(), &[!c]), minisat::StepRes::SAT(_)));
+    assert!(solver.assignments().all(|(v, value)| (value == LBool::Undef) == (v == c.var() || v == d.var())));
+
+    // An interrupted solver reports the units it found so far:
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf250-015.cnf.gz", &mut solver, false).expect("IO Error");
+    match solver.solve_limited(&Budget::new().conflicts(1000), &[]) {
+        SolveRes::Interrupted(_, solver) => {
+            let fixed: Vec<Lit> = solver.assignments()
+                .filter(|&(_, value)| value != LBool::Undef)
+                .map(|(v, value)| v.sign_lit(value == LBool::False))
+                .collect();
+            let mut units = solver.root_units();
+            units.sort();
+            assert_eq!(fixed, units);
+            assert_eq!(solver.assignments().count(), solver.n_vars());
+        }
+        _ => panic!("Expected interruption"),
+    }
+}
+
+
+#[test]
+fn unlock_before_reduce_shrinks_learnts() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // A tight cap on learnt clauses makes 'reduce' run on deep trails of a planning instance, where
+    // a good part of learnt clauses are reasons:
+    let run = |unlock: bool, conflicts: u64| {
+        let mut settings = minisat::CoreSettings::default();
+        settings.core.unlock_before_reduce = unlock;
+        settings.search.learn.max_learnts_abs = Some(100);
+        settings.db.core_lbd_cutoff = 0;
+        let mut solver = minisat::CoreSolver::new(settings);
+        dimacs::parse_file("./tests/cnf/4blocks.cnf.gz", &mut solver, false).expect("IO Error");
+        let deleted = Arc::new(AtomicUsize::new(0));
+        {
+            let deleted = deleted.clone();
+            solver.on_clause_deleted(Some(Box::new(move |_: &[Lit]| { deleted.fetch_add(1, Ordering::Relaxed); })));
+        }
+
+        match solver.solve_limited(&Budget::new().conflicts(conflicts), &[]) {
+            SolveRes::Interrupted(_, solver) => (solver.stats(), deleted.load(Ordering::Relaxed)),
+            _ => panic!("Expected interruption"),
+        }
+    };
+
+    // Both runs are the same up to the conflict right after the first restart to unlock clauses:
+    let (mut lo, mut hi) = (0, 400);
+    assert_eq!(run(true, hi).0.unlock_restarts, 1);
+    while hi - lo > 1 {
+        let mid = (lo + hi) / 2;
+        if run(true, mid).0.unlock_restarts > 0 { hi = mid; } else { lo = mid; }
+    }
+
+    let (stats, unlocked) = run(true, hi);
+    let (plain_stats, kept) = run(false, hi);
+    assert_eq!(plain_stats.unlock_restarts, 0);
+    assert!(stats.max_locked * 4 > 100);
+    assert_eq!(stats.max_locked, plain_stats.max_locked);
+    assert!(unlocked > kept, "{} deleted, {} without unlocking", unlocked, kept);
+}
+
+
+#[test]
+fn hyper_binary_resolution_gives_unit() {
+    let preprocess = |use_hbr: bool| {
+        let mut settings = minisat::SimpSettings::default();
+        settings.simp.use_elim = false;
+        settings.simp.use_hbr = use_hbr;
+        let mut solver = minisat::SimpSolver::new(settings);
+        let vars: Vec<Var> = (0..6).map(|_| solver.new_var(None, true)).collect();
+        let (a, b, c, d, e, f) = (vars[0], vars[1], vars[2], vars[3], vars[4], vars[5]);
+        assert!(solver.add_clause(&[a.neg_lit(), b.pos_lit()]));
+        assert!(solver.add_clause(&[a.neg_lit(), d.pos_lit()]));
+        assert!(solver.add_clause(&[b.neg_lit(), d.neg_lit(), c.pos_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), e.pos_lit()]));
+        assert!(solver.add_clause(&[a.pos_lit(), f.pos_lit()]));
+        assert!(solver.add_clause(&[e.neg_lit(), f.neg_lit(), c.pos_lit()]));
+        assert!(solver.preprocess(&Budget::new()));
+        (solver.root_units(), c)
+    };
+
+    // Either value of 'a' implies 'c', but '-c' alone propagates nothing, so it is no failed
+    // literal. Probing 'a' and '-a' adds binaries '-a | c' and 'a | c', after which it is:
+    let (units, _) = preprocess(false);
+    assert!(units.is_empty());
+    let (units, c) = preprocess(true);
+    assert_eq!(units, vec![c.pos_lit()]);
+
+    for path in ["./tests/cnf/uf20-01.cnf.gz", "./tests/cnf/uuf50-01.cnf.gz"].iter() {
+        let mut settings = minisat::SimpSettings::default();
+        settings.simp.use_hbr = true;
+        let mut solver = minisat::SimpSolver::new(settings);
+        dimacs::parse_file(path, &mut solver, false).expect("IO Error");
+        let sat = path.contains("/uf");
+        match solver.solve() {
+            SolveRes::SAT(..) => assert!(sat),
+            SolveRes::UnSAT(_) => assert!(!sat),
+            SolveRes::Interrupted(..) | SolveRes::Invalid(..) => panic!("Interrupted"),
+        }
+    }
+}
+
+
+#[test]
+fn checkpoint_and_restore_decisions() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let vars: Vec<Var> = (0..5).map(|_| solver.new_var(None, true)).collect();
+    let (a, b, c, d, e) = (vars[0].pos_lit(), vars[1].pos_lit(), vars[2].pos_lit(), vars[3].pos_lit(), vars[4].pos_lit());
+    assert!(solver.add_clause(&[!a, b]));
+    assert!(solver.add_clause(&[!c, d]));
+    assert!(solver.add_clause(&[!c, !b, e]));
+    assert!(solver.add_clause(&[!d, !e]));
+
+    let ground = solver.checkpoint();
+    assert_eq!(solver.decide(a), Ok(None));
+    let after_a = solver.checkpoint();
+    let trail_a = solver.assignment().trail().to_vec();
+    assert_eq!(trail_a, vec![a, b]);
+
+    // 'c' implies both 'd' and 'e', which the last clause forbids:
+    let confl = solver.decide(c).expect("Expected a decision").expect("Expected a conflict");
+    assert_eq!(solver.assignment().trail().len(), 5);
+    let confl = solver.clause(confl).expect("Expected a stored clause").lits().to_vec();
+    match solver.analyze_conflict(&confl) {
+        Some(minisat::Conflict::Learned(level, lit, _)) => {
+            assert_eq!(level, after_a.level());
+            assert_eq!(lit, !c);
+        }
+        _ => panic!("Expected a learnt clause"),
+    }
+
+    assert_eq!(solver.restore(after_a), Ok(()));
+    assert_eq!(solver.assignment().trail(), &trail_a[..]);
+    assert_eq!(solver.checkpoint(), after_a);
+    assert_eq!(solver.decide(!c), Ok(None));
+    let after_not_c = solver.checkpoint();
+
+    // Assigned or unknown literals are not decided, and nothing changes:
+    let trail = solver.assignment().trail().to_vec();
+    assert_eq!(solver.decide(b), Err(minisat::DecideError::Assigned(b)));
+    assert_eq!(solver.decide(!a), Err(minisat::DecideError::Assigned(!a)));
+    let unknown = Var::from_index(5);
+    assert_eq!(solver.decide(unknown.pos_lit()), Err(minisat::DecideError::UnknownVar(unknown)));
+    assert_eq!(solver.assignment().trail(), &trail[..]);
+    assert_eq!(solver.checkpoint(), after_not_c);
+
+    assert_eq!(solver.restore(ground), Ok(()));
+    assert!(solver.assignment().trail().is_empty());
+    assert!(solver.assignment().is_ground_level());
+
+    // Checkpoints above the current level, or of levels undone since, can't be returned to, even
+    // if the level is decided again with as long a trail, or the same way:
+    assert_eq!(solver.restore(after_a), Err(CheckpointError::AboveLevel(after_a.level())));
+    assert_eq!(solver.decide(c), Ok(None));
+    let trail = solver.assignment().trail().to_vec();
+    assert!(trail.len() >= trail_a.len());
+    assert_eq!(solver.restore(after_a), Err(CheckpointError::Undone));
+    assert_eq!(solver.assignment().trail(), &trail[..]);
+    assert_eq!(solver.restore(ground), Ok(()));
+    assert_eq!(solver.decide(a), Ok(None));
+    assert_eq!(solver.restore(after_a), Err(CheckpointError::Undone));
+    let again_a = solver.checkpoint();
+    assert_eq!(solver.restore(again_a), Ok(()));
+    assert_eq!(solver.restore(ground), Ok(()));
+    solve_sat(solver);
+}
+
+
+#[test]
+fn analyze_injected_lemma() {
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let [a, b, c, d, e] = [(); 5].map(|_| solver.new_var(None, true).pos_lit());
+    for clause in [[!a, c], [!b, d], [!d, e]].iter() {
+        assert!(solver.add_clause(clause));
+    }
+
+    // Level 1: 'a' implies 'c'. Level 2: 'b' implies 'd' and 'e':
+    let ground = solver.checkpoint();
+    assert_eq!(solver.decide(a), Ok(None));
+    let after_a = solver.checkpoint();
+    assert_eq!(solver.decide(b), Ok(None));
+    let level = solver.checkpoint().level();
+
+    // A lemma from outside, false under the assignment, resolves to the first UIP 'd':
+    match solver.analyze_conflict(&[!c, !d, !e]) {
+        Some(minisat::Conflict::Learned(bt_level, lit, clause)) => {
+            let assigns = solver.assignment();
+            assert_eq!(lit, !d);
+            assert_eq!(clause, vec![!d, !c]);
+            assert!(clause.iter().all(|&l| assigns.is_assigned_neg(l)));
+            assert_eq!(assigns.vardata(lit).level, level);
+            assert_eq!(bt_level, after_a.level());
+        }
+        _ => panic!("Expected a learnt clause"),
+    }
+    assert_eq!(solver.checkpoint().level(), level);
+
+    // A unit lemma is asserted at ground level, anything else is not a conflict:
+    match solver.analyze_conflict(&[!e]) {
+        Some(minisat::Conflict::Unit(bt_level, lit)) => assert_eq!((bt_level, lit), (ground.level(), !e)),
+        _ => panic!("Expected a unit"),
+    }
+    assert!(solver.analyze_conflict(&[!c, e]).is_none());
+    assert!(solver.analyze_conflict(&[!a, !c]).is_none());
+    assert!(solver.analyze_conflict(&[]).is_none());
+    assert!(solver.analyze_conflict(&[Var::from_index(10).pos_lit()]).is_none());
+}
+
+
+#[test]
+fn solve_minimal_model() {
+    // Every clause has 'x1' and one of the others, so 'x1' alone covers them all, while without
+    // it all four others are needed:
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    let x: Vec<Lit> = (0..5).map(|_| solver.new_var(Some(false), true).pos_lit()).collect();
+    for clause in [[x[0], x[1]], [x[1], x[2]], [x[1], x[3]], [x[1], x[4]]].iter() {
+        assert!(solver.add_clause(clause));
+    }
+
+    match solver.solve_minimal(&Budget::new(), &x) {
+        minisat::MinimalRes::SAT(model, _) => {
+            let true_lits: Vec<Lit> = x.iter().cloned().filter(|lit| model.contains(lit)).collect();
+            assert_eq!(true_lits, vec![x[1]]);
+        }
+        _ => panic!("Expected SAT"),
+    }
+
+    // Minimal models of a random instance, checked against exhaustive search for the count:
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf20-01.cnf.gz", &mut solver, false).expect("IO Error");
+    let clauses = solver.clauses();
+    let objective: Vec<Lit> = (0..solver.n_vars()).map(|i| Var::from_index(i).pos_lit()).collect();
+    let found = match solver.solve_minimal(&Budget::new(), &objective) {
+        minisat::MinimalRes::SAT(model, _) => {
+            assert!(clauses.iter().all(|c| c.iter().any(|lit| model.contains(lit))));
+            objective.iter().filter(|lit| model.contains(lit)).count()
+        }
+        _ => panic!("Expected SAT"),
+    };
+    let bit = |lit: &Lit| 1u32 << objective.iter().position(|o| o.var() == lit.var()).unwrap();
+    let masks: Vec<(u32, u32)> = clauses.iter()
+        .map(|c| (c.iter().filter(|lit| !lit.sign()).map(bit).sum(), c.iter().filter(|lit| lit.sign()).map(bit).sum()))
+        .collect();
+    let exhaustive = (0u32..1 << objective.len())
+        .filter(|bits| masks.iter().all(|&(pos, neg)| bits & pos != 0 || !bits & neg != 0))
+        .map(|bits| bits.count_ones() as usize)
+        .min();
+    assert_eq!(Some(found), exhaustive);
+
+    // Interrupted with a model at hand, the best one so far (minimal or not, it isn't proven yet) is
+    // returned along with the solver, where the bound is retired:
+    let mut improving = 0;
+    for conflicts in 0..100 {
+        let mut solver = minisat::CoreSolver::new(Default::default());
+        dimacs::parse_file("./tests/cnf/uf20-01.cnf.gz", &mut solver, false).expect("IO Error");
+        match solver.solve_minimal(&Budget::new().conflicts(conflicts), &objective) {
+            minisat::MinimalRes::Improving(model, _, solver) => {
+                assert!(clauses.iter().all(|c| c.iter().any(|lit| model.contains(lit))));
+                assert!(objective.iter().filter(|lit| model.contains(lit)).count() >= found);
+                improving += 1;
+                solve_sat(solver);
+            }
+            minisat::MinimalRes::Interrupted(_, solver) => {
+                solve_sat(solver);
+            }
+            minisat::MinimalRes::SAT(model, _) => assert_eq!(objective.iter().filter(|lit| model.contains(lit)).count(), found),
+            minisat::MinimalRes::UnSAT(_) => panic!("Expected SAT"),
+        }
+    }
+    assert!(improving > 0);
+}
+
+
+#[test]
+fn solvers_are_send() {
+    // Hooks and traces are 'Send' too, so a solver with any of them can move to another thread:
+    fn assert_send<T: Send>() {}
+    assert_send::<minisat::CoreSolver>();
+    assert_send::<minisat::SimpSolver>();
+
+    let mut solver = minisat::CoreSolver::new(Default::default());
+    dimacs::parse_file("./tests/cnf/uf20-01.cnf.gz", &mut solver, false).expect("IO Error");
+    solver.on_clause_deleted(Some(Box::new(|_: &[Lit]| {})));
+    solver.record_trace(Vec::new());
+    std::thread::spawn(move || solve_sat(solver)).join().expect("Solver thread panicked");
+}